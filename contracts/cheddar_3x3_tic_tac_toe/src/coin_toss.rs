@@ -0,0 +1,83 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Reveals the secret committed in `make_available`. The game starts once both secrets
+    /// are revealed: bit 0 of their XOR swaps the players, bit 1 gives the first player O.
+    pub fn reveal_secret(&mut self, game_id: &GameId, secret: U128) {
+        let cur_timestamp = env::block_timestamp();
+        let mut game = self.internal_get_game(game_id);
+        assert_eq!(game.game_state, GameState::NotStarted, "The game has already started");
+        let account_id = env::predecessor_account_id();
+        let player = game.player_index(&account_id);
+        let coin_toss = game.coin_toss.as_mut().expect("The game doesn't draw the first player");
+        assert!(cur_timestamp <= coin_toss.reveal_deadline, "The reveal deadline has passed");
+        coin_toss.reveal(player, &account_id, secret.0);
+
+        if let Some(result) = coin_toss.result() {
+            let first_piece = match result & 2 {
+                0 => Piece::X,
+                _ => Piece::O,
+            };
+            game.start_after_coin_toss(result & 1 == 1, first_piece, cur_timestamp);
+            log!("Game {} started, @{} moves first with {:?}", game_id, game.current_player_account_id(), first_piece);
+        }
+        self.internal_update_game(game_id, &game);
+    }
+}
+
+impl Contract {
+    /// Holds the game back until both players reveal the secrets behind `commitments`
+    pub(crate) fn internal_start_coin_toss(&mut self, game_id: &GameId, commitments: [Base58CryptoHash; 2]) {
+        let mut game = self.internal_get_game(game_id);
+        let reveal_deadline = env::block_timestamp() + REVEAL_TIMEOUT;
+        game.coin_toss = Some(CoinToss::new(commitments, reveal_deadline));
+        game.change_state(GameState::NotStarted);
+        self.internal_update_game(game_id, &game);
+    }
+
+    /// Ends a game whose secrets weren't both revealed in time: the player who didn't
+    /// reveal forfeits. If neither did, the game never started: both deposits are refunded
+    /// in full without rating the game.
+    pub(crate) fn internal_expire_coin_toss(&mut self, game_id: &GameId) {
+        let mut game = self.internal_get_game(game_id);
+        let coin_toss = game.coin_toss.clone().expect("The game doesn't draw the first player");
+        // the game ends like an active one
+        game.change_state(GameState::Active);
+        self.internal_update_game(game_id, &game);
+
+        if let Some(player) = coin_toss.defaulter() {
+            let defaulter = game.players[player].account_id.clone();
+            log!("@{} didn't reveal the secret in time", defaulter);
+            self.internal_stop_expired_game(game_id, defaulter);
+            return;
+        }
+
+        log!("Neither player revealed the secret in time");
+        let reward = game.reward();
+        let refund_amount = reward.balance.0 / PLAYERS_NUM as u128;
+        self.internal_tie_refund(game_id, &reward.token_id, refund_amount);
+        let balance = U128(refund_amount);
+        game.change_state(GameState::Finished);
+        self.internal_update_game(game_id, &game);
+
+        let (player1, player2) = game.get_player_accounts();
+        let game_to_store = GameLimitedView{
+            game_result: GameResult::Tie,
+            reason: ResultReason::Timeout,
+            player1,
+            player2,
+            reward_or_tie_refund: GameDeposit {
+                token_id: game.reward().token_id,
+                balance
+            },
+            board: game.board.tiles,
+            sub_boards: game.sub_board_tiles(),
+            cube_layers: game.cube_layers(),
+            moves: game.moves.clone(),
+        };
+
+        self.internal_store_game(game_id, game_to_store);
+        self.internal_stop_game(game_id);
+    }
+}
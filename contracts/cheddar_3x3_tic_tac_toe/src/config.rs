@@ -49,4 +49,39 @@ pub (crate) fn validate_game_duration(duration_sec: u32) {
         "max game duration must be less then 1 hour in seconds ({})",
         MAX_GAME_DURATION_SEC
     )
-}
\ No newline at end of file
+}
+pub (crate) fn validate_rating_bounds(min_rating: Option<u32>, max_rating: Option<u32>) {
+    if let (Some(min_rating), Some(max_rating)) = (min_rating, max_rating) {
+        assert!(
+            min_rating <= max_rating,
+            "min_rating {} is above max_rating {}",
+            min_rating,
+            max_rating
+        );
+    }
+}
+pub (crate) fn validate_season(fee_share: u32, prizes: &[u32]) {
+    assert!(fee_share <= BASIS_P, "fee share must be in range 0..10000");
+    assert!(
+        !prizes.is_empty() && prizes.len() <= MAX_SEASON_PRIZES,
+        "season must have 1 to {} prizes",
+        MAX_SEASON_PRIZES
+    );
+    assert!(
+        prizes.iter().sum::<u32>() <= BASIS_P,
+        "prizes must add up to at most 10000"
+    );
+}
+pub (crate) fn validate_time_control(time_control: &TimeControl) {
+    assert!(
+        (MIN_CLOCK_TIME_SEC..=MAX_GAME_DURATION_SEC as u64).contains(&time_control.initial),
+        "initial clock time must be between {} and {}sec",
+        MIN_CLOCK_TIME_SEC,
+        MAX_GAME_DURATION_SEC
+    );
+    assert!(
+        time_control.increment <= MAX_CLOCK_INCREMENT_SEC,
+        "clock increment must be at most {}sec",
+        MAX_CLOCK_INCREMENT_SEC
+    );
+}
@@ -0,0 +1,169 @@
+use crate::*;
+
+/// Side length of the `GameMode::Qubic` cube
+pub(crate) const CUBE_SIZE: usize = 4;
+
+/// One horizontal layer of the cube, rows of tiles
+pub type CubeLayer = [[Option<Piece>; CUBE_SIZE]; CUBE_SIZE];
+
+/// 13 line directions in the cube: 3 axes, 6 plane diagonals and 4 space diagonals.
+/// Opposite directions are checked by walking both ways.
+const CUBE_DIRECTIONS: [(i8, i8, i8); 13] = [
+    (1, 0, 0), (0, 1, 0), (0, 0, 1),
+    (1, 1, 0), (1, -1, 0), (1, 0, 1), (1, 0, -1), (0, 1, 1), (0, 1, -1),
+    (1, 1, 1), (1, 1, -1), (1, -1, 1), (1, -1, -1),
+];
+
+/// Position in the cube: `x` is the column, `y` the row and `z` the layer
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct CubeCoords {
+    pub x: u8,
+    pub y: u8,
+    pub z: u8,
+}
+
+/// 4x4x4 board of `GameMode::Qubic`, four in a row along any of the 76 lines wins
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct CubeBoard {
+    pub(crate) layers: [CubeLayer; CUBE_SIZE],
+    pub(crate) winner: Option<Winner>,
+    /// completing a line loses
+    pub(crate) misere: bool,
+}
+
+impl CubeBoard {
+    pub fn new(misere: bool) -> Self {
+        Self {
+            layers: Default::default(),
+            winner: None,
+            misere,
+        }
+    }
+
+    fn piece(&self, x: i8, y: i8, z: i8) -> Option<Piece> {
+        let size = CUBE_SIZE as i8;
+        if x < 0 || y < 0 || z < 0 || x >= size || y >= size || z >= size {
+            return None;
+        }
+        self.layers[z as usize][y as usize][x as usize]
+    }
+
+    pub fn check_move(&self, coords: &CubeCoords) -> Result<(), MoveError> {
+        if self.winner.is_some() {
+            return Err(MoveError::GameOver);
+        }
+        let size = CUBE_SIZE as u8;
+        if coords.x >= size || coords.y >= size || coords.z >= size {
+            return Err(MoveError::InvalidPosition { row: coords.y, col: coords.x });
+        }
+        match self.layers[coords.z as usize][coords.y as usize][coords.x as usize] {
+            Some(other_piece) => Err(MoveError::TileFilled { other_piece, row: coords.y, col: coords.x }),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns true if the piece on `coords` is part of a full line
+    pub fn is_winning_move(&self, coords: &CubeCoords) -> bool {
+        let piece = match self.layers[coords.z as usize][coords.y as usize][coords.x as usize] {
+            Some(piece) => piece,
+            None => return false,
+        };
+        let (x, y, z) = (coords.x as i8, coords.y as i8, coords.z as i8);
+        CUBE_DIRECTIONS.iter().any(|(dx, dy, dz)| {
+            let mut length = 1;
+            for sign in [1, -1].iter() {
+                let mut step = 1;
+                while self.piece(x + sign * step * dx, y + sign * step * dy, z + sign * step * dz) == Some(piece) {
+                    length += 1;
+                    step += 1;
+                }
+            }
+            length >= CUBE_SIZE
+        })
+    }
+
+    /// Places `piece` on a checked tile and looks for a winner or a tie after the move
+    pub fn make_move(&mut self, coords: &CubeCoords, piece: Piece) {
+        self.layers[coords.z as usize][coords.y as usize][coords.x as usize] = Some(piece);
+        if self.winner.is_some() {
+            return;
+        }
+        if self.is_winning_move(coords) {
+            let winner = if self.misere { piece.other() } else { piece };
+            self.winner = Some(winner.into());
+        } else if self.layers.iter().flatten().flatten().all(Option::is_some) {
+            self.winner = Some(Winner::Tie);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// All distinct winning lines, found by walking every direction from every tile
+    fn lines() -> Vec<Vec<CubeCoords>> {
+        let mut lines = Vec::new();
+        let size = CUBE_SIZE as i8;
+        for z in 0..size {
+            for y in 0..size {
+                for x in 0..size {
+                    for (dx, dy, dz) in CUBE_DIRECTIONS.iter() {
+                        let line: Vec<(i8, i8, i8)> = (0..size)
+                            .map(|step| (x + step * dx, y + step * dy, z + step * dz))
+                            .collect();
+                        if line.iter().all(|(x, y, z)| (0..size).contains(x) && (0..size).contains(y) && (0..size).contains(z)) {
+                            lines.push(
+                                line.iter()
+                                    .map(|(x, y, z)| CubeCoords { x: *x as u8, y: *y as u8, z: *z as u8 })
+                                    .collect()
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        lines
+    }
+
+    #[test]
+    fn every_line_wins() {
+        let lines = lines();
+        assert_eq!(lines.len(), 76);
+        for line in lines.iter() {
+            let mut board = CubeBoard::new(false);
+            for coords in line.iter() {
+                assert_eq!(board.winner, None);
+                board.make_move(coords, Piece::X);
+            }
+            assert_eq!(board.winner, Some(Winner::X), "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn broken_line_does_not_win() {
+        let mut board = CubeBoard::new(false);
+        // space diagonal with the last tile taken by the other piece
+        for i in 0..3 {
+            board.make_move(&CubeCoords { x: i, y: i, z: i }, Piece::X);
+        }
+        board.make_move(&CubeCoords { x: 3, y: 3, z: 3 }, Piece::O);
+        // three in a row along the x axis
+        for x in 0..3 {
+            board.make_move(&CubeCoords { x, y: 0, z: 3 }, Piece::X);
+        }
+        assert_eq!(board.winner, None);
+        assert_eq!(
+            board.check_move(&CubeCoords { x: 3, y: 3, z: 3 }),
+            Err(MoveError::TileFilled { other_piece: Piece::O, row: 3, col: 3 })
+        );
+        assert_eq!(
+            board.check_move(&CubeCoords { x: 0, y: 0, z: 4 }),
+            Err(MoveError::InvalidPosition { row: 0, col: 0 })
+        );
+    }
+}
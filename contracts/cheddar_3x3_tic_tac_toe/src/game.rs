@@ -1,12 +1,6 @@
 use crate::*;
 
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
-#[serde(crate = "near_sdk::serde")]
-pub enum GameState {
-    NotStarted,
-    Active,
-    Finished
-}
+pub use tic_tac_toe_rules::{ChessClock, CoinToss, GameState, TimeControl};
 
 /// Deposit into `Game` for each `Player`
 /// Used for computing reward
@@ -18,6 +12,21 @@ pub struct GameDeposit {
     pub balance: U128,
 }
 
+/// A piece placed on the board, in the order of `Game.moves`
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct MoveRecord {
+    pub row: u8,
+    pub col: u8,
+    /// sub-board in `GameMode::Ultimate`
+    pub sub_board: Option<u8>,
+    /// layer of the cube in `GameMode::Qubic`
+    pub layer: Option<u8>,
+    pub piece: Piece,
+    pub timestamp_sec: u32,
+}
+
 #[derive(BorshSerialize, BorshDeserialize, Clone, Serialize, Deserialize)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
 #[serde(crate = "near_sdk::serde")]
@@ -32,6 +41,18 @@ pub struct Game {
     pub initiated_at: u64,
     pub last_turn_timestamp: u64,
     pub current_duration: Duration,
+    /// sub-boards of `GameMode::Ultimate`, `board` is the meta-board then
+    pub ultimate: Option<UltimateBoard>,
+    /// cube of `GameMode::Qubic`, only `board.winner` is used then
+    pub cube: Option<CubeBoard>,
+    /// player offering a draw, the offer expires when the opponent moves
+    pub draw_offer: Option<AccountId>,
+    /// time banks in seconds of `players[0]` and `players[1]`, `None` if the game uses the duration limits
+    pub clock: Option<ChessClock>,
+    /// all moves in the order they were made
+    pub moves: Vec<MoveRecord>,
+    /// commit–reveal draw of the first player, the game is `NotStarted` until it's done
+    pub coin_toss: Option<CoinToss>,
 }
 
 impl Game {
@@ -42,11 +63,21 @@ impl Game {
     pub fn create_game(
         player_1: AccountId,
         player_2: AccountId,
-        reward: GameDeposit
+        reward: GameDeposit,
+        mode: GameMode,
+        misere: bool,
     ) -> Game {
         assert_ne!(player_1, player_2, "Player 1 and Player 2 have the same AccountId: @{}", &player_1);
         let (player_1, player_2) = Game::create_players(player_1, player_2);
-        let board = Board::new(&player_1, &player_2);
+        let board = Board::new(&player_1, &player_2, RULES.with_misere(misere));
+        let ultimate = match mode {
+            GameMode::Ultimate => Some(UltimateBoard::new(&player_1, &player_2)),
+            GameMode::Classic | GameMode::Qubic => None,
+        };
+        let cube = match mode {
+            GameMode::Qubic => Some(CubeBoard::new(misere)),
+            GameMode::Classic | GameMode::Ultimate => None,
+        };
         let mut game = Game { 
             game_state: GameState::NotStarted, 
             players:Vec::with_capacity(PLAYERS_NUM),
@@ -59,10 +90,25 @@ impl Game {
             initiated_at: env::block_timestamp(),
             last_turn_timestamp: 0, 
             current_duration: 0,
+            ultimate,
+            cube,
+            draw_offer: None,
+            clock: None,
+            moves: Vec::new(),
+            coin_toss: None,
         };
         game.set_players(player_1, player_2);
         game
     }
+    pub fn mode(&self) -> GameMode {
+        if self.ultimate.is_some() {
+            GameMode::Ultimate
+        } else if self.cube.is_some() {
+            GameMode::Qubic
+        } else {
+            GameMode::Classic
+        }
+    }
     /// creates random piece for player1 and `other()` one for player2
     fn create_players(account_id_1: AccountId, account_id_2: AccountId) -> (Player, Player) {
         let piece_1 = Piece::random();
@@ -97,6 +143,63 @@ impl Game {
         )
     }
 
+    /// `sub_board` is required in `GameMode::Ultimate`, `layer` in `GameMode::Qubic`;
+    /// both are ignored otherwise
+    pub fn check_move(&self, sub_board: Option<u8>, layer: Option<u8>, row: u8, col: u8) -> Result<(), MoveError> {
+        if let Some(cube) = &self.cube {
+            let z = layer.expect("Layer is required in qubic mode");
+            return cube.check_move(&CubeCoords { x: col, y: row, z });
+        }
+        match &self.ultimate {
+            Some(ultimate) => {
+                let sub_board = sub_board.expect("Sub-board is required in ultimate mode");
+                ultimate.check_move(sub_board, row, col)
+            }
+            None => self.board.check_move(row, col),
+        }
+    }
+
+    /// Places current piece on a checked tile and updates the winner.
+    /// In `GameMode::Ultimate` a won sub-board takes its tile on the meta-board.
+    pub fn place_piece(&mut self, sub_board: Option<u8>, layer: Option<u8>, row: u8, col: u8) {
+        let piece = self.current_piece;
+        if let Some(cube) = self.cube.as_mut() {
+            let z = layer.expect("Layer is required in qubic mode");
+            cube.make_move(&CubeCoords { x: col, y: row, z }, piece);
+            self.board.winner = cube.winner;
+            return;
+        }
+        let ultimate = match self.ultimate.as_mut() {
+            Some(ultimate) => ultimate,
+            None => {
+                self.board.tiles[row as usize][col as usize] = Some(piece);
+                self.board.update_winner(row, col);
+                return;
+            }
+        };
+        let sub_board = sub_board.expect("Sub-board is required in ultimate mode");
+        let (meta_row, meta_col) = (sub_board / BOARD_SIZE as u8, sub_board % BOARD_SIZE as u8);
+        match ultimate.make_move(sub_board, row, col, piece) {
+            Some(Winner::O) | Some(Winner::X) => {
+                self.board.tiles[meta_row as usize][meta_col as usize] = Some(piece);
+                self.board.update_winner(meta_row, meta_col);
+            }
+            // a tied sub-board doesn't count for anyone
+            Some(Winner::Tie) | None => {}
+        }
+        if self.board.winner.is_none() && ultimate.is_finished() {
+            self.board.winner = Some(Winner::Tie);
+        }
+    }
+
+    pub fn cube_layers(&self) -> Option<Vec<CubeLayer>> {
+        self.cube.as_ref().map(|cube| cube.layers.to_vec())
+    }
+
+    pub fn sub_board_tiles(&self) -> Option<Vec<[[Option<Piece>; BOARD_SIZE]; BOARD_SIZE]>> {
+        self.ultimate.as_ref().map(UltimateBoard::tiles)
+    }
+
     pub fn change_state(&mut self, new_state: GameState) {
         assert_ne!(new_state, self.game_state, "State is already {:?}", new_state);
         self.game_state = new_state
@@ -134,6 +237,68 @@ impl Game {
         }
     }
 
+    /// Drops the draw offer of the opponent of `player` who moved instead of accepting it
+    pub fn expire_draw_offer(&mut self, player: &AccountId) {
+        if matches!(&self.draw_offer, Some(offer) if offer != player) {
+            self.draw_offer = None;
+        }
+    }
+
+    /// Starts the clocks of both players if the game has a time control
+    pub fn set_time_control(&mut self, time_control: Option<TimeControl>) {
+        let initiated_at_sec = nano_to_sec(self.initiated_at) as u64;
+        self.clock = time_control.map(|time_control| ChessClock::new(time_control, initiated_at_sec));
+    }
+
+    /// Returns true if the clock of the current player ran out
+    pub fn is_out_of_time(&self, cur_timestamp: u64) -> bool {
+        let cur_timestamp_sec = nano_to_sec(cur_timestamp) as u64;
+        matches!(&self.clock, Some(clock) if clock.is_flagged(self.current_player_index as usize, cur_timestamp_sec))
+    }
+
+    /// Time left in seconds of the current and the next player
+    pub fn time_left_sec(&self, cur_timestamp: u64) -> Option<(u32, u32)> {
+        let cur_timestamp_sec = nano_to_sec(cur_timestamp) as u64;
+        self.clock.as_ref().map(|clock| {
+            let to_move = self.current_player_index as usize;
+            (
+                clock.time_left(to_move, to_move, cur_timestamp_sec) as u32,
+                clock.time_left(1 - to_move, to_move, cur_timestamp_sec) as u32,
+            )
+        })
+    }
+
+    pub fn player_index(&self, account_id: &AccountId) -> usize {
+        self.players
+            .iter()
+            .position(|player| player.account_id == *account_id)
+            .unwrap_or_else(|| panic!("Account @{} doesn't play in this game", account_id))
+    }
+
+    /// Returns true if the secrets weren't both revealed before the deadline
+    pub fn is_reveal_expired(&self, cur_timestamp: u64) -> bool {
+        self.game_state == GameState::NotStarted
+            && matches!(&self.coin_toss, Some(coin_toss) if cur_timestamp > coin_toss.reveal_deadline)
+    }
+
+    /// Starts the game once the first player is drawn: `players[1]` moves first if `swap`
+    /// and the first player gets `first_piece`. Nothing is placed on the boards yet.
+    pub fn start_after_coin_toss(&mut self, swap: bool, first_piece: Piece, cur_timestamp: u64) {
+        if swap {
+            self.players.swap(0, 1);
+        }
+        self.players[0].piece = first_piece;
+        self.players[1].piece = first_piece.other();
+        self.current_piece = first_piece;
+        self.board.current_piece = first_piece;
+        if let Some(ultimate) = self.ultimate.as_mut() {
+            *ultimate = UltimateBoard::new(&self.players[0], &self.players[1]);
+        }
+        self.initiated_at = cur_timestamp;
+        self.set_time_control(self.clock.map(|clock| clock.time_control));
+        self.change_state(GameState::Active);
+    }
+
     pub fn reward(&self) -> GameDeposit {
         self.reward.clone()
     }
@@ -153,6 +318,9 @@ impl Game {
         assert_ne!(*player, self.current_player_account_id(), "You can claim timeout win only if it's not your turn");
         //3. Check for timeout
         let cur_timestamp = env::block_timestamp();
+        if self.clock.is_some() {
+            return self.is_out_of_time(cur_timestamp);
+        }
         if cur_timestamp - self.last_turn_timestamp <= utils::TIMEOUT_WIN {
             return false;
         }
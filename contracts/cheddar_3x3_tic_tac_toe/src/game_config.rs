@@ -9,6 +9,18 @@ pub struct GameConfig {
     pub(crate) opponent_id: Option<AccountId>,
     pub(crate) referrer_id: Option<AccountId>,
     pub(crate) created_at: u64,
+    /// only offers with the same mode are paired
+    pub(crate) mode: GameMode,
+    /// completing a line loses, only offers with the same rule are paired
+    pub(crate) misere: bool,
+    /// chess clock in seconds, only offers with the same time control are paired
+    pub(crate) time_control: Option<TimeControl>,
+    /// hash of the secret drawing the first player, only paired with another commitment
+    pub(crate) commitment: Option<Base58CryptoHash>,
+    /// lowest rating of an opponent in the `RatingPool` of the mode, not bounded if not set
+    pub(crate) min_rating: Option<u32>,
+    /// highest rating of an opponent in the `RatingPool` of the mode, not bounded if not set
+    pub(crate) max_rating: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -20,6 +32,12 @@ pub struct GameConfigView {
     pub(crate) opponent_id: Option<AccountId>,
     pub(crate) referrer_id: Option<AccountId>,
     pub(crate) created_at: u32,
+    pub(crate) mode: GameMode,
+    pub(crate) misere: bool,
+    pub(crate) time_control: Option<TimeControl>,
+    pub(crate) commitment: Option<Base58CryptoHash>,
+    pub(crate) min_rating: Option<u32>,
+    pub(crate) max_rating: Option<u32>,
 }
 
 impl From<&GameConfig> for GameConfigView {
@@ -29,7 +47,13 @@ impl From<&GameConfig> for GameConfigView {
             deposit: gc.deposit.into(), 
             opponent_id: gc.opponent_id.clone(), 
             referrer_id: gc.referrer_id.clone(),
-            created_at: nano_to_sec(gc.created_at)
+            created_at: nano_to_sec(gc.created_at),
+            mode: gc.mode,
+            misere: gc.misere,
+            time_control: gc.time_control,
+            commitment: gc.commitment,
+            min_rating: gc.min_rating,
+            max_rating: gc.max_rating,
         }
     }
 }
@@ -39,7 +63,20 @@ impl From<&GameConfig> for GameConfigView {
 #[serde(crate = "near_sdk::serde")]
 pub struct GameConfigNear {
     pub(crate) opponent_id: Option<AccountId>,
-    pub(crate) referrer_id: Option<AccountId>
+    pub(crate) referrer_id: Option<AccountId>,
+    /// `GameMode::Classic` if not set
+    pub(crate) mode: Option<GameMode>,
+    /// if true, completing a line loses; false by default
+    pub(crate) misere: Option<bool>,
+    /// chess clock in seconds replacing the game and turn duration limits; not set by default
+    pub(crate) time_control: Option<TimeControl>,
+    /// `CoinToss::commitment` of a secret revealed with `reveal_secret` once the game is created,
+    /// the first player and the pieces are drawn from both secrets; `env::random_seed` is used by default
+    pub(crate) commitment: Option<Base58CryptoHash>,
+    /// only opponents rated at least `min_rating` can start the game; not bounded by default
+    pub(crate) min_rating: Option<u32>,
+    /// only opponents rated at most `max_rating` can start the game; not bounded by default
+    pub(crate) max_rating: Option<u32>,
 }
 
 impl GameConfig {
@@ -54,7 +91,13 @@ impl GameConfig {
             deposit, 
             opponent_id: None, 
             referrer_id: None,
-            created_at: env::block_timestamp()
+            created_at: env::block_timestamp(),
+            mode: GameMode::Classic,
+            misere: false,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
         }
     }
     /// `GameConfig` from transfer message
@@ -68,7 +111,18 @@ impl GameConfig {
             deposit, 
             opponent_id: game_args.opponent_id.clone(), 
             referrer_id: game_args.referrer_id.clone(),
-            created_at: env::block_timestamp()
+            created_at: env::block_timestamp(),
+            mode: game_args.mode.unwrap_or_default(),
+            misere: game_args.misere.unwrap_or(false),
+            time_control: game_args.time_control,
+            commitment: game_args.commitment,
+            min_rating: game_args.min_rating,
+            max_rating: game_args.max_rating,
         }
     }
+    /// Returns true if an opponent with `rating` may start the game
+    pub fn accepts_rating(&self, rating: &Rating) -> bool {
+        !matches!(self.min_rating, Some(min_rating) if rating.rating < min_rating as f64)
+            && !matches!(self.max_rating, Some(max_rating) if rating.rating > max_rating as f64)
+    }
 }
\ No newline at end of file
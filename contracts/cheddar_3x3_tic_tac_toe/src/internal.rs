@@ -32,19 +32,31 @@ impl Contract {
         self.available_players.get(account_id).expect("You are not in available players list!")
     }
 
+    /// `max_game_duration` is set for `GameMode::Classic`, longer modes get as much time per turn.
+    /// `max_turn_duration` limits a single turn, so it's the same in every mode.
+    pub (crate) fn internal_max_game_duration(&self, game: &Game) -> Duration {
+        self.max_game_duration / MAX_NUM_TURNS * game.mode().max_turns()
+    }
+
     pub (crate) fn internal_ping_expired_games(&mut self, ts: u64) {
         let expired_games_ids: Vec<GameId> = self.games
             .iter()
-            .filter(|(_, game)| {
-                ts - game.initiated_at > self.max_game_duration
+            .filter(|(_, game)| match game.clock {
+                _ if game.game_state == GameState::NotStarted => game.is_reveal_expired(ts),
+                Some(_) => game.is_out_of_time(ts),
+                None => ts - game.initiated_at > self.internal_max_game_duration(game),
             })
             .map(|(game_id, _) | game_id)
             .collect();
         if !expired_games_ids.is_empty() {
             for game_id in expired_games_ids.iter() {
                 let game = self.internal_get_game(game_id);
+                if game.game_state == GameState::NotStarted {
+                    self.internal_expire_coin_toss(game_id);
+                    continue;
+                }
                 self.internal_stop_expired_game(game_id, game.current_player_account_id());
-                log!("GameId: {}. Game duration expired. Required:{} Current:{} ", game_id, self.max_game_duration, ts - game.initiated_at);
+                log!("GameId: {}. Game duration expired. Required:{} Current:{} ", game_id, self.internal_max_game_duration(&game), ts - game.initiated_at);
             }
         }
         self.last_update_timestamp = ts;
@@ -97,6 +109,8 @@ impl Contract {
         game_id: &GameId,
         winner: Option<&AccountId>,
     )  -> U128 {
+        self.internal_rate_game(game_id, winner);
+        self.internal_record_season_game(&self.internal_get_game_players(game_id), winner);
         let reward = self.internal_get_game_reward(game_id);
         let players_deposit = reward.balance;
         let token_id = reward.token_id.clone();
@@ -114,7 +128,8 @@ impl Contract {
 
             self.internal_transfer(&token_id, winner_id, winner_reward.into());
 
-            self.internal_distribute_fee(&token_id, fees_amount, winner_id);
+            let referrer_fee = self.internal_distribute_fee(&token_id, fees_amount, winner_id);
+            self.internal_fund_season(&token_id, fees_amount - referrer_fee);
             self.internal_update_stats(
                 winner_id, 
                 UpdateStatsAction::AddWonGame, 
@@ -143,6 +158,7 @@ impl Contract {
                 &token_id, 
                 refund_amount
             );
+            self.internal_fund_season(&token_id, fees_amount);
             refund_amount.into()
         }
     }
@@ -220,13 +236,17 @@ impl Contract {
 
         let game_to_store = GameLimitedView{
             game_result: views::GameResult::Win(winner),
+            reason: ResultReason::Timeout,
             player1,
             player2,
             reward_or_tie_refund: GameDeposit { 
                 token_id: game.reward().token_id, 
                 balance 
             },
-            board: game.board.tiles
+            board: game.board.tiles,
+            sub_boards: game.sub_board_tiles(),
+            cube_layers: game.cube_layers(),
+            moves: game.moves.clone(),
         };
         self.internal_store_game(game_id, game_to_store);
 
@@ -5,34 +5,47 @@ use near_sdk::{
 use near_sdk::{
     env, ext_contract, log, near_bindgen, ONE_NEAR, ONE_YOCTO, require
 };
-use near_sdk::json_types::U128;
+use near_sdk::json_types::{Base58CryptoHash, U128, U64};
 use near_sdk::borsh::{self, BorshSerialize, BorshDeserialize};
 use near_sdk::serde::{Serialize, Deserialize};
 use near_sdk::collections::{UnorderedMap, UnorderedSet};
 use stats::UserPenalties;
 use views::GameLimitedView;
+use tic_tac_toe_rules::{Coords, Grid, MnkRules, Rating, Ruleset};
 
 mod board;
 mod callbacks;
+mod coin_toss;
 mod config;
+mod cube;
 mod game;
 mod game_config;
 mod internal;
+mod leaderboard;
+mod migration;
 mod player;
+mod rating;
+mod season;
 mod stats;
 mod token_receiver;
+mod ultimate;
 mod views;
 mod utils;
 
 use crate::board::*;
 use crate::config::*;
+use crate::cube::*;
 use crate::game::*;
 use crate::game_config::*;
+use crate::leaderboard::*;
 use crate::player::*;
+use crate::rating::*;
+use crate::season::*;
 use crate::stats::*;
 use crate::token_receiver::*;
+use crate::ultimate::*;
 use crate::utils::*;
-use crate::views::GameResult;
+use crate::views::{GameResult, ResultReason};
 
 #[derive(BorshSerialize, BorshStorageKey)]
 pub enum StorageKey {
@@ -44,7 +57,12 @@ pub enum StorageKey {
     Stats,
     Affiliates {account_id : AccountId},
     TotalRewards {account_id : AccountId},
-    TotalAffiliateRewards {account_id : AccountId}
+    TotalAffiliateRewards {account_id : AccountId},
+    Ratings,
+    Leaderboards,
+    Leaderboard {board: Leaderboard},
+    Seasons,
+    SeasonStats,
 }
 
 pub (crate) type MinDeposit = Balance;
@@ -68,11 +86,20 @@ pub struct Contract {
     referrer_ratio: u32,
     /// system updates
     pub last_update_timestamp: u64,
-    /// max expected turn duration in nanoseconds (max_game_duration / max possible turns num)
+    /// max expected turn duration in nanoseconds (max_game_duration / max possible turns num),
+    /// `max_game_duration` is scaled to the number of turns of the `GameMode`
     max_turn_duration: u64,
     /// storage for printing results
     pub max_stored_games: u8,
-    pub stored_games: UnorderedMap<GameId, GameLimitedView>
+    pub stored_games: UnorderedMap<GameId, GameLimitedView>,
+    /// Glicko-2 ratings of the players in each `RatingPool`
+    ratings: UnorderedMap<(RatingPool, AccountId), PlayerRating>,
+    /// sorted indexes of the accounts
+    leaderboards: UnorderedMap<Leaderboard, LeaderboardIndex>,
+    seasons: UnorderedMap<SeasonId, Season>,
+    /// `SeasonId` which will be set for next created `Season`
+    next_season_id: SeasonId,
+    season_stats: UnorderedMap<(SeasonId, AccountId), SeasonStats>,
 }
 
 #[near_bindgen]
@@ -118,7 +145,12 @@ impl Contract {
             last_update_timestamp: 0,
             max_turn_duration: sec_to_nano(60),
             max_stored_games,
-            stored_games: UnorderedMap::new(StorageKey::StoredGames)
+            stored_games: UnorderedMap::new(StorageKey::StoredGames),
+            ratings: UnorderedMap::new(StorageKey::Ratings),
+            leaderboards: UnorderedMap::new(StorageKey::Leaderboards),
+            seasons: UnorderedMap::new(StorageKey::Seasons),
+            next_season_id: 0,
+            season_stats: UnorderedMap::new(StorageKey::SeasonStats),
         }
     }
 
@@ -138,11 +170,24 @@ impl Contract {
         let deposit: Balance = env::attached_deposit();
         assert!(deposit >= MIN_DEPOSIT_NEAR, "Deposit is too small. Attached: {}, Required: {}", deposit, MIN_DEPOSIT_NEAR);
 
-        let (opponent_id, referrer_id) = if let Some(game_config) = game_config {
-            (game_config.opponent_id, game_config.referrer_id.clone())
+        let (opponent_id, referrer_id, mode, misere, time_control, commitment, min_rating, max_rating) = if let Some(game_config) = game_config {
+            (
+                game_config.opponent_id,
+                game_config.referrer_id.clone(),
+                game_config.mode.unwrap_or_default(),
+                game_config.misere.unwrap_or(false),
+                game_config.time_control,
+                game_config.commitment,
+                game_config.min_rating,
+                game_config.max_rating
+            )
         } else {
-            (None, None)
+            (None, None, GameMode::Classic, false, None, None, None, None)
         };
+        if let Some(time_control) = &time_control {
+            validate_time_control(time_control);
+        }
+        validate_rating_bounds(min_rating, max_rating);
 
         self.available_players.insert(account_id,
             &GameConfig {
@@ -150,7 +195,13 @@ impl Contract {
                 deposit,
                 opponent_id,
                 referrer_id: referrer_id.clone(),
-                created_at: cur_timestamp
+                created_at: cur_timestamp,
+                mode,
+                misere,
+                time_control,
+                commitment,
+                min_rating,
+                max_rating,
             }
         );
         
@@ -189,10 +240,12 @@ impl Contract {
 
             // Get predecessor's available deposit
             let player_1_config = self.internal_get_available_player(&player_1_id);
-            let player_1_config_token = player_1_config.token_id;
+            let player_1_config_token = player_1_config.token_id.clone();
             let player_1_deposit = player_1_config.deposit;
 
             self.internal_check_player_available(&player_1_id);
+            self.internal_check_rating_bounds(&player_2_config, &player_1_id);
+            self.internal_check_rating_bounds(&player_1_config, &player_2_id);
             
             if let Some(player_id) = player_2_config.opponent_id {
                 assert_eq!(player_id, player_1_id, "Wrong account");
@@ -211,6 +264,33 @@ impl Contract {
             let token_id = player_2_config.token_id;
 
             assert_eq!(token_id, player_1_config_token, "Mismatch tokens! Choosen tokens for opponent and you must be the same");
+            assert_eq!(
+                player_1_config.mode,
+                player_2_config.mode,
+                "Mismatched game modes! You: {:?}, Opponent: {:?}",
+                player_1_config.mode,
+                player_2_config.mode
+            );
+            assert_eq!(
+                player_1_config.misere,
+                player_2_config.misere,
+                "Mismatched misère rule! You: {}, Opponent: {}",
+                player_1_config.misere,
+                player_2_config.misere
+            );
+            assert_eq!(
+                player_1_config.time_control,
+                player_2_config.time_control,
+                "Mismatched time controls for players! You: {:?}, Opponent: {:?}",
+                player_1_config.time_control,
+                player_2_config.time_control
+            );
+            assert_eq!(
+                player_1_config.commitment.is_some(),
+                player_2_config.commitment.is_some(),
+                "Mismatched first player draws! Both players must commit a secret or none"
+            );
+            let (mode, misere) = (player_2_config.mode, player_2_config.misere);
             // deposit * 2
             let balance = match player_2_config.deposit.checked_mul(2) {
                 Some(value) => value,
@@ -225,24 +305,42 @@ impl Contract {
             
             let seed = near_sdk::env::random_seed();
             let mut game = match seed[0] % 2 {
+                // the order is drawn from the secrets once both are revealed
+                _ if player_2_config.commitment.is_some() => {
+                    Game::create_game(
+                    player_1_id.clone(),
+                    player_2_id.clone(),
+                    reward,
+                    mode,
+                    misere
+                    )
+                },
                 0 => {
                     Game::create_game(
                     player_2_id.clone(),
                     player_1_id.clone(),
-                    reward
+                    reward,
+                    mode,
+                    misere
                     )
                 },
                 _ => {
                     Game::create_game(
                     player_1_id.clone(),
                     player_2_id.clone(),
-                    reward
+                    reward,
+                    mode,
+                    misere
                     )
                 },
             };
 
+            game.set_time_control(player_2_config.time_control);
             game.change_state(GameState::Active);
             self.games.insert(&game_id, &game);
+            if let (Some(commitment_1), Some(commitment_2)) = (player_1_config.commitment, player_2_config.commitment) {
+                self.internal_start_coin_toss(&game_id, [commitment_1, commitment_2]);
+            }
 
             self.next_game_id += 1;
             self.available_players.remove(&player_1_id);
@@ -263,8 +361,25 @@ impl Contract {
         }
     }
 
-    pub fn make_move(&mut self, game_id: &GameId, row: usize, col: usize) -> [[Option<Piece>; BOARD_SIZE]; BOARD_SIZE] {
+    /// `sub_board` (`row * 3 + col` on the meta-board) is required in `GameMode::Ultimate`,
+    /// `layer` of the cube in `GameMode::Qubic`.
+    /// Returns the board, the meta-board in `GameMode::Ultimate`; the cube is in `get_active_games`.
+    pub fn make_move(
+        &mut self,
+        game_id: &GameId,
+        row: u8,
+        col: u8,
+        sub_board: Option<u8>,
+        layer: Option<u8>
+    ) -> [[Option<Piece>; BOARD_SIZE]; BOARD_SIZE] {
         let cur_timestamp = env::block_timestamp();
+        let game = self.internal_get_game(game_id);
+        if game.is_out_of_time(cur_timestamp) {
+            // a move after the flag fell doesn't count
+            log!("Clock of @{} ran out", game.current_player_account_id());
+            self.internal_stop_expired_game(game_id, game.current_player_account_id());
+            return game.board.tiles;
+        }
         //checkpoint
         self.internal_ping_expired_games(cur_timestamp);
 
@@ -274,15 +389,24 @@ impl Contract {
         assert_eq!(env::predecessor_account_id(), game.current_player_account_id(), "No access");
         assert_eq!(init_game_state, GameState::Active, "Current game isn't active");
 
-        match game.board.check_move(row, col) {
+        match game.check_move(sub_board, layer, row, col) {
             Ok(_) => {
+                game.expire_draw_offer(&env::predecessor_account_id());
                 // fill board tile with current player piece
-                game.board.tiles[row][col] = Some(game.current_piece);
+                game.place_piece(sub_board, layer, row, col);
+                game.moves.push(MoveRecord {
+                    row,
+                    col,
+                    // only kept in the modes using them
+                    sub_board: game.ultimate.as_ref().and(sub_board),
+                    layer: game.cube.as_ref().and(layer),
+                    piece: game.current_piece,
+                    timestamp_sec: nano_to_sec(cur_timestamp),
+                });
                 // switch piece to other one
                 game.current_piece = game.current_piece.other();
                 // switch player
                 game.current_player_index = 1 - game.current_player_index;
-                game.board.update_winner(row, col);
 
                 if let Some(winner) = game.board.winner {
                     // change game state to Finished
@@ -315,6 +439,7 @@ impl Contract {
 
                     let game_to_store = GameLimitedView{
                         game_result,
+                        reason: game.board.rules.result_reason(winner),
                         player1,
                         player2,
                         reward_or_tie_refund: GameDeposit {
@@ -322,6 +447,9 @@ impl Contract {
                             balance
                         },
                         board: game.board.tiles,
+                        sub_boards: game.sub_board_tiles(),
+                        cube_layers: game.cube_layers(),
+                        moves: game.moves.clone(),
                     };
 
                     self.internal_store_game(game_id, game_to_store);
@@ -331,12 +459,19 @@ impl Contract {
                 };
             },
             Err(e) => match e {
-                MoveError::GameAlreadyOver => panic!("Game is already finished"),
+                MoveError::GameOver => panic!("Game is already finished"),
                 MoveError::InvalidPosition { row, col } => panic!(
                     "Provided position is invalid: row: {} col: {}", row, col),
                 MoveError::TileFilled { other_piece, row, col } => panic!(
                     "The tile row: {} col: {} already contained another piece: {:?}", row, col, other_piece
                 ),
+                MoveError::Forbidden { kind, row, col } => panic!(
+                    "The move row: {} col: {} is forbidden: {:?}", row, col, kind
+                ),
+                MoveError::ColumnFull { col } => panic!("The column {} is full", col),
+                MoveError::InvalidSubBoard { sub_board } => panic!("Provided sub-board is invalid: {}", sub_board),
+                MoveError::WrongSubBoard { required, .. } => panic!("You must play in sub-board {}", required),
+                MoveError::SubBoardFinished { sub_board } => panic!("Sub-board {} is already finished", sub_board),
             },
         }
         if game.game_state == GameState::Active {
@@ -349,6 +484,18 @@ impl Contract {
             // this game duration 
             game.current_duration = cur_timestamp - game.initiated_at;
 
+            // the clock replaces the turn and game duration limits
+            let mover = 1 - game.current_player_index as usize;
+            if let Some(clock) = game.clock.as_mut() {
+                if !clock.punch(mover, nano_to_sec(cur_timestamp) as u64) {
+                    log!("Clock of @{} ran out", env::predecessor_account_id());
+                    self.internal_stop_expired_game(game_id, env::predecessor_account_id());
+                } else {
+                    self.internal_update_game(game_id, &game);
+                }
+                return game.board.tiles;
+            }
+
             if previous_turn_timestamp == 0 {
                 if cur_timestamp - game.initiated_at > self.max_turn_duration {
                     log!("Turn duration expired. Required:{} Current:{} ", self.max_turn_duration, cur_timestamp - game.initiated_at);
@@ -369,11 +516,12 @@ impl Contract {
                 return game.board.tiles;
             };
 
-            if game.current_duration <= self.max_game_duration {
+            let max_game_duration = self.internal_max_game_duration(&game);
+            if game.current_duration <= max_game_duration {
                 self.internal_update_game(game_id, &game);
                 return game.board.tiles;
             } else {
-                log!("Game duration expired. Required:{} Current:{} ", self.max_game_duration, game.current_duration);
+                log!("Game duration expired. Required:{} Current:{} ", max_game_duration, game.current_duration);
                 // looser - current player
                 self.internal_stop_expired_game(game_id, env::predecessor_account_id());
                 return game.board.tiles;
@@ -408,6 +556,57 @@ impl Contract {
 
         let game_to_store = GameLimitedView{
             game_result: GameResult::Win(winner),
+            reason: ResultReason::GiveUp,
+            player1,
+            player2,
+            reward_or_tie_refund: GameDeposit {
+                token_id: game.reward().token_id,
+                balance
+            },
+            board: game.board.tiles,
+            sub_boards: game.sub_board_tiles(),
+            cube_layers: game.cube_layers(),
+            moves: game.moves.clone(),
+        };
+
+        self.internal_store_game(game_id, game_to_store);
+        self.internal_stop_game(game_id);
+    }
+
+    /// Offers the opponent a draw, the offer stands until the opponent moves
+    pub fn offer_draw(&mut self, game_id: &GameId) {
+        let mut game: Game = self.internal_get_game(game_id);
+        assert_eq!(game.game_state, GameState::Active, "Current game isn't active");
+        let account_id = env::predecessor_account_id();
+        assert!(game.contains_player_account_id(&account_id), "You are not in this game. GameId: {} ", game_id);
+        assert!(game.draw_offer.is_none(), "Draw is already offered");
+        log!("@{} offers a draw in game {}", account_id, game_id);
+        game.draw_offer = Some(account_id);
+        self.internal_update_game(game_id, &game);
+    }
+
+    /// Accepts the draw offered by the opponent, the deposits are refunded as in a tie
+    #[payable]
+    pub fn accept_draw(&mut self, game_id: &GameId) {
+        assert_one_yocto();
+        let mut game: Game = self.internal_get_game(game_id);
+        assert_eq!(game.game_state, GameState::Active, "Current game isn't active");
+        let account_id = env::predecessor_account_id();
+        assert!(game.contains_player_account_id(&account_id), "You are not in this game. GameId: {} ", game_id);
+        match &game.draw_offer {
+            Some(offer) if *offer != account_id => {},
+            _ => panic!("No draw offer from the opponent"),
+        }
+
+        log!("\nGame over! Draw by agreement!");
+        let balance = self.internal_distribute_reward(game_id, None);
+        game.change_state(GameState::Finished);
+        self.internal_update_game(game_id, &game);
+
+        let (player1, player2) = game.get_player_accounts();
+        let game_to_store = GameLimitedView{
+            game_result: GameResult::Tie,
+            reason: ResultReason::DrawByAgreement,
             player1,
             player2,
             reward_or_tie_refund: GameDeposit {
@@ -415,6 +614,9 @@ impl Contract {
                 balance
             },
             board: game.board.tiles,
+            sub_boards: game.sub_board_tiles(),
+            cube_layers: game.cube_layers(),
+            moves: game.moves.clone(),
         };
 
         self.internal_store_game(game_id, game_to_store);
@@ -431,10 +633,11 @@ impl Contract {
         let (player1, player2) = self.internal_get_game_players(game_id);
 
         game.current_duration = env::block_timestamp() - game.initiated_at;
-        assert!(
-            game.current_duration >= self.max_game_duration || env::block_timestamp() - game.last_turn_timestamp > self.max_turn_duration, 
-            "Too early to stop the game"
-        );
+        let can_stop = match game.clock {
+            Some(_) => game.is_out_of_time(env::block_timestamp()),
+            None => game.current_duration >= self.internal_max_game_duration(&game) || env::block_timestamp() - game.last_turn_timestamp > self.max_turn_duration,
+        };
+        assert!(can_stop, "Too early to stop the game");
 
         let (winner, looser) = if account_id == player1 {
             (player1, player2)
@@ -456,6 +659,7 @@ impl Contract {
 
         let game_to_store = GameLimitedView{
             game_result: GameResult::Win(winner.clone()),
+            reason: ResultReason::Timeout,
             player1: winner,
             player2: looser,
             reward_or_tie_refund: GameDeposit {
@@ -463,6 +667,9 @@ impl Contract {
                 balance
             },
             board: game.board.tiles,
+            sub_boards: game.sub_board_tiles(),
+            cube_layers: game.cube_layers(),
+            moves: game.moves.clone(),
         };
 
         self.internal_store_game(game_id, game_to_store);
@@ -472,6 +679,15 @@ impl Contract {
     pub fn claim_timeout_win(&mut self, game_id: &GameId) {
         let game: Game = self.internal_get_game(&game_id);
         let player = env::predecessor_account_id();
+        if game.game_state == GameState::NotStarted {
+            assert!(game.contains_player_account_id(&player), "No access");
+            if !game.is_reveal_expired(env::block_timestamp()) {
+                log!("can't claim the win, the reveal deadline didn't pass");
+                return;
+            }
+            self.internal_expire_coin_toss(game_id);
+            return;
+        }
         if game.claim_timeout_win(&player) == false {
             log!("can't claim the win, timeout didn't pass");
             return;
@@ -481,6 +697,7 @@ impl Contract {
         self.games.remove(game_id);
         let game_to_store = GameLimitedView{
             game_result: GameResult::Win(player.clone()),
+            reason: ResultReason::Timeout,
             player1: player,
             player2: looser,
             reward_or_tie_refund: GameDeposit {
@@ -488,6 +705,9 @@ impl Contract {
                 balance
             },
             board: game.board.tiles,
+            sub_boards: game.sub_board_tiles(),
+            cube_layers: game.cube_layers(),
+            moves: game.moves.clone(),
         };
         self.internal_store_game(game_id, game_to_store);
     }
@@ -499,6 +719,7 @@ mod tests {
     use near_sdk::test_utils::VMContextBuilder;
     use near_sdk::{testing_env, Balance};
     use crate::views::GameView;
+    use crate::migration::*;
 
     use super::*;
 
@@ -570,7 +791,133 @@ mod tests {
             .build());
         ctr.make_available(Some(GameConfigNear { 
             opponent_id, 
-            referrer_id 
+            referrer_id,
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
+        }));
+    }
+
+    fn make_available_with_mode(
+        ctx: &mut VMContextBuilder,
+        ctr: &mut Contract,
+        user: &AccountId,
+        amount: Balance,
+        mode: GameMode,
+    ) {
+        testing_env!(ctx
+            .attached_deposit(amount)
+            .predecessor_account_id(user.clone())
+            .signer_account_id(user.clone())
+            .build());
+        ctr.make_available(Some(GameConfigNear { 
+            opponent_id: None, 
+            referrer_id: None,
+            mode: Some(mode),
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
+        }));
+    }
+
+    fn make_available_misere(
+        ctx: &mut VMContextBuilder,
+        ctr: &mut Contract,
+        user: &AccountId,
+        amount: Balance,
+    ) {
+        testing_env!(ctx
+            .attached_deposit(amount)
+            .predecessor_account_id(user.clone())
+            .signer_account_id(user.clone())
+            .build());
+        ctr.make_available(Some(GameConfigNear { 
+            opponent_id: None, 
+            referrer_id: None,
+            mode: None,
+            misere: Some(true),
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
+        }));
+    }
+
+    fn make_available_clock(
+        ctx: &mut VMContextBuilder,
+        ctr: &mut Contract,
+        user: &AccountId,
+        amount: Balance,
+        time_control: TimeControl,
+    ) {
+        testing_env!(ctx
+            .attached_deposit(amount)
+            .predecessor_account_id(user.clone())
+            .signer_account_id(user.clone())
+            .build());
+        ctr.make_available(Some(GameConfigNear { 
+            opponent_id: None, 
+            referrer_id: None,
+            mode: None,
+            misere: None,
+            time_control: Some(time_control),
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
+        }));
+    }
+
+    fn make_available_commitment(
+        ctx: &mut VMContextBuilder,
+        ctr: &mut Contract,
+        user: &AccountId,
+        amount: Balance,
+        secret: u128,
+    ) {
+        testing_env!(ctx
+            .attached_deposit(amount)
+            .predecessor_account_id(user.clone())
+            .signer_account_id(user.clone())
+            .build());
+        ctr.make_available(Some(GameConfigNear { 
+            opponent_id: None, 
+            referrer_id: None,
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: Some(CoinToss::commitment(user, secret)),
+            min_rating: None,
+            max_rating: None,
+        }));
+    }
+
+    fn make_available_rating_bounds(
+        ctx: &mut VMContextBuilder,
+        ctr: &mut Contract,
+        user: &AccountId,
+        amount: Balance,
+        min_rating: Option<u32>,
+        max_rating: Option<u32>,
+    ) {
+        testing_env!(ctx
+            .attached_deposit(amount)
+            .predecessor_account_id(user.clone())
+            .signer_account_id(user.clone())
+            .build());
+        ctr.make_available(Some(GameConfigNear { 
+            opponent_id: None, 
+            referrer_id: None,
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating,
+            max_rating,
         }));
     }
 
@@ -614,18 +961,68 @@ mod tests {
         ctr.start_game(opponent.clone())
     }
 
+    fn reveal_secret(
+        ctx: &mut VMContextBuilder,
+        ctr: &mut Contract,
+        user: &AccountId,
+        game_id: &GameId,
+        secret: u128,
+    ) {
+        testing_env!(ctx
+            .predecessor_account_id(user.clone())
+            .build());
+        ctr.reveal_secret(game_id, U128(secret));
+    }
+
+    /// user and opponent commit to `secrets` and the user starts the game
+    fn start_coin_toss_game(secrets: (u128, u128)) -> (VMContextBuilder, Contract, GameId) {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        make_available_commitment(&mut ctx, &mut ctr, &user(), ONE_NEAR, secrets.0);
+        make_available_commitment(&mut ctx, &mut ctr, &opponent(), ONE_NEAR, secrets.1);
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        (ctx, ctr, game_id)
+    }
+
     fn make_move(
         ctx: &mut VMContextBuilder,
         ctr: &mut Contract,
         user: &AccountId,
         game_id: &GameId,
-        row: usize,
-        col: usize
+        row: u8,
+        col: u8
+    ) -> [[Option<Piece>; BOARD_SIZE]; BOARD_SIZE] {
+        testing_env!(ctx
+            .predecessor_account_id(user.clone())
+            .build());
+        ctr.make_move(game_id, row, col, None, None)
+    }
+
+    fn make_ultimate_move(
+        ctx: &mut VMContextBuilder,
+        ctr: &mut Contract,
+        user: &AccountId,
+        game_id: &GameId,
+        sub_board: u8,
+        row: u8,
+        col: u8
+    ) -> [[Option<Piece>; BOARD_SIZE]; BOARD_SIZE] {
+        testing_env!(ctx
+            .predecessor_account_id(user.clone())
+            .build());
+        ctr.make_move(game_id, row, col, Some(sub_board), None)
+    }
+
+    fn make_qubic_move(
+        ctx: &mut VMContextBuilder,
+        ctr: &mut Contract,
+        user: &AccountId,
+        game_id: &GameId,
+        coords: CubeCoords
     ) -> [[Option<Piece>; BOARD_SIZE]; BOARD_SIZE] {
         testing_env!(ctx
             .predecessor_account_id(user.clone())
             .build());
-        ctr.make_move(game_id, row, col)
+        ctr.make_move(game_id, coords.y, coords.x, None, Some(coords.z))
     }
 
     fn stop_game(
@@ -678,8 +1075,8 @@ mod tests {
     }
 
     fn game_basics() -> Result<(VMContextBuilder, Contract), std::io::Error> {
-        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(60 * 10));
-        assert!(ctr.get_available_players().is_empty());
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        assert!(ctr.get_available_players(None).is_empty());
         whitelist_token(&mut ctr);
         assert_eq!(ctr.get_whitelisted_tokens(), Vec::from([
             (acc_cheddar(), (ONE_CHEDDAR / 10).into())
@@ -687,30 +1084,54 @@ mod tests {
 
         let gc1 = GameConfigArgs { 
             opponent_id: Some(opponent()), 
-            referrer_id: Some(referrer()) 
+            referrer_id: Some(referrer()),
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
         };
         let msg1 = near_sdk::serde_json::to_string(&gc1).expect("err serialize");
         let gc2 = GameConfigArgs { 
             opponent_id: Some(user()), 
-            referrer_id: None 
+            referrer_id: None,
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
         };
         let msg2 = near_sdk::serde_json::to_string(&gc2).expect("err serialize");
         make_available_ft(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, msg1);
         make_available_ft(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, msg2);
-        assert_eq!(ctr.get_available_players(), Vec::<(AccountId, GameConfigView)>::from([
+        assert_eq!(ctr.get_available_players(None), Vec::<(AccountId, GameConfigView)>::from([
             (user(), GameConfigView { 
                 token_id: acc_cheddar(), 
                 deposit: U128(ONE_CHEDDAR), 
                 opponent_id: Some(opponent()), 
                 referrer_id: Some(referrer()),
-                created_at: 0
+                created_at: 0,
+                mode: GameMode::Classic,
+                misere: false,
+                time_control: None,
+                commitment: None,
+                min_rating: None,
+                max_rating: None,
             }),
             (opponent(), GameConfigView { 
                 token_id: acc_cheddar(), 
                 deposit: U128(ONE_CHEDDAR), 
                 opponent_id: Some(user()), 
                 referrer_id: None,
-                created_at: 0
+                created_at: 0,
+                mode: GameMode::Classic,
+                misere: false,
+                time_control: None,
+                commitment: None,
+                min_rating: None,
+                max_rating: None,
             }),
         ]));
 
@@ -781,91 +1202,133 @@ mod tests {
 
     #[test]
     fn test_whitelist_token() {
-        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(60 * 10));
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
         whitelist_token(&mut ctr);
         assert_eq!(ctr.get_whitelisted_tokens(), Vec::from([
             (acc_cheddar(), U128(ONE_CHEDDAR / 10))
         ]));
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
     }
     #[test]
     fn make_available_unavailable_near() {
-        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(60 * 10));
-        assert!(ctr.get_available_players().is_empty());
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        assert!(ctr.get_available_players(None).is_empty());
         make_available_near(&mut ctx, &mut ctr, &user(), ONE_NEAR, None, Some(referrer()));
         make_available_near(&mut ctx, &mut ctr, &opponent(), ONE_NEAR, Some(user()), None);
-        assert_eq!(ctr.get_available_players(), Vec::<(AccountId, GameConfigView)>::from([
+        assert_eq!(ctr.get_available_players(None), Vec::<(AccountId, GameConfigView)>::from([
             (user(), GameConfigView { 
                 token_id: near(), 
                 deposit: U128(ONE_NEAR), 
                 opponent_id: None, 
                 referrer_id: Some(referrer()),
-                created_at: 0
+                created_at: 0,
+                mode: GameMode::Classic,
+                misere: false,
+                time_control: None,
+                commitment: None,
+                min_rating: None,
+                max_rating: None,
             }),
             (opponent(), GameConfigView { 
                 token_id: near(), 
                 deposit: U128(ONE_NEAR), 
                 opponent_id: Some(user()), 
                 referrer_id: None,
-                created_at: 0
+                created_at: 0,
+                mode: GameMode::Classic,
+                misere: false,
+                time_control: None,
+                commitment: None,
+                min_rating: None,
+                max_rating: None,
             }),
         ]));
         make_unavailable(&mut ctx, &mut ctr, &user());
         make_unavailable(&mut ctx, &mut ctr, &opponent());
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
     }
     #[test]
     fn test_make_available_unavailable() {
-        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(60 * 10));
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
         whitelist_token(&mut ctr);
         assert_eq!(ctr.get_whitelisted_tokens(), Vec::from([
             (acc_cheddar(), (ONE_CHEDDAR / 10).into())
         ]));
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
         let gc1 = GameConfigArgs { 
             opponent_id: Some(opponent()), 
-            referrer_id: Some(referrer()) 
+            referrer_id: Some(referrer()),
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
         };
         let msg1 = near_sdk::serde_json::to_string(&gc1).expect("err serialize");
         let gc2 = GameConfigArgs { 
             opponent_id: Some(user()), 
-            referrer_id: None 
+            referrer_id: None,
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
         };
         let msg2 = near_sdk::serde_json::to_string(&gc2).expect("err serialize");
         make_available_ft(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, msg1);
         make_available_ft(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, msg2);
-        assert_eq!(ctr.get_available_players(), Vec::<(AccountId, GameConfigView)>::from([
+        assert_eq!(ctr.get_available_players(None), Vec::<(AccountId, GameConfigView)>::from([
             (user(), GameConfigView { 
                 token_id: acc_cheddar(), 
                 deposit: U128(ONE_CHEDDAR), 
                 opponent_id: Some(opponent()), 
                 referrer_id: Some(referrer()),
-                created_at: 0
+                created_at: 0,
+                mode: GameMode::Classic,
+                misere: false,
+                time_control: None,
+                commitment: None,
+                min_rating: None,
+                max_rating: None,
             }),
             (opponent(), GameConfigView { 
                 token_id: acc_cheddar(), 
                 deposit: U128(ONE_CHEDDAR), 
                 opponent_id: Some(user()), 
                 referrer_id: None,
-                created_at: 0
+                created_at: 0,
+                mode: GameMode::Classic,
+                misere: false,
+                time_control: None,
+                commitment: None,
+                min_rating: None,
+                max_rating: None,
             }),
         ]));
         make_unavailable(&mut ctx, &mut ctr, &user());
         make_unavailable(&mut ctx, &mut ctr, &opponent());
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
     }
     #[test]
     #[should_panic(expected="Mismatch tokens! Choosen tokens for opponent and you must be the same")]
     fn start_game_diff_tokens() {
-        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(60 * 10));
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
         whitelist_token(&mut ctr);
         assert_eq!(ctr.get_whitelisted_tokens(), Vec::from([
             (acc_cheddar(), (ONE_CHEDDAR / 10).into())
         ]));
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
         let gc1 = GameConfigArgs { 
             opponent_id: Some(opponent()), 
-            referrer_id: Some(referrer()) 
+            referrer_id: Some(referrer()),
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
         };
         let msg1 = near_sdk::serde_json::to_string(&gc1).expect("err serialize");
 
@@ -875,38 +1338,62 @@ mod tests {
     }
     #[test]
     fn test_give_up() {
-        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(60 * 10));
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
         whitelist_token(&mut ctr);
         assert_eq!(ctr.get_whitelisted_tokens(), Vec::from([
             (acc_cheddar(), (ONE_CHEDDAR / 10).into())
         ]));
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
         let gc1 = GameConfigArgs { 
             opponent_id: Some(opponent()), 
-            referrer_id: Some(referrer()) 
+            referrer_id: Some(referrer()),
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
         };
         let msg1 = near_sdk::serde_json::to_string(&gc1).expect("err serialize");
         let gc2 = GameConfigArgs { 
             opponent_id: Some(user()), 
-            referrer_id: None 
+            referrer_id: None,
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
         };
         let msg2 = near_sdk::serde_json::to_string(&gc2).expect("err serialize");
         make_available_ft(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, msg1);
         make_available_ft(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, msg2);
-        assert_eq!(ctr.get_available_players(), Vec::<(AccountId, GameConfigView)>::from([
+        assert_eq!(ctr.get_available_players(None), Vec::<(AccountId, GameConfigView)>::from([
             (user(), GameConfigView { 
                 token_id: acc_cheddar(), 
                 deposit: U128(ONE_CHEDDAR), 
                 opponent_id: Some(opponent()), 
                 referrer_id: Some(referrer()),
-                created_at: 0 
+                created_at: 0,
+                mode: GameMode::Classic,
+                misere: false,
+                time_control: None,
+                commitment: None,
+                min_rating: None,
+                max_rating: None,
             }),
             (opponent(), GameConfigView { 
                 token_id: acc_cheddar(), 
                 deposit: U128(ONE_CHEDDAR), 
                 opponent_id: Some(user()), 
                 referrer_id: None,
-                created_at: 0 
+                created_at: 0,
+                mode: GameMode::Classic,
+                misere: false,
+                time_control: None,
+                commitment: None,
+                min_rating: None,
+                max_rating: None,
             }),
         ]));
         testing_env!(ctx
@@ -930,20 +1417,32 @@ mod tests {
     }
     #[test]
     fn test_game_basics() {
-        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(60 * 10));
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
         whitelist_token(&mut ctr);
         assert_eq!(ctr.get_whitelisted_tokens(), Vec::from([
             (acc_cheddar(), (ONE_CHEDDAR / 10).into())
         ]));
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
         let gc1 = GameConfigArgs { 
             opponent_id: Some(opponent()), 
-            referrer_id: Some(referrer()) 
+            referrer_id: Some(referrer()),
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
         };
         let msg1 = near_sdk::serde_json::to_string(&gc1).expect("err serialize");
         let gc2 = GameConfigArgs { 
             opponent_id: Some(user()), 
-            referrer_id: None 
+            referrer_id: None,
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
         };
         let msg2 = near_sdk::serde_json::to_string(&gc2).expect("err serialize");
         make_available_ft(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, msg1);
@@ -999,20 +1498,32 @@ mod tests {
 
     #[test]
     fn test_tie_scenario() {
-        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(60 * 10));
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
         whitelist_token(&mut ctr);
         assert_eq!(ctr.get_whitelisted_tokens(), Vec::from([
             (acc_cheddar(), (ONE_CHEDDAR / 10).into())
         ]));
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
         let gc1 = GameConfigArgs { 
             opponent_id: Some(opponent()), 
-            referrer_id: None 
+            referrer_id: None,
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
         };
         let msg1 = near_sdk::serde_json::to_string(&gc1).expect("err serialize");
         let gc2 = GameConfigArgs { 
             opponent_id: Some(user()), 
-            referrer_id: None 
+            referrer_id: None,
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
         };
         let msg2 = near_sdk::serde_json::to_string(&gc2).expect("err serialize");
         make_available_ft(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, msg1);
@@ -1073,20 +1584,32 @@ mod tests {
     #[test]
     #[should_panic(expected="Too early to stop the game")]
     fn test_stop_game_too_early() {
-        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(60 * 10));
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
         whitelist_token(&mut ctr);
         assert_eq!(ctr.get_whitelisted_tokens(), Vec::from([
             (acc_cheddar(), U128(ONE_CHEDDAR / 10))
         ]));
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
         let gc1 = GameConfigArgs { 
             opponent_id: Some(opponent()), 
-            referrer_id: None 
+            referrer_id: None,
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
         };
         let msg1 = near_sdk::serde_json::to_string(&gc1).expect("err serialize");
         let gc2 = GameConfigArgs { 
             opponent_id: Some(user()), 
-            referrer_id: None 
+            referrer_id: None,
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
         };
         let msg2 = near_sdk::serde_json::to_string(&gc2).expect("err serialize");
         make_available_ft(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, msg1);
@@ -1131,20 +1654,32 @@ mod tests {
     #[test]
     #[should_panic(expected="No access")]
     fn test_stop_game_wrong_access() {
-        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(60 * 10));
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
         whitelist_token(&mut ctr);
         assert_eq!(ctr.get_whitelisted_tokens(), Vec::from([
             (acc_cheddar(), (ONE_CHEDDAR / 10).into())
         ]));
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
         let gc1 = GameConfigArgs { 
             opponent_id: Some(opponent()), 
-            referrer_id: None 
+            referrer_id: None,
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
         };
         let msg1 = near_sdk::serde_json::to_string(&gc1).expect("err serialize");
         let gc2 = GameConfigArgs { 
             opponent_id: Some(user()), 
-            referrer_id: None 
+            referrer_id: None,
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
         };
         let msg2 = near_sdk::serde_json::to_string(&gc2).expect("err serialize");
         make_available_ft(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, msg1);
@@ -1188,12 +1723,12 @@ mod tests {
 
     // #[test]
     // fn test_expired_game() {
-    //     let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(60 * 10));
+    //     let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
     //     whitelist_token(&mut ctr);
     //     assert_eq!(ctr.get_whitelisted_tokens(), Vec::from([
     //         (acc_cheddar(), (ONE_CHEDDAR / 10).into())
     //     ]));
-    //     assert!(ctr.get_available_players().is_empty());
+    //     assert!(ctr.get_available_players(None).is_empty());
     //     let gc1 = GameConfigArgs { 
     //         opponent_id: Some(opponent()), 
     //         referrer_id: None 
@@ -1264,7 +1799,7 @@ mod tests {
     //     assert_eq!(ctr.get_whitelisted_tokens(), Vec::from([
     //         (acc_cheddar(), (ONE_CHEDDAR / 10).into())
     //     ]));
-    //     assert!(ctr.get_available_players().is_empty());
+    //     assert!(ctr.get_available_players(None).is_empty());
     //     let gc1 = GameConfigArgs { 
     //         opponent_id: Some(opponent()), 
     //         referrer_id: None 
@@ -1342,22 +1877,22 @@ mod tests {
 
         println!("ContractParams: {:#?}", ctr.get_contract_params());
         println!("TotalStatsNum: {:#?}", ctr.get_total_stats_num());
-        println!("AccountsPlayed: {:#?}", ctr.get_accounts_played());
+        println!("AccountsPlayed: {:#?}", ctr.get_accounts_played(0, 100));
         println!("UserPenalties: {:#?}", ctr.get_user_penalties(&user()));
 
-        println!("PenaltyUsers: {:#?}", ctr.get_penalty_users());
+        println!("PenaltyUsers: {:#?}", ctr.get_penalty_users(None, 100));
 
         make_available_near(&mut ctx, &mut ctr, &user(), ONE_NEAR, None, None);
         make_available_near(&mut ctx, &mut ctr, &opponent(), ONE_NEAR, None, None);
         make_available_near(&mut ctx, &mut ctr, &"third".parse().unwrap(), ONE_NEAR, None, None);
 
-        assert_eq!(ctr.get_available_players().len(), 3);
+        assert_eq!(ctr.get_available_players(None).len(), 3);
 
         testing_env!(ctx
             .block_timestamp(ctr.max_game_duration + MAX_TIME_TO_BE_AVAILABLE)
             .build()
         );
-        assert_eq!(ctr.get_available_players().len(), 3);
+        assert_eq!(ctr.get_available_players(None).len(), 3);
 
         // test ping expired players
         testing_env!(ctx
@@ -1366,8 +1901,8 @@ mod tests {
         );
         make_available_near(&mut ctx, &mut ctr, &"fourth".parse().unwrap(), ONE_NEAR, None, None);
 
-        assert_eq!(ctr.get_available_players().len(), 1);
-        assert_eq!(ctr.get_available_players()[0].0, "fourth".parse().unwrap());
+        assert_eq!(ctr.get_available_players(None).len(), 1);
+        assert_eq!(ctr.get_available_players(None)[0].0, "fourth".parse().unwrap());
 
 
         make_available_near(&mut ctx, &mut ctr, &user(), ONE_NEAR, None, None);
@@ -1437,7 +1972,7 @@ mod tests {
         assert!(new_looser_num_penalties - looser_num_penalties == 1);
 
         assert!(
-            ctr.get_penalty_users()
+            ctr.get_penalty_users(None, 100)
                 .iter()
                 .map(|(acc, pen)| acc.clone())
                 .collect::<Vec<AccountId>>()
@@ -1448,20 +1983,32 @@ mod tests {
     }
      #[test]
     fn test_claim_timeout_win() {
-        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(60 * 10));
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
         whitelist_token(&mut ctr);
         assert_eq!(ctr.get_whitelisted_tokens(), Vec::from([
             (acc_cheddar(), (ONE_CHEDDAR / 10).into())
         ]));
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
         let gc1 = GameConfigArgs { 
             opponent_id: Some(opponent()), 
-            referrer_id: None 
+            referrer_id: None,
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
         };
         let msg1 = near_sdk::serde_json::to_string(&gc1).expect("err serialize");
         let gc2 = GameConfigArgs { 
             opponent_id: Some(user()), 
-            referrer_id: None 
+            referrer_id: None,
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
         };
         let msg2 = near_sdk::serde_json::to_string(&gc2).expect("err serialize");
         make_available_ft(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, msg1);
@@ -1502,20 +2049,32 @@ mod tests {
     }
     #[test]
     fn test_claim_timeout_win_when_no_timeout() {
-        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(60 * 10));
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
         whitelist_token(&mut ctr);
         assert_eq!(ctr.get_whitelisted_tokens(), Vec::from([
             (acc_cheddar(), (ONE_CHEDDAR / 10).into())
         ]));
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
         let gc1 = GameConfigArgs { 
             opponent_id: Some(opponent()), 
-            referrer_id: None 
+            referrer_id: None,
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
         };
         let msg1 = near_sdk::serde_json::to_string(&gc1).expect("err serialize");
         let gc2 = GameConfigArgs { 
             opponent_id: Some(user()), 
-            referrer_id: None 
+            referrer_id: None,
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
         };
         let msg2 = near_sdk::serde_json::to_string(&gc2).expect("err serialize");
         make_available_ft(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, msg1);
@@ -1552,4 +2111,752 @@ mod tests {
         ctr.claim_timeout_win(&game_id);
         assert!(game.game_state == GameState::Active);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_ultimate_game() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        make_available_with_mode(&mut ctx, &mut ctr, &user(), ONE_NEAR, GameMode::Ultimate);
+        make_available_with_mode(&mut ctx, &mut ctr, &opponent(), ONE_NEAR, GameMode::Ultimate);
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        let game = ctr.internal_get_game(&game_id);
+        let player_1 = game.current_player_account_id();
+        let player_2 = game.next_player_account_id();
+        let piece_1 = game.current_piece;
+
+        // (sub-board, tile) pairs, tile is `row * 3 + col` in the sub-board
+        let moves = [
+            (0, 6), (6, 0), (0, 3), (3, 1), (1, 0), (0, 1), (1, 6), (6, 2), (2, 0), (0, 2),
+            (2, 6), (6, 6), (6, 4), (4, 0), (0, 0), (2, 2), (2, 3), (3, 0), (1, 3),
+        ];
+        let mut tiles = [[None; BOARD_SIZE]; BOARD_SIZE];
+        for (turn, (sub_board, tile)) in moves.iter().enumerate() {
+            let player = if turn % 2 == 0 { &player_1 } else { &player_2 };
+            tiles = make_ultimate_move(&mut ctx, &mut ctr, player, &game_id, *sub_board, tile / 3, tile % 3);
+            if turn == 14 {
+                // sub-board 0 is won, the tile on the meta-board is taken
+                // and the opponent can pick any sub-board
+                assert_eq!(tiles[0][0], Some(piece_1));
+                let game = ctr.internal_get_game(&game_id);
+                assert_eq!(game.ultimate.unwrap().next_sub_board, None);
+            }
+        }
+        // player 1 won sub-boards 0, 1 and 2
+        assert_eq!(tiles[0], [Some(piece_1); BOARD_SIZE]);
+        let game = ctr.get_game(&game_id);
+        assert!(matches!(game.game_result, GameResult::Win(winner) if winner == player_1));
+        let sub_boards = game.sub_boards.unwrap();
+        assert_eq!(sub_boards[1][1][0], Some(piece_1));
+        assert_eq!(sub_boards[6][2][0], Some(piece_1.other()));
+    }
+
+    #[test]
+    fn test_ultimate_game_duration() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        make_available_with_mode(&mut ctx, &mut ctr, &user(), ONE_NEAR, GameMode::Ultimate);
+        make_available_with_mode(&mut ctx, &mut ctr, &opponent(), ONE_NEAR, GameMode::Ultimate);
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        let game = ctr.internal_get_game(&game_id);
+        let player_1 = game.current_player_account_id();
+        let player_2 = game.next_player_account_id();
+
+        // every turn is within `max_turn_duration`, the game lasts longer than `max_game_duration`
+        let moves = [(0, 6), (6, 0), (0, 3), (3, 1), (1, 0), (0, 1), (1, 6), (6, 2), (2, 0), (0, 2)];
+        for (turn, (sub_board, tile)) in moves.iter().enumerate() {
+            let player = if turn % 2 == 0 { &player_1 } else { &player_2 };
+            testing_env!(ctx.block_timestamp(sec_to_nano(50 * (turn as u32 + 1))).build());
+            make_ultimate_move(&mut ctx, &mut ctr, player, &game_id, *sub_board, tile / 3, tile % 3);
+        }
+        assert!(env::block_timestamp() > ctr.max_game_duration);
+        ctr.internal_ping_expired_games(env::block_timestamp());
+        assert!(ctr.internal_get_game(&game_id).game_state == GameState::Active);
+    }
+
+    #[test]
+    #[should_panic(expected="You must play in sub-board 4")]
+    fn test_ultimate_wrong_sub_board() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        make_available_with_mode(&mut ctx, &mut ctr, &user(), ONE_NEAR, GameMode::Ultimate);
+        make_available_with_mode(&mut ctx, &mut ctr, &opponent(), ONE_NEAR, GameMode::Ultimate);
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        let game = ctr.internal_get_game(&game_id);
+        let player_1 = game.current_player_account_id();
+        let player_2 = game.next_player_account_id();
+        // the center tile sends the opponent to the center sub-board
+        make_ultimate_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 1, 1);
+        make_ultimate_move(&mut ctx, &mut ctr, &player_2, &game_id, 0, 0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected="Mismatched game modes! You: Classic, Opponent: Ultimate")]
+    fn test_start_game_mismatched_modes() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        make_available_near(&mut ctx, &mut ctr, &user(), ONE_NEAR, None, None);
+        make_available_with_mode(&mut ctx, &mut ctr, &opponent(), ONE_NEAR, GameMode::Ultimate);
+        start_game(&mut ctx, &mut ctr, &user(), &opponent());
+    }
+
+    #[test]
+    fn test_misere_line_loses() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        make_available_misere(&mut ctx, &mut ctr, &user(), ONE_NEAR);
+        make_available_misere(&mut ctx, &mut ctr, &opponent(), ONE_NEAR);
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        let game = ctr.internal_get_game(&game_id);
+        let player_1 = game.current_player_account_id();
+        let player_2 = game.next_player_account_id();
+
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 0);
+        make_move(&mut ctx, &mut ctr, &player_2, &game_id, 1, 0);
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 1);
+        make_move(&mut ctx, &mut ctr, &player_2, &game_id, 1, 1);
+        // player 1 completes the top row and loses
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 2);
+
+        let game = ctr.get_game(&game_id);
+        assert_eq!(game.game_result, GameResult::Win(player_2.clone()));
+        assert_eq!(game.reason, ResultReason::MisereLine);
+        assert_eq!(ctr.get_stats(&player_2).victories_num, 1);
+        assert_eq!(ctr.get_stats(&player_1).victories_num, 0);
+    }
+
+    #[test]
+    #[should_panic(expected="Mismatched misère rule! You: false, Opponent: true")]
+    fn test_start_game_mismatched_misere() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        make_available_near(&mut ctx, &mut ctr, &user(), ONE_NEAR, None, None);
+        make_available_misere(&mut ctx, &mut ctr, &opponent(), ONE_NEAR);
+        start_game(&mut ctx, &mut ctr, &user(), &opponent());
+    }
+
+    #[test]
+    fn test_qubic_game() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        make_available_with_mode(&mut ctx, &mut ctr, &user(), ONE_NEAR, GameMode::Qubic);
+        make_available_with_mode(&mut ctx, &mut ctr, &opponent(), ONE_NEAR, GameMode::Qubic);
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        let game = ctr.internal_get_game(&game_id);
+        let player_1 = game.current_player_account_id();
+        let player_2 = game.next_player_account_id();
+        let piece_1 = game.current_piece;
+
+        // player 1 takes the space diagonal, player 2 the bottom row of the first layer
+        for i in 0..3 {
+            make_qubic_move(&mut ctx, &mut ctr, &player_1, &game_id, CubeCoords { x: i, y: i, z: i });
+            make_qubic_move(&mut ctx, &mut ctr, &player_2, &game_id, CubeCoords { x: i, y: 3, z: 0 });
+        }
+        let layers = GameView::from(&ctr.internal_get_game(&game_id)).cube_layers.unwrap();
+        assert_eq!(layers.len(), 4);
+        assert_eq!(layers[2][2][2], Some(piece_1));
+        assert_eq!(layers[0][3][1], Some(piece_1.other()));
+
+        make_qubic_move(&mut ctx, &mut ctr, &player_1, &game_id, CubeCoords { x: 3, y: 3, z: 3 });
+        let game = ctr.get_game(&game_id);
+        assert_eq!(game.game_result, GameResult::Win(player_1));
+        assert_eq!(game.reason, ResultReason::Line);
+        assert_eq!(game.cube_layers.unwrap()[3][3][3], Some(piece_1));
+        assert_eq!(game.moves.len(), 7);
+        assert_eq!(game.moves[6].layer, Some(3));
+        assert_eq!(game.moves[6].sub_board, None);
+    }
+
+    fn offer_draw(ctx: &mut VMContextBuilder, ctr: &mut Contract, user: &AccountId, game_id: &GameId) {
+        testing_env!(ctx.predecessor_account_id(user.clone()).build());
+        ctr.offer_draw(game_id)
+    }
+
+    fn accept_draw(ctx: &mut VMContextBuilder, ctr: &mut Contract, user: &AccountId, game_id: &GameId) {
+        testing_env!(ctx.attached_deposit(ONE_YOCTO).predecessor_account_id(user.clone()).build());
+        ctr.accept_draw(game_id)
+    }
+
+    #[test]
+    fn test_accept_draw() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        make_available_near(&mut ctx, &mut ctr, &user(), ONE_NEAR, None, None);
+        make_available_near(&mut ctx, &mut ctr, &opponent(), ONE_NEAR, None, None);
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        let game = ctr.internal_get_game(&game_id);
+        let player_1 = game.current_player_account_id();
+        let player_2 = game.next_player_account_id();
+
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 1, 1);
+        // the offer survives the own move of the offering player
+        offer_draw(&mut ctx, &mut ctr, &player_2, &game_id);
+        make_move(&mut ctx, &mut ctr, &player_2, &game_id, 0, 0);
+        assert_eq!(ctr.get_active_games()[0].1.draw_offer, Some(player_2.clone()));
+        accept_draw(&mut ctx, &mut ctr, &player_1, &game_id);
+
+        let game = ctr.get_game(&game_id);
+        assert_eq!(game.game_result, GameResult::Tie);
+        assert_eq!(game.reason, ResultReason::DrawByAgreement);
+        assert_eq!(game.reward_or_tie_refund.balance.0, (2 * ONE_NEAR - 2 * ONE_NEAR / BASIS_P as u128 * MIN_FEES as u128) / 2);
+        assert!(ctr.get_active_games().is_empty());
+        assert_eq!(ctr.get_stats(&player_1).victories_num, 0);
+        assert_eq!(ctr.get_stats(&player_2).victories_num, 0);
+    }
+
+    #[test]
+    #[should_panic(expected="No draw offer from the opponent")]
+    fn test_draw_offer_expires() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        make_available_near(&mut ctx, &mut ctr, &user(), ONE_NEAR, None, None);
+        make_available_near(&mut ctx, &mut ctr, &opponent(), ONE_NEAR, None, None);
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        let game = ctr.internal_get_game(&game_id);
+        let player_1 = game.current_player_account_id();
+        let player_2 = game.next_player_account_id();
+
+        offer_draw(&mut ctx, &mut ctr, &player_2, &game_id);
+        // player 1 moves instead of accepting
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 1, 1);
+        assert_eq!(ctr.internal_get_game(&game_id).draw_offer, None);
+        accept_draw(&mut ctx, &mut ctr, &player_1, &game_id);
+    }
+
+    #[test]
+    fn test_clock_increment() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        let time_control = TimeControl { initial: 120, increment: 5 };
+        make_available_clock(&mut ctx, &mut ctr, &user(), ONE_NEAR, time_control);
+        make_available_clock(&mut ctx, &mut ctr, &opponent(), ONE_NEAR, time_control);
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        let game = ctr.internal_get_game(&game_id);
+        let player_1 = game.current_player_account_id();
+        let player_2 = game.next_player_account_id();
+
+        // longer than `max_turn_duration`, only the clock counts
+        testing_env!(ctx.block_timestamp(sec_to_nano(70)).build());
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 1, 1);
+        testing_env!(ctx.block_timestamp(sec_to_nano(100)).build());
+        make_move(&mut ctx, &mut ctr, &player_2, &game_id, 0, 0);
+        let game = ctr.internal_get_game(&game_id);
+        assert_eq!(game.game_state, GameState::Active);
+        assert_eq!(game.clock.unwrap().remaining, [55, 95]);
+
+        testing_env!(ctx.block_timestamp(sec_to_nano(130)).build());
+        assert_eq!(ctr.get_active_games()[0].1.time_left_sec, Some((25, 95)));
+    }
+
+    #[test]
+    fn test_clock_runs_out() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        let time_control = TimeControl { initial: 60, increment: 0 };
+        make_available_clock(&mut ctx, &mut ctr, &user(), ONE_NEAR, time_control);
+        make_available_clock(&mut ctx, &mut ctr, &opponent(), ONE_NEAR, time_control);
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        let game = ctr.internal_get_game(&game_id);
+        let player_1 = game.current_player_account_id();
+        let player_2 = game.next_player_account_id();
+
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 1, 1);
+        // the flag of player 2 fell, the late move doesn't count
+        testing_env!(ctx.block_timestamp(sec_to_nano(61)).build());
+        make_move(&mut ctx, &mut ctr, &player_2, &game_id, 0, 0);
+
+        let game = ctr.get_game(&game_id);
+        assert_eq!(game.game_result, GameResult::Win(player_1.clone()));
+        assert_eq!(game.reason, ResultReason::Timeout);
+        assert_eq!(game.board[0][0], None);
+        assert!(ctr.get_active_games().is_empty());
+        assert_eq!(ctr.get_stats(&player_2).penalties_num, 1);
+    }
+
+    #[test]
+    #[should_panic(expected="Mismatched time controls for players!")]
+    fn test_start_game_mismatched_time_controls() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        make_available_clock(&mut ctx, &mut ctr, &user(), ONE_NEAR, TimeControl { initial: 60, increment: 0 });
+        make_available_near(&mut ctx, &mut ctr, &opponent(), ONE_NEAR, None, None);
+        start_game(&mut ctx, &mut ctr, &user(), &opponent());
+    }
+
+    #[test]
+    #[should_panic(expected="clock increment must be at most 30sec")]
+    fn test_make_available_invalid_clock() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        make_available_clock(&mut ctx, &mut ctr, &user(), ONE_NEAR, TimeControl { initial: 60, increment: 31 });
+    }
+
+    #[test]
+    fn test_game_moves() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        make_available_near(&mut ctx, &mut ctr, &user(), ONE_NEAR, None, None);
+        make_available_near(&mut ctx, &mut ctr, &opponent(), ONE_NEAR, None, None);
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        let game = ctr.internal_get_game(&game_id);
+        let player_1 = game.current_player_account_id();
+        let player_2 = game.next_player_account_id();
+        let piece_1 = game.players[0].piece;
+
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 0);
+        testing_env!(ctx.block_timestamp(sec_to_nano(10)).build());
+        make_move(&mut ctx, &mut ctr, &player_2, &game_id, 1, 0);
+        let moves = ctr.get_game_moves(&game_id, 0, 10);
+        assert_eq!(moves, vec![
+            MoveRecord { row: 0, col: 0, sub_board: None, layer: None, piece: piece_1, timestamp_sec: 0 },
+            MoveRecord { row: 1, col: 0, sub_board: None, layer: None, piece: piece_1.other(), timestamp_sec: 10 },
+        ]);
+        assert_eq!(ctr.get_game_moves(&game_id, 1, 1), moves[1..].to_vec());
+
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 1);
+        make_move(&mut ctx, &mut ctr, &player_2, &game_id, 1, 1);
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 2);
+
+        // the finished game keeps the whole log
+        let game = ctr.get_game(&game_id);
+        assert_eq!(game.game_result, GameResult::Win(player_1.clone()));
+        assert_eq!(game.moves.len(), 5);
+        assert_eq!(game.moves[..2].to_vec(), moves);
+        assert_eq!(ctr.get_game_moves(&game_id, 4, 10), vec![
+            MoveRecord { row: 0, col: 2, sub_board: None, layer: None, piece: piece_1, timestamp_sec: 10 },
+        ]);
+    }
+
+    #[test]
+    fn test_coin_toss() {
+        let (mut ctx, mut ctr, game_id) = start_coin_toss_game((6, 3));
+        let game = ctr.internal_get_game(&game_id);
+        assert_eq!(game.game_state, GameState::NotStarted);
+        assert_eq!(game.coin_toss.unwrap().reveal_deadline, REVEAL_TIMEOUT);
+
+        reveal_secret(&mut ctx, &mut ctr, &user(), &game_id, 6);
+        assert_eq!(ctr.internal_get_game(&game_id).game_state, GameState::NotStarted);
+        testing_env!(ctx.block_timestamp(sec_to_nano(100)).build());
+        reveal_secret(&mut ctx, &mut ctr, &opponent(), &game_id, 3);
+
+        // 6 ^ 3 = 0b101: the opponent moves first with X
+        let game = ctr.internal_get_game(&game_id);
+        assert_eq!(game.game_state, GameState::Active);
+        assert_eq!(game.get_player_accounts(), (opponent(), user()));
+        assert_eq!(game.players[0].piece, Piece::X);
+        assert_eq!(game.players[1].piece, Piece::O);
+        assert_eq!(game.initiated_at, sec_to_nano(100));
+        let tiles = make_move(&mut ctx, &mut ctr, &opponent(), &game_id, 1, 1);
+        assert_eq!(tiles[1][1], Some(Piece::X));
+    }
+
+    #[test]
+    fn test_coin_toss_first_piece() {
+        let (mut ctx, mut ctr, game_id) = start_coin_toss_game((6, 4));
+        reveal_secret(&mut ctx, &mut ctr, &opponent(), &game_id, 4);
+        reveal_secret(&mut ctx, &mut ctr, &user(), &game_id, 6);
+
+        // 6 ^ 4 = 0b010: the user moves first with O
+        let game = ctr.internal_get_game(&game_id);
+        assert_eq!(game.get_player_accounts(), (user(), opponent()));
+        assert_eq!(game.players[0].piece, Piece::O);
+        assert_eq!(game.current_piece, Piece::O);
+        let tiles = make_move(&mut ctx, &mut ctr, &user(), &game_id, 0, 0);
+        assert_eq!(tiles[0][0], Some(Piece::O));
+    }
+
+    #[test]
+    #[should_panic(expected="Secret doesn't match the commitment")]
+    fn test_reveal_wrong_secret() {
+        let (mut ctx, mut ctr, game_id) = start_coin_toss_game((6, 3));
+        reveal_secret(&mut ctx, &mut ctr, &user(), &game_id, 3);
+    }
+
+    #[test]
+    #[should_panic(expected="Current game isn't active")]
+    fn test_make_move_before_coin_toss() {
+        let (mut ctx, mut ctr, game_id) = start_coin_toss_game((6, 3));
+        make_move(&mut ctx, &mut ctr, &user(), &game_id, 1, 1);
+    }
+
+    #[test]
+    fn test_coin_toss_forfeit() {
+        let (mut ctx, mut ctr, game_id) = start_coin_toss_game((6, 3));
+        reveal_secret(&mut ctx, &mut ctr, &user(), &game_id, 6);
+
+        testing_env!(ctx
+            .predecessor_account_id(user())
+            .block_timestamp(REVEAL_TIMEOUT)
+            .build());
+        ctr.claim_timeout_win(&game_id);
+        assert_eq!(ctr.internal_get_game(&game_id).game_state, GameState::NotStarted);
+
+        testing_env!(ctx.block_timestamp(REVEAL_TIMEOUT + 1).build());
+        ctr.claim_timeout_win(&game_id);
+        let game = ctr.get_game(&game_id);
+        assert_eq!(game.game_result, GameResult::Win(user()));
+        assert_eq!(game.reason, ResultReason::Timeout);
+        assert_eq!(ctr.get_stats(&opponent()).penalties_num, 1);
+        assert!(ctr.get_active_games().is_empty());
+    }
+
+    #[test]
+    fn test_coin_toss_not_revealed() {
+        let (mut ctx, mut ctr, game_id) = start_coin_toss_game((6, 3));
+        testing_env!(ctx
+            .predecessor_account_id(opponent())
+            .block_timestamp(REVEAL_TIMEOUT + 1)
+            .build());
+        ctr.claim_timeout_win(&game_id);
+
+        let game = ctr.get_game(&game_id);
+        assert_eq!(game.game_result, GameResult::Tie);
+        assert_eq!(game.reason, ResultReason::Timeout);
+        // the game never started: full refund without fee or rating
+        assert_eq!(game.reward_or_tie_refund.balance, U128(ONE_NEAR));
+        assert_eq!(ctr.get_rating(user(), None), Rating::default());
+        assert_eq!(ctr.get_stats(&user()).penalties_num, 0);
+        assert_eq!(ctr.get_stats(&opponent()).penalties_num, 0);
+        assert!(ctr.get_active_games().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected="Mismatched first player draws!")]
+    fn test_start_game_mismatched_commitments() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        make_available_commitment(&mut ctx, &mut ctr, &user(), ONE_NEAR, 6);
+        make_available_near(&mut ctx, &mut ctr, &opponent(), ONE_NEAR, None, None);
+        start_game(&mut ctx, &mut ctr, &user(), &opponent());
+    }
+
+    #[test]
+    fn test_make_available_ft_game_options() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        whitelist_token(&mut ctr);
+        let time_control = TimeControl { initial: 60, increment: 0 };
+        for (player, secret) in [(user(), 6), (opponent(), 3)] {
+            let args = GameConfigArgs {
+                opponent_id: None,
+                referrer_id: None,
+                mode: Some(GameMode::Ultimate),
+                misere: Some(true),
+                time_control: Some(time_control),
+                commitment: Some(CoinToss::commitment(&player, secret)),
+                min_rating: None,
+                max_rating: None,
+            };
+            let msg = near_sdk::serde_json::to_string(&args).expect("err serialize");
+            make_available_ft(&mut ctx, &mut ctr, &player, ONE_CHEDDAR, msg);
+        }
+        let config = ctr.available_players.get(&user()).unwrap();
+        assert_eq!(config.mode, GameMode::Ultimate);
+        assert!(config.misere);
+        assert_eq!(config.time_control, Some(time_control));
+
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        let game = ctr.internal_get_game(&game_id);
+        assert!(game.ultimate.is_some());
+        assert!(game.clock.is_some());
+        assert_eq!(game.game_state, GameState::NotStarted);
+        reveal_secret(&mut ctx, &mut ctr, &user(), &game_id, 6);
+        reveal_secret(&mut ctx, &mut ctr, &opponent(), &game_id, 3);
+        assert_eq!(ctr.internal_get_game(&game_id).game_state, GameState::Active);
+    }
+
+    #[test]
+    #[should_panic(expected="clock increment must be at most 30sec")]
+    fn test_make_available_ft_invalid_clock() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        whitelist_token(&mut ctr);
+        let msg = "{\"time_control\":{\"initial\":60,\"increment\":31}}".to_string();
+        make_available_ft(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, msg);
+    }
+
+    #[test]
+    fn test_ratings() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        make_available_near(&mut ctx, &mut ctr, &user(), ONE_NEAR, None, None);
+        make_available_near(&mut ctx, &mut ctr, &opponent(), ONE_NEAR, None, None);
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        let (player_1, player_2) = ctr.internal_get_game(&game_id).get_player_accounts();
+        assert_eq!(ctr.get_rating(player_1.clone(), None), Rating::default());
+
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 0);
+        make_move(&mut ctx, &mut ctr, &player_2, &game_id, 1, 0);
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 1);
+        make_move(&mut ctx, &mut ctr, &player_2, &game_id, 1, 1);
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 2);
+
+        let (rating_1, rating_2) = (ctr.get_rating(player_1.clone(), None), ctr.get_rating(player_2.clone(), None));
+        assert!(rating_1.rating > 1500.0 && rating_2.rating < 1500.0);
+        assert!((rating_1.rating - 1500.0 - (1500.0 - rating_2.rating)).abs() < 0.000_001);
+        assert!(rating_1.deviation < 350.0);
+        let history = ctr.get_rating_history(player_1.clone(), None);
+        assert_eq!(history.len(), 1);
+        assert_eq!((history[0].game_id, history[0].rating), (game_id, rating_1.rating));
+        // other modes are rated separately
+        let pool = RatingPool { mode: GameMode::Classic, misere: true };
+        assert_eq!(ctr.get_rating(player_1.clone(), Some(pool)), Rating::default());
+        let pool = RatingPool { mode: GameMode::Ultimate, misere: false };
+        assert_eq!(ctr.get_rating(player_1, Some(pool)), Rating::default());
+    }
+
+    #[test]
+    fn test_ratings_tie_and_timeout() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        make_available_misere(&mut ctx, &mut ctr, &user(), ONE_NEAR);
+        make_available_misere(&mut ctx, &mut ctr, &opponent(), ONE_NEAR);
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        let (player_1, player_2) = ctr.internal_get_game(&game_id).get_player_accounts();
+        offer_draw(&mut ctx, &mut ctr, &player_2, &game_id);
+        accept_draw(&mut ctx, &mut ctr, &player_1, &game_id);
+        let pool = Some(RatingPool { mode: GameMode::Classic, misere: true });
+        let rating_1 = ctr.get_rating(player_1.clone(), pool);
+        assert!((rating_1.rating - 1500.0).abs() < 0.000_001);
+        assert!(rating_1.deviation < 350.0);
+        assert_eq!(ctr.get_rating(player_1.clone(), None), Rating::default());
+
+        // player 1 doesn't move in the second game
+        make_available_misere(&mut ctx, &mut ctr, &user(), ONE_NEAR);
+        make_available_misere(&mut ctx, &mut ctr, &opponent(), ONE_NEAR);
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        let (loser, winner) = ctr.internal_get_game(&game_id).get_player_accounts();
+        testing_env!(ctx
+            .predecessor_account_id(winner.clone())
+            .block_timestamp(TIMEOUT_WIN + 1)
+            .build());
+        ctr.claim_timeout_win(&game_id);
+        assert!(ctr.get_rating(winner.clone(), pool).rating > 1500.0);
+        assert!(ctr.get_rating(loser, pool).rating < 1500.0);
+        assert_eq!(ctr.get_rating_history(winner, pool).len(), 2);
+    }
+
+    /// Plays a classic game won by the first player, returns the winner and the loser
+    fn rated_players() -> (VMContextBuilder, Contract, AccountId, AccountId) {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        let (winner, loser) = play_won_game(&mut ctx, &mut ctr);
+        (ctx, ctr, winner, loser)
+    }
+    /// the user and the opponent play a game of 1 NEAR each, returns the winner and the loser
+    fn play_won_game(ctx: &mut VMContextBuilder, ctr: &mut Contract) -> (AccountId, AccountId) {
+        make_available_near(ctx, ctr, &user(), ONE_NEAR, None, None);
+        make_available_near(ctx, ctr, &opponent(), ONE_NEAR, None, None);
+        let game_id = start_game(ctx, ctr, &user(), &opponent());
+        let (player_1, player_2) = ctr.internal_get_game(&game_id).get_player_accounts();
+        make_move(ctx, ctr, &player_1, &game_id, 0, 0);
+        make_move(ctx, ctr, &player_2, &game_id, 1, 0);
+        make_move(ctx, ctr, &player_1, &game_id, 0, 1);
+        make_move(ctx, ctr, &player_2, &game_id, 1, 1);
+        make_move(ctx, ctr, &player_1, &game_id, 0, 2);
+        (player_1, player_2)
+    }
+
+    #[test]
+    fn test_available_players_by_rating() {
+        let (mut ctx, mut ctr, winner, loser) = rated_players();
+        make_available_rating_bounds(&mut ctx, &mut ctr, &loser, ONE_NEAR, None, Some(1600));
+        make_available_rating_bounds(&mut ctx, &mut ctr, &winner, ONE_NEAR, Some(1600), None);
+        assert_eq!(ctr.get_available_players(None).len(), 2);
+        let offers = |ctr: &Contract, account_id: &AccountId| -> Vec<AccountId> {
+            ctr.get_available_players(Some(account_id.clone())).into_iter().map(|(offer_id, _)| offer_id).collect()
+        };
+        assert_eq!(offers(&ctr, &winner), vec![winner.clone()]);
+        assert_eq!(offers(&ctr, &loser), vec![loser.clone()]);
+        // unrated players are rated 1500
+        assert_eq!(offers(&ctr, &referrer()), vec![loser]);
+    }
+
+    #[test]
+    #[should_panic(expected="is outside the bounds of the offer: None..Some(1600)")]
+    fn test_start_game_rating_above_bound() {
+        let (mut ctx, mut ctr, winner, loser) = rated_players();
+        make_available_rating_bounds(&mut ctx, &mut ctr, &loser, ONE_NEAR, None, Some(1600));
+        make_available_near(&mut ctx, &mut ctr, &winner, ONE_NEAR, None, None);
+        start_game(&mut ctx, &mut ctr, &winner, &loser);
+    }
+
+    #[test]
+    #[should_panic(expected="is outside the bounds of the offer: None..Some(1600)")]
+    fn test_start_game_ft_rating_bound() {
+        let (mut ctx, mut ctr, winner, loser) = rated_players();
+        whitelist_token(&mut ctr);
+        let msg = near_sdk::serde_json::to_string(&GameConfigArgs {
+            opponent_id: None,
+            referrer_id: None,
+            mode: None,
+            misere: None,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: Some(1600),
+        }).expect("err serialize");
+        make_available_ft(&mut ctx, &mut ctr, &loser, ONE_CHEDDAR, msg);
+        assert_eq!(ctr.get_available_players(None)[0].1.max_rating, Some(1600));
+        make_available_ft(&mut ctx, &mut ctr, &winner, ONE_CHEDDAR, String::new());
+        start_game(&mut ctx, &mut ctr, &winner, &loser);
+    }
+
+    #[test]
+    #[should_panic(expected="min_rating 1600 is above max_rating 1400")]
+    fn test_make_available_invalid_rating_bounds() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        make_available_rating_bounds(&mut ctx, &mut ctr, &user(), ONE_NEAR, Some(1600), Some(1400));
+    }
+
+    #[test]
+    fn test_leaderboards() {
+        let (mut ctx, mut ctr, winner, loser) = rated_players();
+        let accounts = |ctr: &Contract, board: Leaderboard| -> Vec<AccountId> {
+            ctr.get_leaderboard(board, None, 10).into_iter().map(|entry| entry.account_id).collect()
+        };
+        assert_eq!(accounts(&ctr, Leaderboard::Victories), vec![winner.clone(), loser.clone()]);
+        assert_eq!(accounts(&ctr, Leaderboard::TotalReward), vec![winner.clone(), loser.clone()]);
+        assert_eq!(accounts(&ctr, Leaderboard::Rating(RatingPool::default())), vec![winner.clone(), loser.clone()]);
+        // the next page starts after the last entry of the first one
+        let first_page = ctr.get_leaderboard(Leaderboard::Victories, None, 1);
+        assert_eq!(ctr.get_leaderboard(Leaderboard::Victories, first_page.into_iter().last(), 10), vec![
+            LeaderboardEntry { rank: 2, account_id: loser.clone(), score: U128(0) },
+        ]);
+        assert_eq!(ctr.get_leaderboard_len(Leaderboard::WinRate), 0);
+
+        // the opponent doesn't reveal the secret in the second game and gets a penalty
+        make_available_commitment(&mut ctx, &mut ctr, &user(), ONE_NEAR, 6);
+        make_available_commitment(&mut ctx, &mut ctr, &opponent(), ONE_NEAR, 3);
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        reveal_secret(&mut ctx, &mut ctr, &user(), &game_id, 6);
+        testing_env!(ctx
+            .predecessor_account_id(user())
+            .block_timestamp(REVEAL_TIMEOUT + 1)
+            .build());
+        ctr.claim_timeout_win(&game_id);
+        let penalty_users = ctr.get_penalty_users(None, 10);
+        assert_eq!(penalty_users.len(), 1);
+        assert_eq!((&penalty_users[0].0, penalty_users[0].1.penalties_num), (&opponent(), 1));
+        // the user has more victories or ties with the opponent, ties are ordered by account id descending
+        assert_eq!(accounts(&ctr, Leaderboard::Victories), vec![user(), opponent()]);
+        assert_eq!(ctr.get_accounts_played(1, 10).len(), 1);
+    }
+
+    fn create_season(ctx: &mut VMContextBuilder, ctr: &mut Contract, start_sec: u32, end_sec: u32) -> SeasonId {
+        testing_env!(ctx.predecessor_account_id(ctx.context.current_account_id.clone()).build());
+        ctr.create_season(start_sec, end_sec, 5000, vec![7000, 3000])
+    }
+
+    #[test]
+    fn test_season_prizes() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        let season_id = create_season(&mut ctx, &mut ctr, 0, 100);
+        assert_eq!(ctr.get_current_season().map(|(season_id, _)| season_id), Some(season_id));
+        let (winner, loser) = play_won_game(&mut ctx, &mut ctr);
+
+        let fee = 2 * ONE_NEAR / BASIS_P as u128 * MIN_FEES as u128;
+        let prize_pool = fee / BASIS_P as u128 * 5000;
+        assert_eq!(ctr.get_season(season_id).prize_pools, vec![(near(), U128(prize_pool))]);
+        assert_eq!(ctr.get_season_stats(season_id, winner.clone()), SeasonStats { games_num: 1, victories_num: 1 });
+        assert_eq!(ctr.get_season_stats(season_id, loser.clone()), SeasonStats { games_num: 1, victories_num: 0 });
+
+        testing_env!(ctx.block_timestamp(sec_to_nano(100)).build());
+        assert!(ctr.get_current_season().is_none());
+        let first_prize = prize_pool / BASIS_P as u128 * 7000;
+        let second_prize = prize_pool / BASIS_P as u128 * 3000;
+        testing_env!(ctx.predecessor_account_id(winner.clone()).build());
+        assert_eq!(ctr.claim_season_prize(season_id), vec![(near(), U128(first_prize))]);
+        assert_eq!(ctr.get_season_results(season_id), vec![
+            SeasonPrize { rank: 1, account_id: winner.clone(), prizes: vec![(near(), U128(first_prize))], claimed: true },
+            SeasonPrize { rank: 2, account_id: loser.clone(), prizes: vec![(near(), U128(second_prize))], claimed: false },
+        ]);
+        testing_env!(ctx.predecessor_account_id(loser.clone()).build());
+        assert_eq!(ctr.claim_season_prize(season_id), vec![(near(), U128(second_prize))]);
+        assert_eq!(ctr.get_season(season_id).claimed, vec![winner, loser]);
+    }
+
+    #[test]
+    #[should_panic(expected="The season isn't over")]
+    fn test_claim_season_prize_too_early() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        let season_id = create_season(&mut ctx, &mut ctr, 0, 100);
+        let (winner, _) = play_won_game(&mut ctx, &mut ctr);
+        testing_env!(ctx.predecessor_account_id(winner).build());
+        ctr.claim_season_prize(season_id);
+    }
+
+    #[test]
+    #[should_panic(expected="Season overlaps season 0")]
+    fn test_create_overlapping_season() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MAX_GAME_DURATION_SEC));
+        create_season(&mut ctx, &mut ctr, 0, 100);
+        create_season(&mut ctx, &mut ctr, 50, 150);
+    }
+
+    #[test]
+    fn test_migrate() {
+        let mut ctx = VMContextBuilder::new();
+        testing_env!(ctx.predecessor_account_id(ctx.context.current_account_id.clone()).build());
+        let mut tiles = [[None; BOARD_SIZE]; BOARD_SIZE];
+        tiles[1][1] = Some(Piece::O);
+        tiles[0][0] = Some(Piece::X);
+        let reward = GameDeposit { token_id: acc_cheddar(), balance: U128(2 * ONE_CHEDDAR) };
+        let mut old = ContractV1 {
+            whitelisted_tokens: UnorderedMap::new(StorageKey::WhitelistedTokens),
+            games: UnorderedMap::new(StorageKey::Games),
+            available_players: UnorderedMap::new(StorageKey::Players),
+            stats: UnorderedMap::new(StorageKey::Stats),
+            next_game_id: 2,
+            service_fee_percentage: MIN_FEES,
+            max_game_duration: sec_to_nano(MAX_GAME_DURATION_SEC),
+            referrer_ratio: BASIS_P / 2,
+            last_update_timestamp: 0,
+            max_turn_duration: sec_to_nano(60),
+            max_stored_games: 50,
+            stored_games: UnorderedMap::new(StorageKey::StoredGames),
+        };
+        for (account_id, penalties_num) in [(user(), 1), (opponent(), 2)] {
+            old.stats.insert(&account_id, &Stats {
+                referrer_id: None,
+                affiliates: UnorderedSet::new(StorageKey::Affiliates { account_id: account_id.clone() }),
+                games_num: 4,
+                victories_num: 1,
+                penalties_num,
+                total_reward: 0,
+                total_affiliate_reward: 0,
+            });
+        }
+        old.games.insert(&0, &GameV1 {
+            game_state: GameState::Active,
+            players: vec![
+                Player { piece: Piece::O, account_id: user() },
+                Player { piece: Piece::X, account_id: opponent() },
+            ],
+            current_piece: Piece::O,
+            current_player_index: 0,
+            reward: reward.clone(),
+            board: BoardV1 { tiles, current_piece: Piece::O, winner: None },
+            total_turns: 2,
+            initiated_at: 0,
+            last_turn_timestamp: 0,
+            current_duration: 0,
+        });
+        old.available_players.insert(&referrer(), &GameConfigV1 {
+            token_id: acc_cheddar(),
+            deposit: ONE_CHEDDAR,
+            opponent_id: None,
+            referrer_id: None,
+            created_at: 0,
+        });
+        tiles[0][2] = Some(Piece::O);
+        tiles[2][0] = Some(Piece::O);
+        old.stored_games.insert(&1, &GameLimitedViewV1 {
+            game_result: GameResult::Win(user()),
+            player1: user(),
+            player2: opponent(),
+            reward_or_tie_refund: reward,
+            board: tiles,
+        });
+        env::state_write(&old);
+
+        let mut ctr = Contract::migrate();
+        assert_eq!(ctr.get_current_tiles(&0)[1][1], Some(Piece::O));
+        assert_eq!(ctr.get_active_games()[0].1.current_player.account_id, user());
+        let offers = ctr.get_available_players(None);
+        assert_eq!(offers.len(), 1);
+        assert_eq!(offers[0].1.mode, GameMode::Classic);
+        let stored = ctr.get_game(&1);
+        assert_eq!(stored.reason, ResultReason::Line);
+        assert_eq!(stored.board, tiles);
+
+        // 1.0.0 accounts are listed once the leaderboards are backfilled
+        assert!(ctr.get_penalty_users(None, 10).is_empty());
+        assert_eq!(ctr.backfill_leaderboards(0, 1), U64(1));
+        assert_eq!(ctr.backfill_leaderboards(1, 1), U64(0));
+        let penalty_users: Vec<AccountId> = ctr.get_penalty_users(None, 10).into_iter().map(|(account_id, _)| account_id).collect();
+        assert_eq!(penalty_users, vec![opponent(), user()]);
+        let next_page: Vec<AccountId> = ctr.get_penalty_users(Some(opponent()), 10).into_iter().map(|(account_id, _)| account_id).collect();
+        assert_eq!(next_page, vec![user()]);
+        assert_eq!(ctr.get_leaderboard_len(Leaderboard::Victories), 2);
+    }
+}
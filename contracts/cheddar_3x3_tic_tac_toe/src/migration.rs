@@ -0,0 +1,183 @@
+use crate::*;
+use crate::views::GameResult;
+
+/// `Board` of the 1.0.0 contract, before misère
+#[derive(BorshSerialize, BorshDeserialize)]
+pub(crate) struct BoardV1 {
+    pub tiles: [[Option<Piece>; BOARD_SIZE]; BOARD_SIZE],
+    pub current_piece: Piece,
+    pub winner: Option<Winner>,
+}
+
+/// `Game` of the 1.0.0 contract
+#[derive(BorshSerialize, BorshDeserialize)]
+pub(crate) struct GameV1 {
+    pub game_state: GameState,
+    pub players: Vec<Player>,
+    pub current_piece: Piece,
+    pub current_player_index: u8,
+    pub reward: GameDeposit,
+    pub board: BoardV1,
+    pub total_turns: u8,
+    pub initiated_at: u64,
+    pub last_turn_timestamp: u64,
+    pub current_duration: Duration,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub(crate) struct GameConfigV1 {
+    pub token_id: TokenContractId,
+    pub deposit: Balance,
+    pub opponent_id: Option<AccountId>,
+    pub referrer_id: Option<AccountId>,
+    pub created_at: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub(crate) struct GameLimitedViewV1 {
+    pub game_result: GameResult,
+    pub player1: AccountId,
+    pub player2: AccountId,
+    pub reward_or_tie_refund: GameDeposit,
+    pub board: [[Option<Piece>; BOARD_SIZE]; BOARD_SIZE],
+}
+
+/// State of the 1.0.0 contract
+#[derive(BorshSerialize, BorshDeserialize)]
+pub(crate) struct ContractV1 {
+    pub whitelisted_tokens: UnorderedMap<TokenContractId, MinDeposit>,
+    pub games: UnorderedMap<GameId, GameV1>,
+    pub available_players: UnorderedMap<AccountId, GameConfigV1>,
+    pub stats: UnorderedMap<AccountId, Stats>,
+    pub next_game_id: GameId,
+    pub service_fee_percentage: u32,
+    pub max_game_duration: Duration,
+    pub referrer_ratio: u32,
+    pub last_update_timestamp: u64,
+    pub max_turn_duration: u64,
+    pub max_stored_games: u8,
+    pub stored_games: UnorderedMap<GameId, GameLimitedViewV1>,
+}
+
+impl From<GameV1> for Game {
+    /// 1.0.0 didn't record the order of the moves, `moves` starts empty
+    fn from(game: GameV1) -> Self {
+        Game {
+            game_state: game.game_state,
+            players: game.players,
+            current_piece: game.current_piece,
+            current_player_index: game.current_player_index,
+            reward: game.reward,
+            board: Board {
+                tiles: game.board.tiles,
+                current_piece: game.board.current_piece,
+                winner: game.board.winner,
+                rules: RULES,
+            },
+            total_turns: game.total_turns,
+            initiated_at: game.initiated_at,
+            last_turn_timestamp: game.last_turn_timestamp,
+            current_duration: game.current_duration,
+            ultimate: None,
+            cube: None,
+            draw_offer: None,
+            clock: None,
+            moves: Vec::new(),
+            coin_toss: None,
+        }
+    }
+}
+
+impl From<GameConfigV1> for GameConfig {
+    fn from(config: GameConfigV1) -> Self {
+        GameConfig {
+            token_id: config.token_id,
+            deposit: config.deposit,
+            opponent_id: config.opponent_id,
+            referrer_id: config.referrer_id,
+            created_at: config.created_at,
+            mode: GameMode::Classic,
+            misere: false,
+            time_control: None,
+            commitment: None,
+            min_rating: None,
+            max_rating: None,
+        }
+    }
+}
+
+impl From<GameLimitedViewV1> for GameLimitedView {
+    /// 1.0.0 didn't record the reason of a result: a win is a `Line` if the board
+    /// has one, otherwise the loser gave up or ran out of time (`Timeout`)
+    fn from(view: GameLimitedViewV1) -> Self {
+        let reason = match &view.game_result {
+            GameResult::Tie => ResultReason::NoMovesLeft,
+            GameResult::Win(_) => {
+                let has_line = (0..RULES.height())
+                    .flat_map(|y| (0..RULES.width()).map(move |x| Coords { x, y }))
+                    .any(|coords| match view.board.piece(&coords) {
+                        Some(piece) => RULES.is_winning_move(&view.board, &coords, piece),
+                        None => false,
+                    });
+                if has_line { ResultReason::Line } else { ResultReason::Timeout }
+            }
+        };
+        GameLimitedView {
+            game_result: view.game_result,
+            reason,
+            player1: view.player1,
+            player2: view.player2,
+            reward_or_tie_refund: view.reward_or_tie_refund,
+            board: view.board,
+            sub_boards: None,
+            cube_layers: None,
+            moves: Vec::new(),
+        }
+    }
+}
+
+/// Rewrites every value of `map` under the same storage prefix with the new layout
+fn migrate_map<K, V1, V>(mut map: UnorderedMap<K, V1>, prefix: StorageKey) -> UnorderedMap<K, V>
+where
+    K: BorshSerialize + BorshDeserialize,
+    V1: BorshSerialize + BorshDeserialize,
+    V: BorshSerialize + BorshDeserialize + From<V1>,
+{
+    let entries = map.to_vec();
+    map.clear();
+    let mut new_map = UnorderedMap::new(prefix);
+    for (key, value) in entries {
+        new_map.insert(&key, &V::from(value));
+    }
+    new_map
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Upgrades the state of the 1.0.0 contract. In-flight games, open offers and
+    /// stored games are rewritten as classic games without misère, clock or coin toss.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let old: ContractV1 = env::state_read().expect("Contract state not found");
+        Self {
+            whitelisted_tokens: old.whitelisted_tokens,
+            games: migrate_map(old.games, StorageKey::Games),
+            available_players: migrate_map(old.available_players, StorageKey::Players),
+            stats: old.stats,
+            next_game_id: old.next_game_id,
+            service_fee_percentage: old.service_fee_percentage,
+            max_game_duration: old.max_game_duration,
+            referrer_ratio: old.referrer_ratio,
+            last_update_timestamp: old.last_update_timestamp,
+            max_turn_duration: old.max_turn_duration,
+            max_stored_games: old.max_stored_games,
+            stored_games: migrate_map(old.stored_games, StorageKey::StoredGames),
+            ratings: UnorderedMap::new(StorageKey::Ratings),
+            leaderboards: UnorderedMap::new(StorageKey::Leaderboards),
+            seasons: UnorderedMap::new(StorageKey::Seasons),
+            next_season_id: 0,
+            season_stats: UnorderedMap::new(StorageKey::SeasonStats),
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use crate::*;
+
+pub type SeasonId = u64;
+
+/// Most prize places of a season
+pub(crate) const MAX_SEASON_PRIZES: usize = 100;
+
+/// Owner-defined season: a share of the service fee of every game settled during the
+/// season goes to its prize pool, the top accounts of `Leaderboard::Season` claim it once it ends
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct Season {
+    pub start_sec: u32,
+    /// the season runs until `end_sec` excluded
+    pub end_sec: u32,
+    /// share of the service fee kept by the contract, after the referrer fee, in `BASIS_P`
+    pub fee_share: u32,
+    /// share of the prize pool of each place in `BASIS_P`, the first place first
+    pub prizes: Vec<u32>,
+    /// prize pool in each reward token
+    pub prize_pools: Vec<(TokenContractId, Balance)>,
+    /// accounts which claimed their prize
+    pub claimed: Vec<AccountId>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct SeasonView {
+    pub start_sec: u32,
+    pub end_sec: u32,
+    pub fee_share: u32,
+    pub prizes: Vec<u32>,
+    pub prize_pools: Vec<(TokenContractId, U128)>,
+    pub claimed: Vec<AccountId>,
+}
+
+impl From<&Season> for SeasonView {
+    fn from(season: &Season) -> Self {
+        Self {
+            start_sec: season.start_sec,
+            end_sec: season.end_sec,
+            fee_share: season.fee_share,
+            prizes: season.prizes.clone(),
+            prize_pools: season.prize_pools.iter().map(|(token_id, pool)| (token_id.clone(), (*pool).into())).collect(),
+            claimed: season.claimed.clone(),
+        }
+    }
+}
+
+/// Stats of an account in a season, the lifetime ones are in `Stats`
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct SeasonStats {
+    pub games_num: u64,
+    pub victories_num: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct SeasonPrize {
+    /// 1 for the first place
+    pub rank: u64,
+    pub account_id: AccountId,
+    pub prizes: Vec<(TokenContractId, U128)>,
+    pub claimed: bool,
+}
+
+impl Season {
+    pub fn is_active(&self, timestamp_sec: u32) -> bool {
+        (self.start_sec..self.end_sec).contains(&timestamp_sec)
+    }
+
+    /// Prize of the place `rank` in each reward token, 0 for the first place
+    pub fn prizes(&self, rank: usize) -> Vec<(TokenContractId, Balance)> {
+        self.prize_pools
+            .iter()
+            .map(|(token_id, pool)| {
+                let prize = pool
+                    .checked_div(BASIS_P.into())
+                    .unwrap_or(0)
+                    .checked_mul(self.prizes[rank] as u128)
+                    .unwrap_or(0);
+                (token_id.clone(), prize)
+            })
+            .collect()
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Schedules a season in `start_sec..end_sec`, seasons can't overlap.
+    /// `fee_share` and `prizes` are in `BASIS_P`, see `Season`.
+    #[private]
+    pub fn create_season(&mut self, start_sec: u32, end_sec: u32, fee_share: u32, prizes: Vec<u32>) -> SeasonId {
+        assert!(start_sec < end_sec, "Season must end after it starts");
+        validate_season(fee_share, &prizes);
+        if let Some(last_season_id) = self.next_season_id.checked_sub(1) {
+            let last_season = self.internal_get_season(last_season_id);
+            assert!(start_sec >= last_season.end_sec, "Season overlaps season {}", last_season_id);
+        }
+        let season_id = self.next_season_id;
+        self.seasons.insert(&season_id, &Season {
+            start_sec,
+            end_sec,
+            fee_share,
+            prizes,
+            prize_pools: vec![],
+            claimed: vec![],
+        });
+        self.next_season_id += 1;
+        season_id
+    }
+
+    /// Transfers the prizes of the caller's place in an ended season
+    pub fn claim_season_prize(&mut self, season_id: SeasonId) -> Vec<(TokenContractId, U128)> {
+        let account_id = env::predecessor_account_id();
+        let mut season = self.internal_get_season(season_id);
+        assert!(nano_to_sec(env::block_timestamp()) >= season.end_sec, "The season isn't over");
+        assert!(!season.claimed.contains(&account_id), "The prize is already claimed");
+        let rank = self
+            .get_leaderboard(Leaderboard::Season(season_id), None, season.prizes.len() as u64)
+            .iter()
+            .position(|entry| entry.account_id == account_id)
+            .expect("Not in the prize places of the season");
+        season.claimed.push(account_id.clone());
+        self.seasons.insert(&season_id, &season);
+
+        season
+            .prizes(rank)
+            .into_iter()
+            .filter(|(_, prize)| *prize > 0)
+            .map(|(token_id, prize)| {
+                log!("@{} claimed the prize of place {} in season {}: {} of {}", account_id, rank + 1, season_id, prize, token_id);
+                self.internal_transfer(&token_id, &account_id, prize.into());
+                (token_id, prize.into())
+            })
+            .collect()
+    }
+
+    pub fn get_season(&self, season_id: SeasonId) -> SeasonView {
+        SeasonView::from(&self.internal_get_season(season_id))
+    }
+
+    /// Season running now, if any
+    pub fn get_current_season(&self) -> Option<(SeasonId, SeasonView)> {
+        let season_id = self.internal_current_season_id(nano_to_sec(env::block_timestamp()))?;
+        Some((season_id, self.get_season(season_id)))
+    }
+
+    pub fn get_seasons(&self, from_index: u64, limit: u64) -> Vec<(SeasonId, SeasonView)> {
+        (from_index..self.next_season_id)
+            .take(limit as usize)
+            .map(|season_id| (season_id, self.get_season(season_id)))
+            .collect()
+    }
+
+    pub fn get_season_stats(&self, season_id: SeasonId, account_id: AccountId) -> SeasonStats {
+        self.season_stats.get(&(season_id, account_id)).unwrap_or_default()
+    }
+
+    /// Prize places of a season, final once the season ends
+    pub fn get_season_results(&self, season_id: SeasonId) -> Vec<SeasonPrize> {
+        let season = self.internal_get_season(season_id);
+        self.get_leaderboard(Leaderboard::Season(season_id), None, season.prizes.len() as u64)
+            .into_iter()
+            .enumerate()
+            .map(|(rank, entry)| SeasonPrize {
+                rank: entry.rank,
+                prizes: season.prizes(rank).into_iter().map(|(token_id, prize)| (token_id, prize.into())).collect(),
+                claimed: season.claimed.contains(&entry.account_id),
+                account_id: entry.account_id,
+            })
+            .collect()
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_get_season(&self, season_id: SeasonId) -> Season {
+        self.seasons.get(&season_id).expect("Season not found")
+    }
+
+    /// Seasons don't overlap, the running one is the latest started one if it didn't end
+    pub(crate) fn internal_current_season_id(&self, timestamp_sec: u32) -> Option<SeasonId> {
+        (0..self.next_season_id)
+            .rev()
+            .find(|season_id| self.internal_get_season(*season_id).start_sec <= timestamp_sec)
+            .filter(|season_id| self.internal_get_season(*season_id).is_active(timestamp_sec))
+    }
+
+    /// Counts a settled game in the season stats of both players
+    pub(crate) fn internal_record_season_game(&mut self, players: &(AccountId, AccountId), winner: Option<&AccountId>) {
+        let season_id = match self.internal_current_season_id(nano_to_sec(env::block_timestamp())) {
+            Some(season_id) => season_id,
+            None => return,
+        };
+        for account_id in [&players.0, &players.1] {
+            let key = (season_id, account_id.clone());
+            let mut stats = self.season_stats.get(&key).unwrap_or_default();
+            let old_score = (stats.games_num > 0).then(|| stats.victories_num.into());
+            stats.games_num += 1;
+            if winner == Some(account_id) {
+                stats.victories_num += 1;
+            }
+            self.season_stats.insert(&key, &stats);
+            self.internal_update_leaderboard(Leaderboard::Season(season_id), account_id, old_score, Some(stats.victories_num.into()));
+        }
+    }
+
+    /// Adds the `fee_share` of `fee` to the `token_id` prize pool of the running season
+    pub(crate) fn internal_fund_season(&mut self, token_id: &TokenContractId, fee: Balance) {
+        let season_id = match self.internal_current_season_id(nano_to_sec(env::block_timestamp())) {
+            Some(season_id) => season_id,
+            None => return,
+        };
+        let mut season = self.internal_get_season(season_id);
+        let amount = fee
+            .checked_div(BASIS_P.into())
+            .unwrap_or(0)
+            .checked_mul(season.fee_share as u128)
+            .unwrap_or(0);
+        match season.prize_pools.iter_mut().find(|(pool_token_id, _)| pool_token_id == token_id) {
+            Some((_, pool)) => *pool += amount,
+            None => season.prize_pools.push((token_id.clone(), amount)),
+        }
+        self.seasons.insert(&season_id, &season);
+    }
+}
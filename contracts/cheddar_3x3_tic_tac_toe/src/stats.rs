@@ -61,9 +61,12 @@ impl Contract {
         self.stats.len() as _
     }
     /// return vector of accounts played game even once upon a time
-    pub fn get_accounts_played(&self) -> Vec<AccountId> {
+    pub fn get_accounts_played(&self, from_index: u64, limit: u64) -> Vec<AccountId> {
         self.stats
-            .keys()
+            .keys_as_vector()
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
             .collect()
     }
 }
@@ -97,6 +100,7 @@ impl Contract {
         balance: Option<Balance>
     ) {
             let mut stats = self.internal_get_stats(account_id);
+            let old_scores = Leaderboard::STATS.map(|board| stats.leaderboard_score(board));
             match action {
                 UpdateStatsAction::AddPlayedGame => {
                     stats.games_num += 1;
@@ -127,5 +131,8 @@ impl Contract {
                 },
             }
             self.stats.insert(account_id, &stats);
+            for (board, old_score) in Leaderboard::STATS.iter().zip(old_scores.iter()) {
+                self.internal_update_leaderboard(*board, account_id, *old_score, stats.leaderboard_score(*board));
+            }
     }
 }
\ No newline at end of file
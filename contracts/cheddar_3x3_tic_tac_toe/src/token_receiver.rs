@@ -12,7 +12,17 @@ pub trait ExtFungibleToken {
 #[serde(crate="near_sdk::serde")]
 pub struct GameConfigArgs {
     pub opponent_id: Option<AccountId>,
-    pub referrer_id: Option<AccountId>
+    pub referrer_id: Option<AccountId>,
+    /// `GameMode::Classic` if not set
+    pub mode: Option<GameMode>,
+    /// if true, completing a line loses; false by default
+    pub misere: Option<bool>,
+    /// chess clock in seconds, see `GameConfigNear::time_control`
+    pub time_control: Option<TimeControl>,
+    /// coin toss commitment, see `GameConfigNear::commitment`
+    pub commitment: Option<Base58CryptoHash>,
+    pub min_rating: Option<u32>,
+    pub max_rating: Option<u32>,
 }
 
 /// FT Receiver
@@ -53,6 +63,10 @@ impl FungibleTokenReceiver for Contract {
             GameConfig::with_only_token_params(&token_id, amount.0)
         } else {
             let game_args:GameConfigArgs = near_sdk::serde_json::from_str(&msg).expect("Config is invalid");
+            if let Some(time_control) = &game_args.time_control {
+                validate_time_control(time_control);
+            }
+            validate_rating_bounds(game_args.min_rating, game_args.max_rating);
             GameConfig::from_transfer_msg(&token_id, amount.0, &game_args)
         };
 
@@ -89,7 +103,13 @@ impl Contract {
                 deposit: amount,
                 opponent_id: game_config.opponent_id,
                 referrer_id,
-                created_at: env::block_timestamp()
+                created_at: env::block_timestamp(),
+                mode: game_config.mode,
+                misere: game_config.misere,
+                time_control: game_config.time_control,
+                commitment: game_config.commitment,
+                min_rating: game_config.min_rating,
+                max_rating: game_config.max_rating,
             }
         );
         
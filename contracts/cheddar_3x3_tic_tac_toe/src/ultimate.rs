@@ -0,0 +1,128 @@
+use crate::*;
+
+/// Number of sub-boards in the ultimate mode
+pub(crate) const SUB_BOARDS_NUM: u8 = (BOARD_SIZE * BOARD_SIZE) as u8;
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum GameMode {
+    /// single 3x3 board
+    #[default]
+    Classic,
+    /// 3x3 grid of 3x3 sub-boards, winning three sub-boards in a row wins the game
+    Ultimate,
+    /// 4x4x4 cube, four in a row along any line wins the game
+    Qubic,
+}
+
+impl GameMode {
+    /// Most turns a game can last
+    pub fn max_turns(&self) -> u64 {
+        match self {
+            GameMode::Classic => MAX_NUM_TURNS,
+            GameMode::Ultimate => MAX_NUM_TURNS * SUB_BOARDS_NUM as u64,
+            GameMode::Qubic => CUBE_SIZE.pow(3) as u64,
+        }
+    }
+}
+
+/// Sub-boards of the ultimate mode. `Game.board` is the meta-board:
+/// its tiles hold the winners of the sub-boards. Sub-boards always use
+/// `RULES`, misère only applies to lines on the meta-board.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct UltimateBoard {
+    /// sub-boards row by row, index is `row * BOARD_SIZE + col` on the meta-board
+    pub(crate) sub_boards: Vec<Board>,
+    /// sub-board the next move must be played in,
+    /// `None` if any unfinished sub-board can be picked
+    pub(crate) next_sub_board: Option<u8>,
+}
+
+impl UltimateBoard {
+    pub fn new(player_1: &Player, player_2: &Player) -> Self {
+        Self {
+            sub_boards: (0..SUB_BOARDS_NUM).map(|_| Board::new(player_1, player_2, RULES)).collect(),
+            next_sub_board: None,
+        }
+    }
+
+    /// Checks if the move can be played in `sub_board`,
+    /// tile errors are returned as for a single `Board`
+    pub fn check_move(&self, sub_board: u8, row: u8, col: u8) -> Result<(), MoveError> {
+        if sub_board >= SUB_BOARDS_NUM {
+            return Err(MoveError::InvalidSubBoard { sub_board });
+        }
+        if let Some(required) = self.next_sub_board {
+            if sub_board != required {
+                return Err(MoveError::WrongSubBoard { sub_board, required });
+            }
+        }
+        let board = &self.sub_boards[sub_board as usize];
+        if board.winner.is_some() {
+            return Err(MoveError::SubBoardFinished { sub_board });
+        }
+        board.check_move(row, col)
+    }
+
+    /// Places `piece` on a checked tile and picks the sub-board for the next move.
+    /// Returns the winner of `sub_board` if this move decided it.
+    pub fn make_move(&mut self, sub_board: u8, row: u8, col: u8, piece: Piece) -> Option<Winner> {
+        let board = &mut self.sub_boards[sub_board as usize];
+        board.tiles[row as usize][col as usize] = Some(piece);
+        board.update_winner(row, col);
+        let winner = board.winner;
+
+        // the tile played in points to the opponent's sub-board
+        let next_sub_board = row * BOARD_SIZE as u8 + col;
+        self.next_sub_board = if self.sub_boards[next_sub_board as usize].winner.is_none() {
+            Some(next_sub_board)
+        } else {
+            None
+        };
+        winner
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.sub_boards.iter().all(|board| board.winner.is_some())
+    }
+
+    pub fn tiles(&self) -> Vec<[[Option<Piece>; BOARD_SIZE]; BOARD_SIZE]> {
+        self.sub_boards.iter().map(|board| board.tiles).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ultimate_board() -> UltimateBoard {
+        UltimateBoard::new(
+            &Player::new(Piece::O, "user".parse().unwrap()),
+            &Player::new(Piece::X, "opponent.near".parse().unwrap()),
+        )
+    }
+
+    #[test]
+    fn check_move_errors() {
+        let mut board = ultimate_board();
+        assert_eq!(board.check_move(9, 0, 0), Err(MoveError::InvalidSubBoard { sub_board: 9 }));
+        // the center tile sends the opponent to the center sub-board
+        board.make_move(0, 1, 1, Piece::O);
+        assert_eq!(board.check_move(0, 0, 0), Err(MoveError::WrongSubBoard { sub_board: 0, required: 4 }));
+        assert_eq!(board.check_move(4, 1, 1), Ok(()));
+        board.make_move(4, 1, 1, Piece::X);
+        assert_eq!(
+            board.check_move(4, 1, 1),
+            Err(MoveError::TileFilled { other_piece: Piece::X, row: 1, col: 1 })
+        );
+
+        // sub-board 2 is won, it can't be picked anymore
+        for row in 0..3 {
+            board.make_move(2, row, 0, Piece::O);
+        }
+        board.next_sub_board = None;
+        assert_eq!(board.check_move(2, 1, 1), Err(MoveError::SubBoardFinished { sub_board: 2 }));
+    }
+}
@@ -13,6 +13,9 @@ pub(crate) const MIN_FEES: u32 = 10;   // 0.1%
 pub(crate) const MAX_FEES: u32 = 500; // 5%
 pub(crate) const BASIS_P: u32 = 10000; // 100%
 pub(crate) const TIMEOUT_WIN: u64 = 300000000000; // 5 minutes timeout
+pub(crate) const REVEAL_TIMEOUT: u64 = 300000000000; // 5 minutes to reveal the secret
+pub(crate) const MIN_CLOCK_TIME_SEC: u64 = 30;
+pub(crate) const MAX_CLOCK_INCREMENT_SEC: u64 = 30;
 
 pub(crate) const MIN_DEPOSIT_NEAR: Balance = ONE_NEAR / 10; // 0.1 NEAR
 
@@ -21,8 +24,10 @@ pub(crate) type TokenContractId = AccountId;
 pub(crate) type GameId = u64;
 pub(crate) type AffiliateId = AccountId;
 
+/// Board dimensions and win length used for move checks and winner detection
+pub(crate) const RULES: MnkRules = MnkRules::TIC_TAC_TOE;
 /// This constant can be used to set the board size
-pub(crate) const BOARD_SIZE: usize = 3;
+pub(crate) const BOARD_SIZE: usize = RULES.width as usize;
 pub(crate) const MAX_NUM_TURNS: u64 = 9;
 pub(crate) const PLAYERS_NUM: usize = 2;
 
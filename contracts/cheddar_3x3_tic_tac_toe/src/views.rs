@@ -1,12 +1,7 @@
 use crate::*;
 use std::collections::HashMap;
 
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
-#[serde(crate = "near_sdk::serde")]
-pub enum GameResult {
-    Win(AccountId),
-    Tie
-}
+pub use tic_tac_toe_rules::{GameResult, ResultReason};
 
 #[derive(Serialize, Deserialize)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
@@ -29,7 +24,17 @@ pub struct GameView {
     pub game_status: GameState,
     pub current_player: Player,
     pub reward: GameDeposit,
+    /// the meta-board in `GameMode::Ultimate`
     pub tiles: [[Option<Piece>; BOARD_SIZE]; BOARD_SIZE],
+    pub ultimate: Option<UltimateBoard>,
+    /// `GameMode::Qubic` cube, layer by layer
+    pub cube_layers: Option<Vec<CubeLayer>>,
+    /// player offering a draw to the opponent
+    pub draw_offer: Option<AccountId>,
+    /// clock time left in seconds of `player1` and `player2`, `None` without a time control
+    pub time_left_sec: Option<(u32, u32)>,
+    /// commitments and revealed secrets while the first player is drawn
+    pub coin_toss: Option<CoinToss>,
     /* * */
     pub initiated_at_sec: u32,
     pub last_turn_timestamp_sec: u32,
@@ -40,10 +45,18 @@ pub struct GameView {
 #[serde(crate = "near_sdk::serde")]
 pub struct GameLimitedView {
     pub game_result: GameResult,
+    /// rule that decided `game_result`
+    pub reason: ResultReason,
     pub player1: AccountId,
     pub player2: AccountId,
     pub reward_or_tie_refund: GameDeposit,
     pub board: [[Option<Piece>; BOARD_SIZE]; BOARD_SIZE],
+    /// tiles of the sub-boards in `GameMode::Ultimate`
+    pub sub_boards: Option<Vec<[[Option<Piece>; BOARD_SIZE]; BOARD_SIZE]>>,
+    /// `GameMode::Qubic` cube, layer by layer
+    pub cube_layers: Option<Vec<CubeLayer>>,
+    /// all moves of the game in order, see `get_game_moves`
+    pub moves: Vec<MoveRecord>,
 }
 
 impl From<&Game> for GameView {
@@ -57,6 +70,11 @@ impl From<&Game> for GameView {
             current_player,
             reward: g.reward(),
             tiles: g.board.tiles,
+            ultimate: g.ultimate.clone(),
+            cube_layers: g.cube_layers(),
+            draw_offer: g.draw_offer.clone(),
+            time_left_sec: g.time_left_sec(env::block_timestamp()),
+            coin_toss: g.coin_toss.clone(),
             initiated_at_sec: nano_to_sec(g.initiated_at),
             last_turn_timestamp_sec: nano_to_sec(g.last_turn_timestamp),
             current_duration_sec: nano_to_sec(g.current_duration),
@@ -70,7 +88,7 @@ impl Contract {
         let games:HashMap<u64, GameView> = self.games.iter()
             .map(|(game_id,game)| (game_id, GameView::from(&game)))
             .collect();
-        let available_players = self.get_available_players();
+        let available_players = self.get_available_players(None);
 
         ContractParams { 
             games, 
@@ -85,6 +103,19 @@ impl Contract {
         self.stored_games.get(game_id).expect("Game not found")
     }
 
+    /// Up to `limit` moves of an active or a stored game starting at `from_index`
+    pub fn get_game_moves(&self, game_id: &GameId, from_index: u64, limit: u64) -> Vec<MoveRecord> {
+        let moves = match self.games.get(game_id) {
+            Some(game) => game.moves,
+            None => self.get_game(game_id).moves,
+        };
+        moves
+            .into_iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+
     pub fn get_last_games(&self) -> Vec<(GameId, GameLimitedView)> {
         self.stored_games.to_vec()
     }
@@ -109,10 +140,19 @@ impl Contract {
             .into()
     }
 
-    pub fn get_available_players(&self) -> Vec<(AccountId, GameConfigView)> {
+    /// Offers waiting for an opponent. With `account_id` only the offers whose
+    /// rating bounds accept the rating of `account_id` are listed.
+    pub fn get_available_players(&self, account_id: Option<AccountId>) -> Vec<(AccountId, GameConfigView)> {
         self.available_players
             .to_vec()
             .iter()
+            .filter(|(_, game_config)| match &account_id {
+                Some(account_id) => {
+                    let rating = self.internal_get_rating(account_id, RatingPool::from(game_config)).rating;
+                    game_config.accepts_rating(&rating)
+                }
+                None => true,
+            })
             .map(|(acc, game_config)| (acc.clone(), GameConfigView::from(game_config)))
             .collect()
     }
@@ -125,19 +165,17 @@ impl Contract {
             .collect()
     }
 
-    pub fn get_penalty_users(&self) -> Vec<(AccountId, UserPenalties)> {
-        let accounts_played = self.get_accounts_played();
-        assert_eq!(accounts_played.len() as u32, self.get_total_stats_num());
-
-        let result: Vec<(_,_)> = accounts_played.iter()
-            .map(|acc| {
-                    let penalties_by_acc = self.get_user_penalties(acc);
-                    (acc.clone(), penalties_by_acc)
-                }
-            )
-            .filter(|(_, penalties_by_acc)| penalties_by_acc.penalties_num > 0)
-            .collect();
-
-        result
+    /// accounts with penalty games, the most penalized first, after `from_account_id`,
+    /// the last account of the previous page
+    pub fn get_penalty_users(&self, from_account_id: Option<AccountId>, limit: u64) -> Vec<(AccountId, UserPenalties)> {
+        let from = from_account_id.map(|account_id| LeaderboardEntry {
+            rank: 0,
+            score: u128::from(self.get_user_penalties(&account_id).penalties_num).into(),
+            account_id,
+        });
+        self.get_leaderboard(Leaderboard::Penalties, from, limit)
+            .into_iter()
+            .map(|entry| (entry.account_id, UserPenalties { penalties_num: entry.score.0 as u64 }))
+            .collect()
     }
 }
\ No newline at end of file
@@ -29,6 +29,8 @@ impl Contract {
                     penalties_num: stats.penalties_num,
                     total_reward: stats.total_reward,
                     total_affiliate_reward: stats.total_affiliate_reward,
+                    matches_num: stats.matches_played,
+                    match_victories_num: stats.match_victories_num,
             };
             self.stats.insert(&user.clone(), &stats);
         }
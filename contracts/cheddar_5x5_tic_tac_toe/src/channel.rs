@@ -0,0 +1,130 @@
+use std::convert::TryFrom;
+
+use ed25519_dalek::{Signature, Verifier};
+
+use crate::*;
+
+/// State channel of a game: the players exchange moves signed off-chain and only
+/// send the final state, or the latest one in a dispute, to the contract
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct Channel {
+    /// ed25519 keys of `players.0` and `players.1`
+    pub keys: (PublicKey, PublicKey),
+    /// the current player must answer the submitted state with a later one before
+    /// the deadline, `None` until a state is submitted
+    pub challenge_deadline: Option<Timestamp>,
+}
+
+/// Bytes a player signs for the state made of `moves`: borsh of the contract
+/// account id, the game id and the moves from the start of the game
+pub(crate) fn channel_message(game_id: GameId, moves: &[Coords]) -> Vec<u8> {
+    (env::current_account_id(), game_id, moves.to_vec())
+        .try_to_vec()
+        .expect("Failed to serialize the channel state")
+}
+
+/// Returns true if `signature` of `message` is valid for the ed25519 `key`
+fn is_valid_signature(key: &PublicKey, message: &[u8], signature: &[u8]) -> bool {
+    // the first byte of `PublicKey` is the curve type
+    let key = match ed25519_dalek::PublicKey::from_bytes(&key.as_bytes()[1..]) {
+        Ok(key) => key,
+        Err(_) => return false,
+    };
+    match Signature::try_from(signature) {
+        Ok(signature) => key.verify(message, &signature).is_ok(),
+        Err(_) => false,
+    }
+}
+
+impl Channel {
+    /// Key of the player who made the move at `index`, `players.0` moves first
+    fn signer_key(&self, index: usize) -> &PublicKey {
+        match index % 2 {
+            0 => &self.keys.0,
+            _ => &self.keys.1,
+        }
+    }
+
+    /// Checks the signatures of a state: the player who made the last move signs the
+    /// whole state, the opponent signs the state before it. Together they prove both
+    /// players agreed on every move.
+    fn verify_state(&self, game_id: GameId, moves: &[Coords], signatures: &[Base64VecU8]) {
+        let required = moves.len().min(2);
+        assert_eq!(signatures.len(), required, "Channel state needs {} signatures", required);
+        for (signature, len) in signatures.iter().zip((0..moves.len()).rev()) {
+            let signed_moves = &moves[..=len];
+            assert!(
+                is_valid_signature(self.signer_key(len), &channel_message(game_id, signed_moves), &signature.0),
+                "Invalid signature of the channel state after move {}",
+                signed_moves.len()
+            );
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Submits a state of a channel game: `moves` from the start of the game, the signature
+    /// of the state by the player who made the last move and the signature of the state
+    /// without the last move by the opponent. The moves are replayed with the rules of the
+    /// game. A decided game is paid out right away, otherwise the player to move must
+    /// submit a later state within `CHALLENGE_PERIOD` or lose the game.
+    pub fn submit_channel_state(&mut self, game_id: &GameId, moves: Vec<Coords>, signatures: Vec<Base64VecU8>) -> Option<GameResult> {
+        let cur_timestamp: Timestamp = nano_to_sec(env::block_timestamp()) as Timestamp;
+        let mut game = self.internal_get_game(game_id);
+        assert_eq!(game.game_state, GameState::Active, "Current game isn't active");
+        assert!(game.contains_player_account_id(&env::predecessor_account_id()), "No access");
+        let channel = game.channel.clone().expect("The game isn't played in a channel");
+        assert!(!game.is_challenge_expired(cur_timestamp), "The challenge window is over");
+        assert!(
+            moves.len() > game.moves.len() || channel.challenge_deadline.is_none(),
+            "Channel state must be later than the submitted one"
+        );
+        assert!(
+            moves.len() >= game.moves.len()
+                && game
+                    .moves
+                    .iter()
+                    .zip(moves.iter())
+                    .all(|(record, coords)| (record.coords.x, record.coords.y) == (coords.x, coords.y)),
+            "Channel state doesn't continue the submitted one"
+        );
+        channel.verify_state(*game_id, &moves, &signatures);
+
+        for coords in moves.into_iter().skip(game.moves.len()) {
+            if let Err(e) = game.check_move(&coords) {
+                panic!("Invalid move in the channel state: {:?}", e);
+            }
+            game.place_stone(coords, cur_timestamp);
+        }
+        if game.winner.is_some() {
+            return self.internal_finish_game(game_id, game);
+        }
+
+        game.last_turn_timestamp = cur_timestamp;
+        game.channel = Some(Channel {
+            challenge_deadline: Some(cur_timestamp + CHALLENGE_PERIOD),
+            ..channel
+        });
+        log!("@{} must answer the channel state before {}", game.current_player_account_id(), cur_timestamp + CHALLENGE_PERIOD);
+        self.internal_update_game(game_id, &game);
+        None
+    }
+}
+
+impl Contract {
+    /// Lets the players of a new game sign the moves off-chain,
+    /// `key_1` belongs to `player_1_id` and `key_2` to the opponent
+    pub(crate) fn internal_open_channel(&mut self, game_id: &GameId, player_1_id: &AccountId, key_1: PublicKey, key_2: PublicKey) {
+        let mut game = self.internal_get_game(game_id);
+        let keys = if game.players.0 == *player_1_id {
+            (key_1, key_2)
+        } else {
+            (key_2, key_1)
+        };
+        game.channel = Some(Channel { keys, challenge_deadline: None });
+        self.internal_update_game(game_id, &game);
+    }
+}
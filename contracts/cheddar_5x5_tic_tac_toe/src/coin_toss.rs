@@ -0,0 +1,76 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Reveals the secret committed in `make_available`. The game starts once both secrets
+    /// are revealed: the player who started the game moves first if their XOR is even.
+    pub fn reveal_secret(&mut self, game_id: &GameId, secret: U128) {
+        let cur_timestamp: Timestamp = nano_to_sec(env::block_timestamp()) as Timestamp;
+        let mut game = self.internal_get_game(game_id);
+        assert_eq!(game.game_state, GameState::NotStarted, "The game has already started");
+        let account_id = env::predecessor_account_id();
+        let player = game.player_index(&account_id);
+        let coin_toss = game.coin_toss.as_mut().expect("The game doesn't draw the first player");
+        assert!(cur_timestamp <= coin_toss.reveal_deadline, "The reveal deadline has passed");
+        coin_toss.reveal(player, &account_id, secret.0);
+
+        if let Some(result) = coin_toss.result() {
+            let swap = result & 1 == 1;
+            if swap {
+                if let Some(match_id) = game.match_id {
+                    // `Match.players.0` plays O in the first game
+                    let mut game_match = self.get_match(match_id);
+                    game_match.players = (game_match.players.1.clone(), game_match.players.0.clone());
+                    self.matches.insert(&match_id, &game_match);
+                }
+            }
+            game.start_after_coin_toss(swap, cur_timestamp);
+            log!("Game {} started, @{} moves first", game_id, game.players.0);
+        }
+        self.internal_update_game(game_id, &game);
+    }
+}
+
+impl Contract {
+    /// Holds the game back until both players reveal the secrets behind `commitments`
+    pub(crate) fn internal_start_coin_toss(&mut self, game_id: &GameId, commitments: [Base58CryptoHash; 2]) {
+        let mut game = self.internal_get_game(game_id);
+        let reveal_deadline = nano_to_sec(env::block_timestamp()) as Timestamp + REVEAL_TIMEOUT;
+        game.coin_toss = Some(CoinToss::new(commitments, reveal_deadline));
+        game.change_state(GameState::NotStarted);
+        self.internal_update_game(game_id, &game);
+    }
+
+    /// Ends a game whose secrets weren't both revealed in time: the player who didn't
+    /// reveal forfeits. If neither did, the game never started: both deposits are refunded
+    /// in full without rating the game, and the match of the game is cancelled.
+    pub(crate) fn internal_expire_coin_toss(&mut self, game_id: &GameId) -> GameResult {
+        let mut game = self.internal_get_game(game_id);
+        let coin_toss = game.coin_toss.clone().expect("The game doesn't draw the first player");
+        // the game ends like an active one
+        game.change_state(GameState::Active);
+        self.internal_update_game(game_id, &game);
+
+        if let Some(player) = coin_toss.defaulter() {
+            let defaulter = match player {
+                0 => game.players.0.clone(),
+                _ => game.players.1.clone(),
+            };
+            log!("@{} didn't reveal the secret in time", defaulter);
+            self.internal_stop_expired_game(game_id, defaulter);
+            return self.get_game(game_id).game_result;
+        }
+
+        log!("Neither player revealed the secret in time");
+        let reward = match game.match_id {
+            Some(match_id) => self.internal_cancel_match(match_id),
+            None => game.reward.clone(),
+        };
+        let refund_amount = reward.balance.0 / 2;
+        self.internal_tie_refund(&game.players, refund_amount);
+        let game_to_store = GameLimitedView::finished(&game, GameResult::Tie, ResultReason::Timeout, refund_amount.into());
+        self.internal_store_game(game_id, &game_to_store);
+        self.games.remove(game_id);
+        game_to_store.game_result
+    }
+}
@@ -36,6 +36,108 @@ pub(crate) fn validate_fee(service_fee: u16, referrer_fee_share: u16) {
         "referrer fees need to be in range 0..10000 from total fees"
     );
 }
+pub(crate) fn validate_rules(rules: &MnkRules) {
+    assert!(
+        rules.gravity || rules.width == rules.height,
+        "board must be square, got {}x{}",
+        rules.width,
+        rules.height
+    );
+    assert!(
+        (MIN_BOARD_SIZE..=MAX_BOARD_SIZE).contains(&rules.width)
+            && (MIN_BOARD_SIZE..=MAX_BOARD_SIZE).contains(&rules.height),
+        "board size must be between {} and {}",
+        MIN_BOARD_SIZE,
+        MAX_BOARD_SIZE
+    );
+    assert!(
+        (MIN_WIN_LENGTH..=MAX_WIN_LENGTH).contains(&rules.win_length),
+        "win length must be between {} and {}",
+        MIN_WIN_LENGTH,
+        MAX_WIN_LENGTH
+    );
+    assert!(
+        rules.win_length <= rules.width.min(rules.height),
+        "win length {} doesn't fit on {}x{} board",
+        rules.win_length,
+        rules.width,
+        rules.height
+    );
+}
+pub(crate) fn validate_best_of(best_of: u8) {
+    assert!(
+        BEST_OF.contains(&best_of),
+        "best_of must be one of {:?}",
+        BEST_OF
+    );
+}
+pub(crate) fn validate_rating_bounds(min_rating: Option<u32>, max_rating: Option<u32>) {
+    if let (Some(min_rating), Some(max_rating)) = (min_rating, max_rating) {
+        assert!(
+            min_rating <= max_rating,
+            "min_rating {} is above max_rating {}",
+            min_rating,
+            max_rating
+        );
+    }
+}
+pub(crate) fn validate_season(fee_share: u16, prizes: &[u16]) {
+    assert!(fee_share <= BASIS_P, "fee share must be in range 0..10000");
+    assert!(
+        !prizes.is_empty() && prizes.len() <= MAX_SEASON_PRIZES,
+        "season must have 1 to {} prizes",
+        MAX_SEASON_PRIZES
+    );
+    assert!(
+        prizes.iter().map(|prize| *prize as u32).sum::<u32>() <= BASIS_P as u32,
+        "prizes must add up to at most 10000"
+    );
+}
+pub(crate) fn validate_tournament(size: u8, prizes: &[u16]) {
+    assert!(
+        TOURNAMENT_SIZES.contains(&size),
+        "tournament size must be one of {:?}",
+        TOURNAMENT_SIZES
+    );
+    assert!(
+        !prizes.is_empty() && prizes.len() <= size as usize,
+        "tournament must have 1 to {} prizes",
+        size
+    );
+    assert_eq!(
+        prizes.iter().map(|prize| *prize as u32).sum::<u32>(),
+        BASIS_P as u32,
+        "prizes must add up to 10000"
+    );
+}
+pub(crate) fn validate_takebacks(takebacks: u8) {
+    assert!(
+        takebacks <= MAX_TAKEBACKS,
+        "takebacks must be at most {}",
+        MAX_TAKEBACKS
+    );
+}
+pub(crate) fn validate_time_control(time_control: &TimeControl) {
+    assert!(
+        (MIN_CLOCK_TIME..=MAX_GAME_DURATION).contains(&time_control.initial),
+        "initial clock time must be between {} and {}sec",
+        MIN_CLOCK_TIME,
+        MAX_GAME_DURATION
+    );
+    assert!(
+        time_control.increment <= MAX_CLOCK_INCREMENT,
+        "clock increment must be at most {}sec",
+        MAX_CLOCK_INCREMENT
+    );
+}
+pub(crate) fn validate_channel_key(channel_key: &PublicKey, config: &GameConfig) {
+    assert_eq!(channel_key.curve_type(), CurveType::ED25519, "channel key must be an ed25519 key");
+    assert!(
+        config.best_of == 1 && config.takebacks == 0 && config.time_control.is_none(),
+        "channel games can't be played in a match, with takebacks or with a clock"
+    );
+    assert_eq!(config.opening, Opening::Standard, "channel games must use the standard opening");
+}
 pub(crate) fn validate_game_duration(d: u64) {
     assert!(
         MIN_MAX_GAME_DURATION <= d && d <= MAX_GAME_DURATION,
@@ -1,53 +1,27 @@
 use core::panic;
-use std::cmp::{min, max};
 
 use crate::{*, views::Tiles};
 
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Clone, Debug)]
-#[serde(crate = "near_sdk::serde")]
-pub enum GameState {
-    NotStarted,
-    Active,
-    Finished,
-}
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
-#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
-#[serde(crate = "near_sdk::serde")]
-pub enum Winner {
-    X,
-    O,
-    Tie,
-}
-
-#[derive(Debug, Clone, PartialEq)]
-pub enum MoveError {
-    /// The game was already over when a move was attempted
-    GameOver,
-    /// The position provided was invalid
-    InvalidPosition { row: u8, col: u8 },
-    /// The tile already contained another piece
-    TileFilled {
-        other_piece: Piece,
-        row: u8,
-        col: u8,
-    },
-}
+pub use tic_tac_toe_rules::{BitBoard, ChessClock, CoinToss, Coords, GameState, MoveError, TimeControl, Variant, Winner};
 
+/// Deposit into `Game` for each `Player`
+/// Used for computing reward
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
 #[serde(crate = "near_sdk::serde")]
-pub struct Coords {
-    pub x: u8,
-    pub y: u8,
+pub struct GameDeposit {
+    pub balance: U128,
 }
 
-/// Deposit into `Game` for each `Player`
-/// Used for computing reward
+/// A stone placed on the board, in the order of `Game.moves`
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
 #[serde(crate = "near_sdk::serde")]
-pub struct GameDeposit {
-    pub balance: U128,
+pub struct MoveRecord {
+    pub coords: Coords,
+    pub piece: Piece,
+    /// block timestamp of the move in seconds
+    pub timestamp: Timestamp,
 }
 
 #[derive(BorshSerialize, BorshDeserialize)]
@@ -65,8 +39,36 @@ pub struct Game {
     //board fields
     pub last_move: Option<Coords>,
     pub winner: Option<Winner>,
-    pub board: UnorderedMap<Coords, Piece>,
+    pub board: BitBoard,
     pub duration: Duration,
+    /// board size and win length agreed by both players
+    pub rules: MnkRules,
+    pub variant: Variant,
+    pub opening: Opening,
+    /// `None` once the opening is over and players take turns
+    pub opening_phase: Option<OpeningPhase>,
+    /// `Match` this game is part of, the stake is paid once the match is decided
+    pub match_id: Option<MatchId>,
+    /// `Tournament` this game is part of, the winner advances to the next round
+    pub tournament_id: Option<TournamentId>,
+    /// player offering a draw, the offer expires when the opponent moves
+    pub draw_offer: Option<AccountId>,
+    /// takebacks left for `players.0` and `players.1`
+    pub takebacks_left: (u8, u8),
+    /// player asking to take back their last move, expires when the opponent moves
+    pub takeback_request: Option<AccountId>,
+    /// `last_move` before the latest move, `None` if there is no move to take back
+    pub previous_move: Option<Option<Coords>>,
+    /// time banks of `players.0` and `players.1`, `None` if the game uses the duration limits
+    pub clock: Option<ChessClock>,
+    /// all stones in the order they were placed, opening stones included
+    pub moves: Vec<MoveRecord>,
+    /// number of opening stones at the start of `moves`
+    pub opening_stones: u8,
+    /// commit–reveal draw of the first player, the game is `NotStarted` until it's done
+    pub coin_toss: Option<CoinToss>,
+    /// keys signing the moves played off-chain, `None` if the moves are sent on-chain
+    pub channel: Option<Channel>,
 }
 
 impl Game {
@@ -75,10 +77,12 @@ impl Game {
     /// It generates randomly in `Contract.start_game` because
     /// first move gives more chances to win
     pub fn create_game(
-        game_id: GameId,
         player_1: AccountId,
         player_2: AccountId,
         reward: GameDeposit,
+        rules: MnkRules,
+        variant: Variant,
+        opening: Opening,
     ) -> Game {
         assert_ne!(
             player_1, player_2,
@@ -99,8 +103,23 @@ impl Game {
             //board fields
             last_move: None,
             winner: None,
-            board: UnorderedMap::new(StorageKey::GameBoard { game_id }),
+            board: BitBoard::default(),
             duration: 0,
+            rules,
+            variant,
+            opening,
+            opening_phase: opening.first_phase(),
+            match_id: None,
+            tournament_id: None,
+            draw_offer: None,
+            takebacks_left: (0, 0),
+            takeback_request: None,
+            previous_move: None,
+            clock: None,
+            moves: Vec::new(),
+            opening_stones: 0,
+            coin_toss: None,
+            channel: None,
         };
         game.set_players(player_1, player_2);
         game
@@ -171,6 +190,88 @@ impl Game {
     pub fn contains_player_account_id(&self, user: &AccountId) -> bool {
         self.players.0 == *user || self.players.1 == *user
     }
+    /// Drops the draw offer and the takeback request of the opponent of `player`
+    /// who moved instead of accepting them
+    pub fn expire_offers(&mut self, player: &AccountId) {
+        if matches!(&self.draw_offer, Some(offer) if offer != player) {
+            self.draw_offer = None;
+        }
+        if matches!(&self.takeback_request, Some(request) if request != player) {
+            self.takeback_request = None;
+        }
+    }
+    pub fn player_index(&self, player: &AccountId) -> usize {
+        if *player == self.players.0 {
+            0
+        } else if *player == self.players.1 {
+            1
+        } else {
+            panic!("Account @{} doesn't play in this game", player)
+        }
+    }
+    /// Starts the clocks of both players if the game has a time control
+    pub fn set_time_control(&mut self, time_control: Option<TimeControl>) {
+        self.clock = time_control.map(|time_control| ChessClock::new(time_control, self.initiated_at));
+    }
+    /// Returns true if the clock of the current player ran out
+    pub fn is_out_of_time(&self, cur_timestamp: Timestamp) -> bool {
+        matches!(&self.clock, Some(clock) if clock.is_flagged(self.current_player_index as usize, cur_timestamp))
+    }
+    /// Time left of `players.0` and `players.1`
+    pub fn time_left(&self, cur_timestamp: Timestamp) -> Option<(Duration, Duration)> {
+        self.clock.as_ref().map(|clock| {
+            let to_move = self.current_player_index as usize;
+            (clock.time_left(0, to_move, cur_timestamp), clock.time_left(1, to_move, cur_timestamp))
+        })
+    }
+    /// Returns true if the current player didn't answer the submitted channel state in time
+    pub fn is_challenge_expired(&self, cur_timestamp: Timestamp) -> bool {
+        matches!(
+            &self.channel,
+            Some(Channel { challenge_deadline: Some(deadline), .. }) if cur_timestamp > *deadline
+        )
+    }
+    /// Returns true if the secrets weren't both revealed before the deadline
+    pub fn is_reveal_expired(&self, cur_timestamp: Timestamp) -> bool {
+        self.game_state == GameState::NotStarted
+            && matches!(&self.coin_toss, Some(coin_toss) if cur_timestamp > coin_toss.reveal_deadline)
+    }
+    /// Starts the game once the first player is drawn, `players.1` moves first if `swap`
+    pub fn start_after_coin_toss(&mut self, swap: bool, cur_timestamp: Timestamp) {
+        if swap {
+            self.players = (self.players.1.clone(), self.players.0.clone());
+            if let Some(channel) = self.channel.as_mut() {
+                channel.keys = (channel.keys.1.clone(), channel.keys.0.clone());
+            }
+        }
+        self.initiated_at = cur_timestamp;
+        self.set_time_control(self.clock.map(|clock| clock.time_control));
+        self.change_state(GameState::Active);
+    }
+    pub fn takebacks_left_mut(&mut self, player: &AccountId) -> &mut u8 {
+        if *player == self.players.0 {
+            &mut self.takebacks_left.0
+        } else if *player == self.players.1 {
+            &mut self.takebacks_left.1
+        } else {
+            panic!("Account @{} doesn't play in this game", player)
+        }
+    }
+    /// Removes the last stone and gives the turn back to the player who placed it
+    pub fn take_back(&mut self, cur_timestamp: Timestamp) {
+        let previous_move = self.previous_move.take().expect("No move to take back");
+        let coords = std::mem::replace(&mut self.last_move, previous_move).expect("No move to take back");
+        self.board.remove(&coords);
+        self.moves.pop();
+        self.current_piece = self.current_piece.other();
+        self.current_player_index = 1 - self.current_player_index;
+        self.total_turns = self.total_turns.saturating_sub(1);
+        // the player gets a fresh turn
+        self.last_turn_timestamp = cur_timestamp;
+        if let Some(clock) = self.clock.as_mut() {
+            clock.restart(cur_timestamp);
+        }
+    }
     pub fn reward(&self) -> GameDeposit {
         self.reward.clone()
     }
@@ -200,6 +301,12 @@ impl Game {
         assert!(self.contains_player_account_id(&player), "No access");
         //4. Check for timeout
         let cur_timestamp = nano_to_sec(env::block_timestamp());
+        if self.channel.is_some() {
+            return self.is_challenge_expired(cur_timestamp);
+        }
+        if self.clock.is_some() {
+            return self.is_out_of_time(cur_timestamp);
+        }
         if cur_timestamp - self.last_turn_timestamp <= utils::TIMEOUT_WIN {
             return false;
         }
@@ -215,164 +322,54 @@ impl Game {
     }
     
     // board methods
+    /// Tile of a move given either by `coords` or, in gravity games, by the column only
+    pub fn move_coords(&self, coords: Option<Coords>, col: Option<u8>) -> Result<Coords, MoveError> {
+        match (coords, col) {
+            (Some(coords), None) => Ok(coords),
+            (None, Some(col)) => {
+                assert!(self.rules.gravity, "Only gravity games accept column-only moves");
+                self.rules.drop_coords(&self.board, col)
+            }
+            _ => panic!("Either coords or col must be provided"),
+        }
+    }
     pub fn check_move(&self, coords: &Coords) -> Result<(), MoveError> {
         if self.winner.is_some() {
             return Err(MoveError::GameOver);
         }
-        if coords.y >= BOARD_SIZE || coords.x >= BOARD_SIZE {
-            return Err(MoveError::InvalidPosition {
-                row: coords.y,
-                col: coords.x,
-            });
-        }
-        // Move in already filled tile
-        else if let Some(other_piece) = self.board.get(&coords) {
-            return Err(MoveError::TileFilled {
-                other_piece,
-                row: coords.y,
-                col: coords.x,
-            });
+        self.rules.check_move(&self.board, coords)?;
+        // Renju restrictions apply to O from the first move after the opening
+        if self.variant == Variant::Renju && self.current_piece == Piece::O && self.opening_phase.is_none() {
+            if let Some(kind) = forbidden_move(&self.rules, &self.board, coords, Piece::O) {
+                return Err(MoveError::Forbidden { kind, row: coords.y, col: coords.x });
+            }
         }
         Ok(())
     }
+    /// Places the stone of the current player on `coords` checked with `check_move`
+    /// and passes the turn to the opponent
+    pub fn place_stone(&mut self, coords: Coords, cur_timestamp: Timestamp) {
+        // fill board tile with current player piece
+        self.board.insert(&coords, self.current_piece);
+        self.moves.push(MoveRecord { coords: coords.clone(), piece: self.current_piece, timestamp: cur_timestamp });
+        // set the last move
+        self.previous_move = Some(self.last_move.take());
+        self.last_move = Some(coords.clone());
+        // switch piece to other one
+        self.current_piece = self.current_piece.other();
+        // switch player
+        self.current_player_index = 1 - self.current_player_index;
+        self.update_winner(&coords);
+    }
+    /// Returns true if the piece which made the last move (`current_piece.other()`)
+    /// completes a winning line on `position`
     pub fn check_winner(&self, position: &Coords) -> bool {
-        let expected = Some(self.current_piece.other());
-        let mut c = position.clone();
-        let mut counter = 1;
-        // 1. check rows
-        // go max 4 pos to the left and see how far we can go
-        for _ in 1..=min(4, position.x) {
-            c.x = c.x - 1;
-            if self.board.get(&c) == expected {
-                counter += 1;
-            } else {
-                break;
-            }
-        }
-        if counter >= 5 {
-            return true;
-        }
-        c = position.clone();
-        for _ in 1..=max(4, BOARD_SIZE - 1 - position.x) {
-            c.x = c.x + 1;
-            if self.board.get(&c) == expected {
-                counter += 1;
-            } else {
-                break;
-            }
-            if counter >= 5 {
-                return true;
-            }
-        }
-        // 2. check columns
-        c = position.clone();
-        counter = 1;
-        for _ in 1..=min(4, position.y) {
-            c.y = c.y - 1;
-            if self.board.get(&c) == expected {
-                counter += 1;
-            } else {
-                break;
-            }
-        }
-        if counter >= 5 {
-            return true;
-        }
-        c = position.clone();
-        for _ in 1..=max(4, BOARD_SIZE - 1 - position.y) {
-            c.y = c.y + 1;
-            if self.board.get(&c) == expected {
-                counter += 1;
-            } else {
-                break;
-            }
-            if counter >= 5 {
-                return true;
-            }
-        }
-        // 3. check diagonal (NW - SE)
-        // eg. o X o o x o
-        //     x o X o o x
-        //     o x x X o o
-        //     o o o x X x
-        //     o o o x x X
-        //     x x o x o x
-        c = position.clone();
-        counter = 1;
-        for _ in 1..=min(4, min(position.x, position.y)) {
-            c.x = c.x - 1;
-            c.y = c.y - 1;
-            if self.board.get(&c) == expected {
-                counter += 1;
-            } else {
-                break;
-            }
-        }
-        if counter >= 5 {
-            return true;
-        }
-        c = position.clone();
-        for _ in 1..=max(4, BOARD_SIZE - 1 - max(position.x, position.y)) {
-            c.x = c.x + 1;
-            c.y = c.y + 1;
-            if self.board.get(&c) == expected {
-                counter += 1;
-            } else {
-                break;
-            }
-            if counter >= 5 {
-                return true;
-            }
-        }
-
-        //4. check diagonal (NE - SW)
-        c = position.clone();
-        let mut counter = 1;
-        for _ in 1..=position.y {
-            c.x = c.x + 1;
-            c.y = c.y - 1;
-            if self.board.get(&c) == expected {
-                counter += 1;
-            } else {
-                break;
-            }
-        }
-        if counter >= 5 {
-            return true;
-        }
-        c = position.clone();
-        for _ in 1..=position.x {
-            c.x = c.x - 1;
-            c.y = c.y + 1;
-            if self.board.get(&c) == expected {
-                counter += 1;
-            } else {
-                break;
-            }
-            if counter >= 5 {
-                return true;
-            }
-        }
-        false
+        self.rules.is_winning_move(&self.board, position, self.current_piece.other())
     }
     /// To find a potential winner, we only need to check the row, column and (maybe) diagonal
     /// that the last move was made in.
-    /// To find a potential winner, we only need to check the row, column and (maybe) diagonal
-    /// that the last move was made in.
     pub fn update_winner(&mut self, coords: &Coords) {
-        if self.board.len() >= MAX_NUM_TURNS {
-            self.winner = Some(Winner::Tie);
-            return;
-        }
-        if self.check_winner(coords) {
-            if self.current_piece.other() == Piece::X {
-                self.winner = Some(Winner::X);
-                print!("X is the winner");
-            } else if self.current_piece.other() == Piece::O {
-                self.winner = Some(Winner::O);
-                print!("O is the winner");
-            }
-        }
+        self.winner = self.winner.or_else(|| self.rules.game_result(&self.board, coords, self.current_piece.other()));
     }
 
     pub fn to_tiles(&self) -> Tiles {
@@ -398,11 +395,13 @@ impl Game {
 
     }
 }
+
 #[cfg(all(test, not(target_arch = "wasm32")))]
 mod test {
     use near_sdk::{AccountId, json_types::U128};
 
-    use crate::{utils::BOARD_SIZE, game::MoveError, player::Piece};
+    use crate::{utils::DEFAULT_RULES, game::{MoveError, Variant}, opening::Opening, player::Piece};
+    use tic_tac_toe_rules::{ForbiddenMove, MnkRules};
 
     use super::{Game, GameDeposit, Coords};
 
@@ -416,7 +415,7 @@ mod test {
         "cheddar".parse().unwrap()
     }
     pub fn init_game() -> Game {
-        return Game::create_game(1, user(), opponent(), GameDeposit{balance: U128(50000)});
+        return Game::create_game(user(), opponent(), GameDeposit{balance: U128(50000)}, DEFAULT_RULES, Variant::Freestyle, Opening::Standard);
     }
     #[test]
     fn valid_move() {
@@ -434,14 +433,14 @@ mod test {
         let game = init_game();
         // make move
         let result = game.check_move(&Coords {
-            x: BOARD_SIZE,
-            y: BOARD_SIZE,
+            x: DEFAULT_RULES.width,
+            y: DEFAULT_RULES.height,
         });
         assert_eq!(
             result,
             Err(MoveError::InvalidPosition {
-                row: BOARD_SIZE,
-                col: BOARD_SIZE
+                row: DEFAULT_RULES.height,
+                col: DEFAULT_RULES.width
             })
         );
     }
@@ -454,7 +453,7 @@ mod test {
         let mut game = init_game();
 
         // make move
-        game.board.insert(&Coords { x: 0, y: 0 }, &piece_1);
+        game.board.insert(&Coords { x: 0, y: 0 }, piece_1);
         let result = game.check_move(&Coords { x: 0, y: 0 });
         assert_eq!(
             result,
@@ -480,14 +479,14 @@ mod test {
         // _ _ _ _ _
         // _ _ _ _ _
         // _ _ _ _ _
-        game.board.insert(&Coords { x: 0, y: 0 }, &piece_1);
-        game.board.insert(&Coords { x: 1, y: 0 }, &piece_1);
-        game.board.insert(&Coords { x: 2, y: 0 }, &piece_1);
-        game.board.insert(&Coords { x: 3, y: 0 }, &piece_1);
-        game.board.insert(&Coords { x: 0, y: 1 }, &piece_2);
-        game.board.insert(&Coords { x: 1, y: 1 }, &piece_2);
-        game.board.insert(&Coords { x: 2, y: 1 }, &piece_2);
-        game.board.insert(&Coords { x: 3, y: 1 }, &piece_2);
+        game.board.insert(&Coords { x: 0, y: 0 }, piece_1);
+        game.board.insert(&Coords { x: 1, y: 0 }, piece_1);
+        game.board.insert(&Coords { x: 2, y: 0 }, piece_1);
+        game.board.insert(&Coords { x: 3, y: 0 }, piece_1);
+        game.board.insert(&Coords { x: 0, y: 1 }, piece_2);
+        game.board.insert(&Coords { x: 1, y: 1 }, piece_2);
+        game.board.insert(&Coords { x: 2, y: 1 }, piece_2);
+        game.board.insert(&Coords { x: 3, y: 1 }, piece_2);
         let result = game.check_winner(&Coords { x: 4, y: 1 });
         assert_eq!(result, true);
     }
@@ -504,10 +503,10 @@ mod test {
         // O _ _ _ _
         // O _ _ _ _
         // _ _ _ _ _
-        game.board.insert(&Coords { x: 0, y: 0 }, &piece_2);
-        game.board.insert(&Coords { x: 0, y: 1 }, &piece_2);
-        game.board.insert(&Coords { x: 0, y: 2 }, &piece_2);
-        game.board.insert(&Coords { x: 0, y: 3 }, &piece_2);
+        game.board.insert(&Coords { x: 0, y: 0 }, piece_2);
+        game.board.insert(&Coords { x: 0, y: 1 }, piece_2);
+        game.board.insert(&Coords { x: 0, y: 2 }, piece_2);
+        game.board.insert(&Coords { x: 0, y: 3 }, piece_2);
         let result = game.check_winner(&Coords { x: 0, y: 4 });
         assert_eq!(result, true);
     }
@@ -525,10 +524,10 @@ mod test {
         // O _ _ _ _
         // O _ _ _ _
         // O _ _ _ _
-        game.board.insert(&Coords { x: 0, y: 1 }, &piece_2);
-        game.board.insert(&Coords { x: 0, y: 2 }, &piece_2);
-        game.board.insert(&Coords { x: 0, y: 3 }, &piece_2);
-        game.board.insert(&Coords { x: 0, y: 4 }, &piece_2);
+        game.board.insert(&Coords { x: 0, y: 1 }, piece_2);
+        game.board.insert(&Coords { x: 0, y: 2 }, piece_2);
+        game.board.insert(&Coords { x: 0, y: 3 }, piece_2);
+        game.board.insert(&Coords { x: 0, y: 4 }, piece_2);
         let result = game.check_winner(&Coords { x: 0, y: 0 });
         assert_eq!(result, true);
     }
@@ -546,10 +545,10 @@ mod test {
         // _ _ _ _ _
         // O _ _ _ _
         // O _ _ _ _
-        game.board.insert(&Coords { x: 0, y: 0 }, &piece_2);
-        game.board.insert(&Coords { x: 0, y: 1 }, &piece_2);
-        game.board.insert(&Coords { x: 0, y: 3 }, &piece_2);
-        game.board.insert(&Coords { x: 0, y: 4 }, &piece_2);
+        game.board.insert(&Coords { x: 0, y: 0 }, piece_2);
+        game.board.insert(&Coords { x: 0, y: 1 }, piece_2);
+        game.board.insert(&Coords { x: 0, y: 3 }, piece_2);
+        game.board.insert(&Coords { x: 0, y: 4 }, piece_2);
         let result = game.check_winner(&Coords { x: 0, y: 2 });
         assert_eq!(result, true);
     }
@@ -567,10 +566,10 @@ mod test {
         // _ _ O _ _
         // _ _ _ O _
         // _ _ _ _ _
-        game.board.insert(&Coords { x: 0, y: 0 }, &piece_2);
-        game.board.insert(&Coords { x: 1, y: 1 }, &piece_2);
-        game.board.insert(&Coords { x: 2, y: 2 }, &piece_2);
-        game.board.insert(&Coords { x: 3, y: 3 }, &piece_2);
+        game.board.insert(&Coords { x: 0, y: 0 }, piece_2);
+        game.board.insert(&Coords { x: 1, y: 1 }, piece_2);
+        game.board.insert(&Coords { x: 2, y: 2 }, piece_2);
+        game.board.insert(&Coords { x: 3, y: 3 }, piece_2);
         let result = game.check_winner(&Coords { x: 4, y: 4 });
         assert_eq!(result, true);
     }
@@ -588,10 +587,10 @@ mod test {
         // _ _ O _ _
         // _ _ _ O _
         // _ _ _ _ O
-        game.board.insert(&Coords { x: 4, y: 4 }, &piece_2);
-        game.board.insert(&Coords { x: 1, y: 1 }, &piece_2);
-        game.board.insert(&Coords { x: 2, y: 2 }, &piece_2);
-        game.board.insert(&Coords { x: 3, y: 3 }, &piece_2);
+        game.board.insert(&Coords { x: 4, y: 4 }, piece_2);
+        game.board.insert(&Coords { x: 1, y: 1 }, piece_2);
+        game.board.insert(&Coords { x: 2, y: 2 }, piece_2);
+        game.board.insert(&Coords { x: 3, y: 3 }, piece_2);
         let result = game.check_winner(&Coords { x: 0, y: 0 });
         assert_eq!(result, true);
     }
@@ -609,10 +608,10 @@ mod test {
         // _ _ _ _ _
         // _ _ _ O _
         // _ _ _ _ O
-        game.board.insert(&Coords { x: 0, y: 0 }, &piece_2);
-        game.board.insert(&Coords { x: 1, y: 1 }, &piece_2);
-        game.board.insert(&Coords { x: 4, y: 4 }, &piece_2);
-        game.board.insert(&Coords { x: 3, y: 3 }, &piece_2);
+        game.board.insert(&Coords { x: 0, y: 0 }, piece_2);
+        game.board.insert(&Coords { x: 1, y: 1 }, piece_2);
+        game.board.insert(&Coords { x: 4, y: 4 }, piece_2);
+        game.board.insert(&Coords { x: 3, y: 3 }, piece_2);
         let result = game.check_winner(&Coords { x: 2, y: 2 });
         assert_eq!(result, true);
     }
@@ -630,10 +629,10 @@ mod test {
         // _ _ O _ _
         // _ O _ _ _
         // _ _ _ _ _
-        game.board.insert(&Coords { x: 4, y: 0 }, &piece_2);
-        game.board.insert(&Coords { x: 3, y: 1 }, &piece_2);
-        game.board.insert(&Coords { x: 2, y: 2 }, &piece_2);
-        game.board.insert(&Coords { x: 1, y: 3 }, &piece_2);
+        game.board.insert(&Coords { x: 4, y: 0 }, piece_2);
+        game.board.insert(&Coords { x: 3, y: 1 }, piece_2);
+        game.board.insert(&Coords { x: 2, y: 2 }, piece_2);
+        game.board.insert(&Coords { x: 1, y: 3 }, piece_2);
         let result = game.check_winner(&Coords { x: 0, y: 4 });
         assert_eq!(result, true);
     }
@@ -651,10 +650,10 @@ mod test {
         // _ _ O _ _
         // _ O _ _ _
         // O _ _ _ _
-        game.board.insert(&Coords { x: 0, y: 4 }, &piece_2);
-        game.board.insert(&Coords { x: 1, y: 3 }, &piece_2);
-        game.board.insert(&Coords { x: 2, y: 2 }, &piece_2);
-        game.board.insert(&Coords { x: 3, y: 1 }, &piece_2);
+        game.board.insert(&Coords { x: 0, y: 4 }, piece_2);
+        game.board.insert(&Coords { x: 1, y: 3 }, piece_2);
+        game.board.insert(&Coords { x: 2, y: 2 }, piece_2);
+        game.board.insert(&Coords { x: 3, y: 1 }, piece_2);
         let result = game.check_winner(&Coords { x: 4, y: 0 });
         assert_eq!(result, true);
     }
@@ -672,10 +671,10 @@ mod test {
         // _ _ O _ _
         // _ _ _ _ _
         // O _ _ _ _
-        game.board.insert(&Coords { x: 4, y: 0 }, &piece_2);
-        game.board.insert(&Coords { x: 3, y: 1 }, &piece_2);
-        game.board.insert(&Coords { x: 2, y: 2 }, &piece_2);
-        game.board.insert(&Coords { x: 0, y: 4 }, &piece_2);
+        game.board.insert(&Coords { x: 4, y: 0 }, piece_2);
+        game.board.insert(&Coords { x: 3, y: 1 }, piece_2);
+        game.board.insert(&Coords { x: 2, y: 2 }, piece_2);
+        game.board.insert(&Coords { x: 0, y: 4 }, piece_2);
         let result = game.check_winner(&Coords { x: 1, y: 3 });
         assert_eq!(result, true);
     }
@@ -704,10 +703,10 @@ mod test {
         let mut game = init_game();
 
         // prepare the board
-        game.board.insert(&Coords { x: 24, y: 0}, &piece_2);
-        game.board.insert(&Coords { x: 23, y: 1}, &piece_2);
-        game.board.insert(&Coords { x: 22, y: 2}, &piece_2);
-        game.board.insert(&Coords { x: 20, y: 4}, &piece_2);
+        game.board.insert(&Coords { x: 24, y: 0}, piece_2);
+        game.board.insert(&Coords { x: 23, y: 1}, piece_2);
+        game.board.insert(&Coords { x: 22, y: 2}, piece_2);
+        game.board.insert(&Coords { x: 20, y: 4}, piece_2);
         let result = game.check_winner(&Coords{ x: 21, y: 3 });
         assert_eq!(result, true); 
     }
@@ -720,10 +719,10 @@ mod test {
         let mut game = init_game();
 
         // prepare the board
-        game.board.insert(&Coords { x: 24, y: 20}, &piece_2);
-        game.board.insert(&Coords { x: 23, y: 20}, &piece_2);
-        game.board.insert(&Coords { x: 22, y: 20}, &piece_2);
-        game.board.insert(&Coords { x: 21, y: 20}, &piece_2);
+        game.board.insert(&Coords { x: 24, y: 20}, piece_2);
+        game.board.insert(&Coords { x: 23, y: 20}, piece_2);
+        game.board.insert(&Coords { x: 22, y: 20}, piece_2);
+        game.board.insert(&Coords { x: 21, y: 20}, piece_2);
         let result = game.check_winner(&Coords{ x: 20, y: 20 });
         assert_eq!(result, true); 
     }
@@ -736,10 +735,10 @@ mod test {
         let mut game = init_game();
 
         // prepare the board
-        game.board.insert(&Coords { x: 24, y: 24}, &piece_2);
-        game.board.insert(&Coords { x: 23, y: 23}, &piece_2);
-        game.board.insert(&Coords { x: 21, y: 21}, &piece_2);
-        game.board.insert(&Coords { x: 20, y: 20}, &piece_2);
+        game.board.insert(&Coords { x: 24, y: 24}, piece_2);
+        game.board.insert(&Coords { x: 23, y: 23}, piece_2);
+        game.board.insert(&Coords { x: 21, y: 21}, piece_2);
+        game.board.insert(&Coords { x: 20, y: 20}, piece_2);
         let result = game.check_winner(&Coords{ x: 22, y: 22 });
         assert_eq!(result, true); 
     }
@@ -752,10 +751,10 @@ mod test {
         let mut game = init_game();
 
         // prepare the board
-        game.board.insert(&Coords { x: 24, y: 20}, &piece_2);
-        game.board.insert(&Coords { x: 24, y: 21}, &piece_2);
-        game.board.insert(&Coords { x: 24, y: 22}, &piece_2);
-        game.board.insert(&Coords { x: 24, y: 23}, &piece_2);
+        game.board.insert(&Coords { x: 24, y: 20}, piece_2);
+        game.board.insert(&Coords { x: 24, y: 21}, piece_2);
+        game.board.insert(&Coords { x: 24, y: 22}, piece_2);
+        game.board.insert(&Coords { x: 24, y: 23}, piece_2);
         let result = game.check_winner(&Coords{ x: 24, y: 24 });
         assert_eq!(result, true); 
     }
@@ -768,14 +767,85 @@ mod test {
         let mut game = init_game();
 
         // prepare the board
-        game.board.insert(&Coords { x: 24, y: 0}, &piece_2);
-        game.board.insert(&Coords { x: 24, y: 1}, &piece_2);
-        game.board.insert(&Coords { x: 24, y: 2}, &piece_2);
-        game.board.insert(&Coords { x: 24, y: 3}, &piece_2);
+        game.board.insert(&Coords { x: 24, y: 0}, piece_2);
+        game.board.insert(&Coords { x: 24, y: 1}, piece_2);
+        game.board.insert(&Coords { x: 24, y: 2}, piece_2);
+        game.board.insert(&Coords { x: 24, y: 3}, piece_2);
         let result = game.check_winner(&Coords{ x: 24, y: 4 });
         assert_eq!(result, true); 
     }
     #[test]
+    fn check_move_custom_board() {
+        let game = Game::create_game(user(), opponent(), GameDeposit{balance: U128(50000)}, MnkRules::new(9, 9, 4), Variant::Freestyle, Opening::Standard);
+        assert_eq!(game.check_move(&Coords { x: 8, y: 8 }), Ok(()));
+        assert_eq!(
+            game.check_move(&Coords { x: 9, y: 0 }),
+            Err(MoveError::InvalidPosition { row: 0, col: 9 })
+        );
+    }
+    #[test]
+    fn check_winner_custom_win_length() {
+        let piece_2 = Piece::X;
+        let mut game = Game::create_game(user(), opponent(), GameDeposit{balance: U128(50000)}, MnkRules::new(9, 9, 4), Variant::Freestyle, Opening::Standard);
+
+        // _ _ _ _ _
+        // _ X X X _
+        // _ _ _ _ _
+        game.board.insert(&Coords { x: 1, y: 1 }, piece_2);
+        game.board.insert(&Coords { x: 2, y: 1 }, piece_2);
+        game.board.insert(&Coords { x: 3, y: 1 }, piece_2);
+        assert!(game.check_winner(&Coords { x: 4, y: 1 }));
+        assert!(game.check_winner(&Coords { x: 0, y: 1 }));
+        assert!(!game.check_winner(&Coords { x: 5, y: 1 }));
+    }
+    #[test]
+    fn check_winner_exact_five() {
+        let piece_2 = Piece::X;
+        let rules = DEFAULT_RULES.with_overline_wins(false);
+        let mut game = Game::create_game(user(), opponent(), GameDeposit{balance: U128(50000)}, rules, Variant::Freestyle, Opening::Standard);
+
+        // X X X X _ X
+        game.board.insert(&Coords { x: 0, y: 0 }, piece_2);
+        game.board.insert(&Coords { x: 1, y: 0 }, piece_2);
+        game.board.insert(&Coords { x: 2, y: 0 }, piece_2);
+        game.board.insert(&Coords { x: 3, y: 0 }, piece_2);
+        game.board.insert(&Coords { x: 5, y: 0 }, piece_2);
+        assert!(!game.check_winner(&Coords { x: 4, y: 0 }));
+        // X X X X X on the bottom edge
+        game.board.insert(&Coords { x: 24, y: 20 }, piece_2);
+        game.board.insert(&Coords { x: 24, y: 21 }, piece_2);
+        game.board.insert(&Coords { x: 24, y: 22 }, piece_2);
+        game.board.insert(&Coords { x: 24, y: 23 }, piece_2);
+        assert!(game.check_winner(&Coords { x: 24, y: 24 }));
+    }
+    #[test]
+    fn check_move_renju() {
+        let mut game = Game::create_game(user(), opponent(), GameDeposit{balance: U128(50000)}, DEFAULT_RULES, Variant::Renju, Opening::Standard);
+
+        // O O O _ _
+        // _ _ _ _ _
+        game.board.insert(&Coords { x: 0, y: 0 }, Piece::O);
+        game.board.insert(&Coords { x: 1, y: 0 }, Piece::O);
+        game.board.insert(&Coords { x: 2, y: 0 }, Piece::O);
+        // a single four is fine
+        assert_eq!(game.check_move(&Coords { x: 3, y: 0 }), Ok(()));
+
+        // O O O _ _
+        // _ _ _ O _
+        // _ _ _ O _
+        // _ _ _ O _
+        game.board.insert(&Coords { x: 3, y: 1 }, Piece::O);
+        game.board.insert(&Coords { x: 3, y: 2 }, Piece::O);
+        game.board.insert(&Coords { x: 3, y: 3 }, Piece::O);
+        assert_eq!(
+            game.check_move(&Coords { x: 3, y: 0 }),
+            Err(MoveError::Forbidden { kind: ForbiddenMove::DoubleFour, row: 0, col: 3 })
+        );
+        // X has no restrictions
+        game.current_piece = Piece::X;
+        assert_eq!(game.check_move(&Coords { x: 3, y: 0 }), Ok(()));
+    }
+    #[test]
     fn test_to_tiles() {
         let piece_2 = Piece::X;
         
@@ -788,10 +858,10 @@ mod test {
         // _ _ X _ _
         // _ X _ _ _
         // _ _ _ _ _
-        game.board.insert(&Coords { x: 4, y: 0 }, &piece_2);
-        game.board.insert(&Coords { x: 3, y: 1 }, &piece_2);
-        game.board.insert(&Coords { x: 2, y: 2 }, &piece_2);
-        game.board.insert(&Coords { x: 1, y: 3 }, &piece_2);
+        game.board.insert(&Coords { x: 4, y: 0 }, piece_2);
+        game.board.insert(&Coords { x: 3, y: 1 }, piece_2);
+        game.board.insert(&Coords { x: 2, y: 2 }, piece_2);
+        game.board.insert(&Coords { x: 1, y: 3 }, piece_2);
         let vector = game.to_tiles();
         assert_eq!(vector.o_coords.len(), 0);
         assert_eq!(vector.x_coords.len(), 4);
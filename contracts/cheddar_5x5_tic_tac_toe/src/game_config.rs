@@ -9,6 +9,26 @@ pub struct GameConfig {
     pub(crate) referrer_id: Option<AccountId>,
     pub(crate) created_at: Timestamp, // timestamp in seconds
     pub(crate) available_until: Timestamp, 
+    /// board size and win length, only offers with the same rules are paired
+    pub(crate) rules: MnkRules,
+    /// only offers with the same variant are paired
+    pub(crate) variant: Variant,
+    /// only offers with the same opening are paired
+    pub(crate) opening: Opening,
+    /// number of games in the match, only offers with the same value are paired
+    pub(crate) best_of: u8,
+    /// takebacks each player gets per game, only offers with the same value are paired
+    pub(crate) takebacks: u8,
+    /// chess clock of each player, only offers with the same value are paired
+    pub(crate) time_control: Option<TimeControl>,
+    /// hash of the secret drawing the first player, only paired with another commitment
+    pub(crate) commitment: Option<Base58CryptoHash>,
+    /// ed25519 key signing the moves of a channel game, only paired with another channel key
+    pub(crate) channel_key: Option<PublicKey>,
+    /// lowest rating of an opponent in the `RatingPool` of the rules, not bounded if not set
+    pub(crate) min_rating: Option<u32>,
+    /// highest rating of an opponent in the `RatingPool` of the rules, not bounded if not set
+    pub(crate) max_rating: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -19,6 +39,16 @@ pub struct GameConfigView {
     pub(crate) opponent_id: Option<AccountId>,
     pub(crate) referrer_id: Option<AccountId>,
     pub(crate) created_at: Timestamp,
+    pub(crate) rules: MnkRules,
+    pub(crate) variant: Variant,
+    pub(crate) opening: Opening,
+    pub(crate) best_of: u8,
+    pub(crate) takebacks: u8,
+    pub(crate) time_control: Option<TimeControl>,
+    pub(crate) commitment: Option<Base58CryptoHash>,
+    pub(crate) channel_key: Option<PublicKey>,
+    pub(crate) min_rating: Option<u32>,
+    pub(crate) max_rating: Option<u32>,
 }
 
 impl From<&GameConfig> for GameConfigView {
@@ -27,17 +57,75 @@ impl From<&GameConfig> for GameConfigView {
             deposit: gc.deposit.into(), 
             opponent_id: gc.opponent_id.clone(), 
             referrer_id: gc.referrer_id.clone(),
-            created_at: nano_to_sec(gc.created_at).into()
+            created_at: nano_to_sec(gc.created_at).into(),
+            rules: gc.rules,
+            variant: gc.variant,
+            opening: gc.opening,
+            best_of: gc.best_of,
+            takebacks: gc.takebacks,
+            time_control: gc.time_control,
+            commitment: gc.commitment,
+            channel_key: gc.channel_key.clone(),
+            min_rating: gc.min_rating,
+            max_rating: gc.max_rating,
         }
     }
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Default)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
 #[serde(crate = "near_sdk::serde")]
 pub struct GameConfigNear {
     pub(crate) opponent_id: Option<AccountId>,
-    pub(crate) referrer_id: Option<AccountId>
+    pub(crate) referrer_id: Option<AccountId>,
+    /// board side length, 25 by default; the number of columns in gravity games
+    pub(crate) board_size: Option<u8>,
+    /// gravity games only: number of rows, `board_size` by default
+    pub(crate) board_height: Option<u8>,
+    /// number of pieces in a row needed to win, 5 by default
+    pub(crate) win_length: Option<u8>,
+    /// if false, lines longer than `win_length` don't win; true by default
+    pub(crate) overline_wins: Option<bool>,
+    /// if true, completing a line loses; false by default
+    pub(crate) misere: Option<bool>,
+    /// if true, pieces fall to the lowest empty row of the column; false by default
+    pub(crate) gravity: Option<bool>,
+    /// `Variant::Freestyle` by default
+    pub(crate) variant: Option<Variant>,
+    /// `Opening::Standard` by default
+    pub(crate) opening: Option<Opening>,
+    /// number of games in the match: 1, 3, 5 or 7; 1 by default
+    pub(crate) best_of: Option<u8>,
+    /// takebacks each player gets per game, up to `MAX_TAKEBACKS`; 0 by default
+    pub(crate) takebacks: Option<u8>,
+    /// initial time and increment per move in seconds, replaces the game and turn duration limits;
+    /// no clock by default
+    pub(crate) time_control: Option<TimeControl>,
+    /// `CoinToss::commitment` of a secret revealed with `reveal_secret` once the game is created,
+    /// the first player is drawn from both secrets; `env::random_seed` is used by default
+    pub(crate) commitment: Option<Base58CryptoHash>,
+    /// ed25519 key signing the moves played off-chain, see `submit_channel_state`;
+    /// moves are sent on-chain by default
+    pub(crate) channel_key: Option<PublicKey>,
+    /// only opponents rated at least `min_rating` can start the game; not bounded by default
+    pub(crate) min_rating: Option<u32>,
+    /// only opponents rated at most `max_rating` can start the game; not bounded by default
+    pub(crate) max_rating: Option<u32>,
+}
+
+impl GameConfigNear {
+    /// Rules picked by the challenger, missing options are taken from `DEFAULT_RULES`
+    pub fn rules(&self) -> MnkRules {
+        let board_size = self.board_size.unwrap_or(DEFAULT_RULES.width);
+        MnkRules::new(
+            board_size,
+            self.board_height.unwrap_or(board_size),
+            self.win_length.unwrap_or(DEFAULT_RULES.win_length),
+        )
+        .with_overline_wins(self.overline_wins.unwrap_or(DEFAULT_RULES.overline_wins))
+        .with_misere(self.misere.unwrap_or(DEFAULT_RULES.misere))
+        .with_gravity(self.gravity.unwrap_or(DEFAULT_RULES.gravity))
+    }
 }
 
 impl GameConfig {
@@ -51,6 +139,41 @@ impl GameConfig {
             referrer_id: None,
             created_at: nano_to_sec(env::block_timestamp()).into(),
             available_until: 0,
+            rules: DEFAULT_RULES,
+            variant: Variant::Freestyle,
+            opening: Opening::Standard,
+            best_of: 1,
+            takebacks: 0,
+            time_control: None,
+            commitment: None,
+            channel_key: None,
+            min_rating: None,
+            max_rating: None,
+        }
+    }
+    /// `GameConfig` of an offer made with `make_available`, missing options take their defaults
+    pub fn from_game_config_near(
+        deposit: Balance,
+        game_config: GameConfigNear,
+        created_at: Timestamp,
+        available_until: Timestamp,
+    ) -> Self {
+        Self {
+            deposit,
+            rules: game_config.rules(),
+            opponent_id: game_config.opponent_id,
+            referrer_id: game_config.referrer_id,
+            created_at,
+            available_until,
+            variant: game_config.variant.unwrap_or_default(),
+            opening: game_config.opening.unwrap_or_default(),
+            best_of: game_config.best_of.unwrap_or(1),
+            takebacks: game_config.takebacks.unwrap_or(0),
+            time_control: game_config.time_control,
+            commitment: game_config.commitment,
+            channel_key: game_config.channel_key,
+            min_rating: game_config.min_rating,
+            max_rating: game_config.max_rating,
         }
     }
     /// `GameConfig` from transfer message
@@ -64,6 +187,21 @@ impl GameConfig {
             referrer_id: game_args.referrer_id.clone(),
             created_at: nano_to_sec(env::block_timestamp()).into(),
             available_until: 0,
+            rules: DEFAULT_RULES,
+            variant: Variant::Freestyle,
+            opening: Opening::Standard,
+            best_of: 1,
+            takebacks: 0,
+            time_control: None,
+            commitment: None,
+            channel_key: None,
+            min_rating: None,
+            max_rating: None,
         }
     }
+    /// Returns true if an opponent with `rating` may start the game
+    pub fn accepts_rating(&self, rating: &Rating) -> bool {
+        !matches!(self.min_rating, Some(min_rating) if rating.rating < min_rating as f64)
+            && !matches!(self.max_rating, Some(max_rating) if rating.rating > max_rating as f64)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,194 @@
+use crate::*;
+
+pub(crate) type MatchId = u64;
+
+/// Allowed number of games in a match, 1 is a single game without a `Match`
+pub(crate) const BEST_OF: [u8; 4] = [1, 3, 5, 7];
+
+/// Series of up to `best_of` games between the same players funded by one stake.
+/// Pieces alternate each game and the stake is paid once the series is decided.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct Match {
+    /// `players.0` plays O in the first game
+    pub players: (AccountId, AccountId),
+    pub best_of: u8,
+    /// games won by `players.0` and `players.1`
+    pub wins: (u8, u8),
+    /// games played so far, the last one may still be running
+    pub games: Vec<GameId>,
+    /// escrowed deposits of both players
+    pub reward: GameDeposit,
+    pub rules: MnkRules,
+    pub variant: Variant,
+    pub opening: Opening,
+    /// takebacks each player gets per game
+    pub takebacks: u8,
+    pub time_control: Option<TimeControl>,
+    pub state: GameState,
+    /// `None` until the match is decided, or if it was cancelled
+    pub result: Option<GameResult>,
+}
+
+impl Match {
+    fn wins_needed(&self) -> u8 {
+        self.best_of / 2 + 1
+    }
+
+    /// Adds the result of the last game
+    fn record(&mut self, winner: Option<&AccountId>) {
+        match winner {
+            Some(winner) if *winner == self.players.0 => self.wins.0 += 1,
+            Some(winner) if *winner == self.players.1 => self.wins.1 += 1,
+            Some(winner) => panic!("Account @{} doesn't play in this match", winner),
+            None => {}
+        }
+    }
+
+    /// The result once a player can't be caught up or all games are played
+    fn decided_result(&self) -> Option<GameResult> {
+        let wins_needed = self.wins_needed();
+        if self.wins.0 >= wins_needed {
+            return Some(GameResult::Win(self.players.0.clone()));
+        }
+        if self.wins.1 >= wins_needed {
+            return Some(GameResult::Win(self.players.1.clone()));
+        }
+        if self.games.len() < self.best_of as usize {
+            return None;
+        }
+        // ties left the majority out of reach
+        Some(match self.wins.0.cmp(&self.wins.1) {
+            std::cmp::Ordering::Greater => GameResult::Win(self.players.0.clone()),
+            std::cmp::Ordering::Less => GameResult::Win(self.players.1.clone()),
+            std::cmp::Ordering::Equal => GameResult::Tie,
+        })
+    }
+
+    /// Players of the next game: `players.0` plays O in odd games
+    fn next_game_players(&self) -> (AccountId, AccountId) {
+        match self.games.len() % 2 {
+            0 => self.players.clone(),
+            _ => (self.players.1.clone(), self.players.0.clone()),
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn get_match(&self, match_id: MatchId) -> Match {
+        self.matches.get(&match_id).expect("Match not found")
+    }
+
+    /// Up to `limit` matches which aren't decided yet, starting at `from_index`
+    pub fn get_active_matches(&self, from_index: u64, limit: u64) -> Vec<(MatchId, Match)> {
+        self.active_matches
+            .as_vector()
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .map(|match_id| (match_id, self.get_match(match_id)))
+            .collect()
+    }
+}
+
+impl Contract {
+    /// Escrows the stake of both players and starts the first game of the match
+    pub(crate) fn internal_start_match(
+        &mut self,
+        players: (AccountId, AccountId),
+        reward: GameDeposit,
+        config: &GameConfig,
+    ) -> GameId {
+        let match_id = self.next_match_id;
+        self.next_match_id += 1;
+        let mut game_match = Match {
+            players,
+            best_of: config.best_of,
+            wins: (0, 0),
+            games: Vec::with_capacity(config.best_of as usize),
+            reward,
+            rules: config.rules,
+            variant: config.variant,
+            opening: config.opening,
+            takebacks: config.takebacks,
+            time_control: config.time_control,
+            state: GameState::Active,
+            result: None,
+        };
+        let game_id = self.internal_start_match_game(match_id, &mut game_match);
+        self.matches.insert(&match_id, &game_match);
+        self.active_matches.insert(&match_id);
+        self.internal_update_stats(&game_match.players.0, UpdateStatsAction::AddPlayedMatch, None, None);
+        self.internal_update_stats(&game_match.players.1, UpdateStatsAction::AddPlayedMatch, None, None);
+        game_id
+    }
+
+    fn internal_start_match_game(&mut self, match_id: MatchId, game_match: &mut Match) -> GameId {
+        let game_id = self.next_game_id;
+        self.next_game_id += 1;
+        let (first_player, second_player) = game_match.next_game_players();
+        let mut game = Game::create_game(
+            first_player,
+            second_player,
+            game_match.reward.clone(),
+            game_match.rules,
+            game_match.variant,
+            game_match.opening,
+        );
+        game.match_id = Some(match_id);
+        game.takebacks_left = (game_match.takebacks, game_match.takebacks);
+        game.set_time_control(game_match.time_control);
+        game.change_state(GameState::Active);
+        self.games.insert(&game_id, &game);
+        game_match.games.push(game_id);
+        log!("Match {}: game {} of {} started", match_id, game_match.games.len(), game_match.best_of);
+        game_id
+    }
+
+    /// Ends the match without a result and returns its escrowed stake
+    pub(crate) fn internal_cancel_match(&mut self, match_id: MatchId) -> GameDeposit {
+        let mut game_match = self.get_match(match_id);
+        game_match.state = GameState::Finished;
+        self.matches.insert(&match_id, &game_match);
+        self.active_matches.remove(&match_id);
+        log!("Match {} is cancelled", match_id);
+        game_match.reward
+    }
+
+    /// Records the result of a match game. Pays the stake once the match is decided,
+    /// otherwise starts the next game with swapped pieces. Returns the paid amount.
+    pub(crate) fn internal_record_match_game(&mut self, match_id: MatchId, winner: Option<&AccountId>) -> U128 {
+        let mut game_match = self.get_match(match_id);
+        game_match.record(winner);
+        if let Some(winner_id) = winner {
+            self.internal_update_stats(winner_id, UpdateStatsAction::AddWonGame, None, None);
+        }
+        let balance = match game_match.decided_result() {
+            Some(result) => {
+                let match_winner = match &result {
+                    GameResult::Win(winner_id) => Some(winner_id.clone()),
+                    GameResult::Tie => None,
+                };
+                log!("Match {} is over: {:?}", match_id, result);
+                let balance = self.internal_settle(&game_match.reward, &game_match.players, match_winner.as_ref());
+                if let Some(winner_id) = match_winner {
+                    self.internal_update_stats(&winner_id, UpdateStatsAction::AddWonMatch, None, None);
+                }
+                game_match.state = GameState::Finished;
+                game_match.result = Some(result);
+                self.active_matches.remove(&match_id);
+                balance
+            }
+            None => {
+                self.internal_start_match_game(match_id, &mut game_match);
+                self.internal_update_stats(&game_match.players.0, UpdateStatsAction::AddPlayedGame, None, None);
+                self.internal_update_stats(&game_match.players.1, UpdateStatsAction::AddPlayedGame, None, None);
+                U128(0)
+            }
+        };
+        self.matches.insert(&match_id, &game_match);
+        balance
+    }
+}
@@ -20,9 +20,38 @@ impl Contract {
         self.max_turn_duration = self.max_game_duration / MAX_NUM_TURNS;
         true
     }
+
+    /// One-off cleanup of the boards kept under `StorageKey::GameBoard` by games
+    /// created before boards were stored inline in `Game`. Sweeps up to `limit`
+    /// game ids starting from `from_game_id` and returns the number of bytes reclaimed.
+    #[private]
+    pub fn clear_game_boards(&mut self, from_game_id: GameId, limit: u64) -> U64 {
+        let storage_before = env::storage_usage();
+        let to_game_id = from_game_id.saturating_add(limit).min(self.next_game_id);
+        for game_id in from_game_id..to_game_id {
+            Self::internal_clear_game_board(game_id);
+        }
+        let reclaimed = storage_before - env::storage_usage();
+        log!("Cleared boards of games {}..{}, reclaimed {} bytes", from_game_id, to_game_id, reclaimed);
+        reclaimed.into()
+    }
 }
 
 impl Contract {
+    /// Removes the entries of an `UnorderedMap<Coords, Piece>` stored under `StorageKey::GameBoard`.
+    /// The map length was kept in the already removed `Game`, so entries are read until the first gap.
+    fn internal_clear_game_board(game_id: GameId) {
+        let prefix = StorageKey::GameBoard { game_id }.into_storage_key();
+        let lookup = |id: u8, suffix: &[u8]| [prefix.as_slice(), &[id], suffix].concat();
+        let mut index: u64 = 0;
+        while let Some(raw_key) = env::storage_read(&lookup(b'k', &index.to_le_bytes())) {
+            env::storage_remove(&lookup(b'i', &raw_key));
+            env::storage_remove(&lookup(b'k', &index.to_le_bytes()));
+            env::storage_remove(&lookup(b'v', &index.to_le_bytes()));
+            index += 1;
+        }
+    }
+
     pub(crate) fn internal_get_available_player(&self, account_id: &AccountId) -> GameConfig {
         self.available_players
             .get(account_id)
@@ -33,12 +62,26 @@ impl Contract {
         let expired_games_ids: Vec<GameId> = self
             .games
             .iter()
-            .filter(|(_, game)| ts - game.initiated_at > self.max_game_duration)
+            .filter(|(_, game)| match game.clock {
+                _ if game.game_state == GameState::NotStarted => game.is_reveal_expired(ts),
+                _ if game.channel.is_some() => game.is_challenge_expired(ts),
+                Some(_) => game.is_out_of_time(ts),
+                None => ts - game.initiated_at > self.max_game_duration,
+            })
             .map(|(game_id, _)| game_id)
             .collect();
         if !expired_games_ids.is_empty() {
             for game_id in expired_games_ids.iter() {
                 let game = self.internal_get_game(game_id);
+                if game.game_state == GameState::NotStarted {
+                    self.internal_expire_coin_toss(game_id);
+                    continue;
+                }
+                if game.channel.is_some() {
+                    log!("GameId: {}. @{} didn't answer the channel state in time", game_id, game.current_player_account_id());
+                    self.internal_stop_expired_game(game_id, game.current_player_account_id());
+                    continue;
+                }
                 self.internal_stop_expired_game(game_id, game.current_player_account_id());
                 log!(
                     "GameId: {}. Game duration expired. Required:{} Current:{} ",
@@ -80,7 +123,28 @@ impl Contract {
         game_id: &GameId,
         winner: Option<&AccountId>,
     ) -> U128 {
-        let reward = self.internal_get_game_reward(game_id);
+        self.internal_rate_game(game_id, winner);
+        let game = self.internal_get_game(game_id);
+        self.internal_record_season_game(&game.players, winner);
+        if let Some(match_id) = game.match_id {
+            return self.internal_record_match_game(match_id, winner);
+        }
+        if let Some(tournament_id) = game.tournament_id {
+            return self.internal_record_tournament_game(tournament_id, game_id, &game.players, winner);
+        }
+        if let Some(winner_id) = winner {
+            self.internal_update_stats(winner_id, UpdateStatsAction::AddWonGame, None, None);
+        }
+        self.internal_settle(&game.reward, &game.players, winner)
+    }
+
+    /// Pays `reward` minus the service fee to the winner, or splits it between `players` on a tie
+    pub(crate) fn internal_settle(
+        &mut self,
+        reward: &GameDeposit,
+        players: &(AccountId, AccountId),
+        winner: Option<&AccountId>,
+    ) -> U128 {
         let players_deposit = reward.balance;
         let fees_amount = players_deposit
             .0
@@ -93,16 +157,10 @@ impl Contract {
 
         if let Some(winner_id) = winner {
             log!("Winner is {}. Reward: {}", winner_id, winner_reward);
-            let stats = self.get_stats(winner_id);
             self.internal_transfer(winner_id, winner_reward.into());
 
-            self.internal_distribute_fee(fees_amount, winner_id);
-            self.internal_update_stats(
-                winner_id,
-                UpdateStatsAction::AddWonGame,
-                None,
-                None,
-            );
+            let referrer_fee = self.internal_distribute_fee(fees_amount, winner_id);
+            self.internal_fund_season(fees_amount - referrer_fee);
             self.internal_update_stats(
                 winner_id,
                 UpdateStatsAction::AddTotalReward,
@@ -120,7 +178,8 @@ impl Contract {
                 "Incorrect Tie refund amount calculation"
             );
             log!("Tie. Refund: {}", refund_amount);
-            self.internal_tie_refund(game_id, refund_amount);
+            self.internal_tie_refund(players, refund_amount);
+            self.internal_fund_season(fees_amount);
             refund_amount.into()
         }
     }
@@ -165,12 +224,11 @@ impl Contract {
 
     pub(crate) fn internal_tie_refund(
         &mut self,
-        game_id: &GameId,
+        players: &(AccountId, AccountId),
         refund_amount: Balance,
     ) {
-        let (player1, player2) = self.internal_get_game_players(game_id);
-        self.internal_transfer(&player1, refund_amount.into());
-        self.internal_transfer(&player2, refund_amount.into());
+        self.internal_transfer(&players.0, refund_amount.into());
+        self.internal_transfer(&players.1, refund_amount.into());
     }
 
     pub(crate) fn internal_stop_expired_game(&mut self, game_id: &GameId, looser: AccountId) {
@@ -197,16 +255,7 @@ impl Contract {
         game.change_state(GameState::Finished);
         self.internal_update_game(game_id, &game);
 
-        let game_to_store = GameLimitedView {
-            game_result: views::GameResult::Win(winner),
-            player1,
-            player2,
-            reward_or_tie_refund: GameDeposit {
-                balance,
-            },
-            tiles: game.to_tiles(),
-            last_move: None,
-        };
+        let game_to_store = GameLimitedView::finished(&game, GameResult::Win(winner), ResultReason::Timeout, balance);
         self.internal_store_game(game_id, &game_to_store);
         assert_eq!(
             game.game_state,
@@ -224,16 +273,24 @@ impl Contract {
         }
     }
 
+    /// Players take part in one game at a time: a player can't start a game while playing
+    /// another one or while registered in a tournament they weren't eliminated from
     pub(crate) fn internal_check_player_available(&mut self, account_id: &AccountId) {
-        let has_games_started = self
-            .games
-            .iter()
-            .any(|(_game_id, game)| game.contains_player_account_id(account_id));
         assert!(
-            !has_games_started,
+            !self.internal_is_playing(account_id),
             "Player @{} already start another game",
             &account_id
-        )
+        );
+        if let Some(tournament_id) = self.tournament_players.get(account_id) {
+            panic!("Player @{} plays in tournament {}", account_id, tournament_id);
+        }
+    }
+
+    /// True if the player is in one of the running games
+    pub(crate) fn internal_is_playing(&self, account_id: &AccountId) -> bool {
+        self.games
+            .iter()
+            .any(|(_game_id, game)| game.contains_player_account_id(account_id))
     }
 
     pub(crate) fn internal_add_referrer(&mut self, player_id: &AccountId, referrer_id: &AccountId) {
@@ -256,6 +313,101 @@ impl Contract {
         }
     }
 
+    /// Pays out and stores `game` once a move decided it on the board
+    pub(crate) fn internal_finish_game(&mut self, game_id: &GameId, mut game: Game) -> Option<GameResult> {
+        let winner = game.winner.expect("The game isn't decided yet");
+        // change game state to Finished
+        game.change_state(GameState::Finished);
+        self.internal_update_game(game_id, &game);
+        // get winner account, if there is Tie - refund to both players
+        // with crop service fee amount from it
+        let winner_account: Option<&AccountId> = match winner {
+            game::Winner::X => game.get_player_acc_by_piece(Piece::X),
+            game::Winner::O => game.get_player_acc_by_piece(Piece::O),
+            game::Winner::Tie => None,
+        };
+
+        let balance = if let Some(winner) = winner_account {
+            // SOME WINNER
+            log!("\nGame over! {} won!", winner);
+            self.internal_distribute_reward(game_id, winner_account)
+        } else {
+            // TIE
+            log!("\nGame over! Tie!");
+            self.internal_distribute_reward(game_id, None)
+        };
+
+        let game_result = match winner_account {
+            Some(winner) => GameResult::Win(winner.clone()),
+            None => GameResult::Tie,
+        };
+
+        let game_to_store = GameLimitedView::finished(&game, game_result, game.rules.result_reason(winner), balance);
+
+        self.internal_store_game(game_id, &game_to_store);
+        self.games.remove(game_id);
+        game.get_winner()
+    }
+
+    /// Saves the turn timestamps of `game` after a player acted on it and
+    /// stops the game when the turn or the whole game took too long
+    pub(crate) fn internal_end_turn(&mut self, game_id: &GameId, mut game: Game, cur_timestamp: Duration) -> Option<GameResult> {
+        if game.game_state == GameState::Active {
+
+            game.total_turns += 1;
+            // previous turn timestamp
+            let previous_turn_timestamp = game.last_turn_timestamp;
+            // this turn timestamp
+            game.last_turn_timestamp = cur_timestamp;
+            // this game duration 
+            game.duration = cur_timestamp - game.initiated_at;
+
+            // the clock replaces the turn and game duration limits
+            let mover = game.player_index(&env::predecessor_account_id());
+            if let Some(clock) = game.clock.as_mut() {
+                if !clock.punch(mover, cur_timestamp) {
+                    log!("Clock of @{} ran out", env::predecessor_account_id());
+                    self.internal_stop_expired_game(game_id, env::predecessor_account_id());
+                } else {
+                    self.internal_update_game(game_id, &game);
+                }
+                return game.get_winner();
+            }
+
+            if previous_turn_timestamp == 0 {
+                if cur_timestamp - game.initiated_at > self.max_turn_duration {
+                    log!("Turn duration expired. Required:{} Current:{} ", self.max_turn_duration, cur_timestamp - game.initiated_at);
+                    // looser - current player
+                    self.internal_stop_expired_game(game_id, env::predecessor_account_id());
+                    return game.get_winner();
+                } else {
+                    self.internal_update_game(game_id, &game);
+                    return game.get_winner();
+                }
+            }
+
+            // expired turn time scenario - too long movement from current player
+            if game.last_turn_timestamp - previous_turn_timestamp > self.max_turn_duration {
+                log!("Turn duration expired. Required:{} Current:{} ", self.max_turn_duration, game.last_turn_timestamp - previous_turn_timestamp);
+                // looser - current player
+                self.internal_stop_expired_game(game_id, env::predecessor_account_id());
+                return game.get_winner();
+            };
+
+            if game.duration <= self.max_game_duration {
+                self.internal_update_game(game_id, &game);
+                return game.get_winner();
+            } else {
+                log!("Game duration expired. Required:{} Current:{} ", self.max_game_duration, game.duration);
+                // looser - current player
+                self.internal_stop_expired_game(game_id, env::predecessor_account_id());
+                return game.get_winner();
+            }
+        } else {
+            panic!("Something wrong with game id: {} state", game_id)
+        }
+    }
+
     pub(crate) fn internal_get_game(&self, game_id: &GameId) -> Game {
         self.games.get(game_id).expect("Game not found")
     }
@@ -269,11 +421,6 @@ impl Contract {
         game.get_player_accounts()
     }
 
-    pub(crate) fn internal_get_game_reward(&self, game_id: &GameId) -> GameDeposit {
-        let game = self.internal_get_game(game_id);
-        game.reward()
-    }
-
     pub(crate) fn get_stored_games_num(&self) -> u8 {
         return self.stored_games.len() as _;
     }
@@ -0,0 +1,80 @@
+pub(crate) use tic_tac_toe_rules::{rating_score, LeaderboardEntry, LeaderboardIndex, MIN_GAMES_FOR_WIN_RATE};
+
+use crate::*;
+
+/// Leaderboards of the contract, `TotalReward` is in CHEDDAR
+pub type Leaderboard = tic_tac_toe_rules::Leaderboard<RatingPool>;
+
+impl Stats {
+    /// Score of the account in a leaderboard built from the stats, `None` if it isn't listed
+    pub(crate) fn leaderboard_score(&self, board: Leaderboard) -> Option<u128> {
+        if self.games_num == 0 {
+            return None;
+        }
+        match board {
+            Leaderboard::Victories => Some(self.victories_num.into()),
+            Leaderboard::TotalReward => Some(self.total_reward),
+            Leaderboard::WinRate if self.games_num >= MIN_GAMES_FOR_WIN_RATE => {
+                Some((self.victories_num * BASIS_P as u64 / self.games_num).into())
+            }
+            Leaderboard::Penalties if self.penalties_num > 0 => Some(self.penalties_num.into()),
+            _ => None,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Accounts of `board` from the best one, ties are ordered by account id descending.
+    /// `from` is the last entry of the previous page, `None` for the first page.
+    pub fn get_leaderboard(&self, board: Leaderboard, from: Option<LeaderboardEntry>, limit: u64) -> Vec<LeaderboardEntry> {
+        self.leaderboards.get(&board).map(|index| index.page(from, limit)).unwrap_or_default()
+    }
+
+    /// Number of accounts in `board`
+    pub fn get_leaderboard_len(&self, board: Leaderboard) -> u64 {
+        self.leaderboards.get(&board).map(|index| index.len()).unwrap_or(0)
+    }
+
+    /// One-off backfill of the stats leaderboards with the accounts whose stats weren't updated
+    /// since the upgrade to leaderboards. Indexes up to `limit` accounts of `stats` starting
+    /// from `from_index` and returns the number of accounts left after them. Accounts already
+    /// listed keep their entries, so batches can be repeated.
+    #[private]
+    pub fn backfill_leaderboards(&mut self, from_index: u64, limit: u64) -> U64 {
+        let accounts = self.stats.keys_as_vector();
+        let to_index = from_index.saturating_add(limit).min(accounts.len());
+        let page: Vec<(AccountId, Stats)> = (from_index..to_index)
+            .map(|index| (accounts.get(index).unwrap(), self.stats.values_as_vector().get(index).unwrap()))
+            .collect();
+        let left = accounts.len() - to_index;
+        for (account_id, stats) in page {
+            for board in Leaderboard::STATS.iter() {
+                self.internal_update_leaderboard(*board, &account_id, None, stats.leaderboard_score(*board));
+            }
+        }
+        log!("Backfilled the leaderboards with accounts {}..{}, {} left", from_index, to_index, left);
+        left.into()
+    }
+}
+
+impl Contract {
+    /// Moves `account_id` from `old_score` to `new_score` in `board`, `None` isn't listed
+    pub(crate) fn internal_update_leaderboard(
+        &mut self,
+        board: Leaderboard,
+        account_id: &AccountId,
+        old_score: Option<u128>,
+        new_score: Option<u128>,
+    ) {
+        if old_score == new_score {
+            return;
+        }
+        let mut index = self
+            .leaderboards
+            .get(&board)
+            .unwrap_or_else(|| LeaderboardIndex::new(StorageKey::Leaderboard { board }));
+        index.update(account_id, old_score, new_score);
+        self.leaderboards.insert(&board, &index);
+    }
+}
@@ -2,43 +2,64 @@ use std::fs::create_dir;
 
 use near_contract_standards::storage_management::StorageBalance;
 use near_sdk::{
-    AccountId, Balance, BorshStorageKey, Gas, Duration, PanicOnDefault,
-    Promise, PromiseOrValue, PromiseResult, assert_one_yocto, Timestamp
+    AccountId, Balance, BorshStorageKey, Gas, Duration, IntoStorageKey, PanicOnDefault,
+    Promise, PromiseOrValue, PromiseResult, assert_one_yocto, Timestamp, PublicKey, CurveType
 };
 use near_sdk::{
     env, ext_contract, log, near_bindgen, ONE_YOCTO, require
 };
-use near_sdk::json_types::{U128};
+use near_sdk::json_types::{Base58CryptoHash, Base64VecU8, U128, U64};
 use near_sdk::borsh::{self, BorshSerialize, BorshDeserialize};
 use near_sdk::serde::{Serialize, Deserialize};
 use near_sdk::collections::{UnorderedMap, UnorderedSet};
 use stats::UserPenalties;
 use views::{GameLimitedView};
+use tic_tac_toe_rules::{forbidden_move, Grid, MnkRules, Rating, Ruleset};
 
 mod callbacks;
+mod channel;
+mod coin_toss;
 mod config;
 mod game;
 mod game_config;
+mod game_match;
 mod internal;
+mod leaderboard;
+mod migration;
+mod notation;
+mod opening;
 mod player;
+mod queue;
+mod rating;
+mod season;
 mod stats;
 mod token_receiver;
+mod tournament;
 mod views;
 mod utils;
 
+use crate::channel::*;
 use crate::config::*;
 use crate::game::*;
 use crate::game_config::*;
+use crate::game_match::*;
+use crate::leaderboard::*;
+use crate::opening::*;
 use crate::player::*;
+use crate::queue::*;
+use crate::rating::*;
+use crate::season::*;
 use crate::stats::*;
 use crate::token_receiver::*;
+use crate::tournament::*;
 use crate::utils::*;
-use crate::views::GameResult;
+use crate::views::{GameResult, ResultReason};
 
 #[derive(BorshSerialize, BorshStorageKey)]
 pub enum StorageKey {
     Games,
     StoredGames,
+    /// boards of games created before they were stored inline in `Game`
     GameBoard {game_id: GameId},
     Players,
     /* * */
@@ -47,6 +68,18 @@ pub enum StorageKey {
     TotalRewards {account_id : AccountId},
     TotalAffiliateRewards {account_id : AccountId},
     RegisteredPlayers,
+    Matches,
+    Queue,
+    Ratings,
+    Leaderboards,
+    Leaderboard {board: Leaderboard},
+    Seasons,
+    SeasonStats,
+    Tournaments,
+    ActiveMatches,
+    TournamentPlayers,
+    QueueBracket {bracket: u8},
+    QueuedPlayers,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -82,6 +115,30 @@ pub struct Contract {
     pub stored_games: UnorderedMap<GameId, GameLimitedView>,
     /// registered players and their total_rewards and deposit NEAR to cover storage
     pub registered_players: UnorderedMap<AccountId, Vault>,
+    /// best-of-N matches, finished ones are kept for the views
+    matches: UnorderedMap<MatchId, Match>,
+    /// `MatchId` which will be set for next created `Match`
+    next_match_id: MatchId,
+    /// matchmaking queue of each stake bracket
+    queue: UnorderedMap<u8, QueueIndex>,
+    /// Glicko-2 ratings of the players in each `RatingPool`
+    ratings: UnorderedMap<(RatingPool, AccountId), PlayerRating>,
+    /// sorted indexes of the accounts
+    leaderboards: UnorderedMap<Leaderboard, LeaderboardIndex>,
+    seasons: UnorderedMap<SeasonId, Season>,
+    /// `SeasonId` which will be set for next created `Season`
+    next_season_id: SeasonId,
+    season_stats: UnorderedMap<(SeasonId, AccountId), SeasonStats>,
+    /// finished and cancelled tournaments are kept for the views
+    tournaments: UnorderedMap<TournamentId, Tournament>,
+    /// `TournamentId` which will be set for next created `Tournament`
+    next_tournament_id: TournamentId,
+    /// matches which aren't decided yet
+    active_matches: UnorderedSet<MatchId>,
+    /// players registered in a tournament until they are eliminated, see `internal_check_player_available`
+    tournament_players: UnorderedMap<AccountId, TournamentId>,
+    /// stake bracket and `joined_at` of the players in the matchmaking queue
+    queued_players: UnorderedMap<AccountId, (u8, Timestamp)>,
 }
 #[near_bindgen]
 impl Contract {
@@ -111,6 +168,19 @@ impl Contract {
             max_stored_games: config.max_stored_games,
             stored_games: UnorderedMap::new(StorageKey::StoredGames),
             registered_players: UnorderedMap::new(StorageKey::RegisteredPlayers),
+            matches: UnorderedMap::new(StorageKey::Matches),
+            next_match_id: 0,
+            queue: UnorderedMap::new(StorageKey::Queue),
+            ratings: UnorderedMap::new(StorageKey::Ratings),
+            leaderboards: UnorderedMap::new(StorageKey::Leaderboards),
+            seasons: UnorderedMap::new(StorageKey::Seasons),
+            next_season_id: 0,
+            season_stats: UnorderedMap::new(StorageKey::SeasonStats),
+            tournaments: UnorderedMap::new(StorageKey::Tournaments),
+            next_tournament_id: 0,
+            active_matches: UnorderedSet::new(StorageKey::ActiveMatches),
+            tournament_players: UnorderedMap::new(StorageKey::TournamentPlayers),
+            queued_players: UnorderedMap::new(StorageKey::QueuedPlayers),
         }
     }
 
@@ -132,25 +202,28 @@ impl Contract {
         let deposit: Balance = bet;
         assert!(bet >= MIN_BET_CHEDDAR, "Bet is too small. Required at least: {}", MIN_DEPOSIT_CHEDDAR);
 
-        let (opponent_id, referrer_id) = if let Some(game_config) = game_config {
-            (game_config.opponent_id, game_config.referrer_id.clone())
-        } else {
-            (None, None)
-        };
-        self.available_players.insert(account_id,
-            &GameConfig {
-                deposit,
-                opponent_id,
-                referrer_id: referrer_id.clone(),
-                created_at: nano_to_sec(cur_timestamp),
-                available_until: nano_to_sec(cur_timestamp) + available_for,
-            }
+        let config = GameConfig::from_game_config_near(
+            deposit,
+            game_config.unwrap_or_default(),
+            nano_to_sec(cur_timestamp),
+            nano_to_sec(cur_timestamp) + available_for,
         );
+        validate_rules(&config.rules);
+        validate_best_of(config.best_of);
+        validate_takebacks(config.takebacks);
+        if let Some(time_control) = &config.time_control {
+            validate_time_control(time_control);
+        }
+        validate_rating_bounds(config.min_rating, config.max_rating);
+        if let Some(channel_key) = &config.channel_key {
+            validate_channel_key(channel_key, &config);
+        }
+        self.available_players.insert(account_id, &config);
         
         self.internal_check_player_available(&account_id);
 
-        if let Some(referrer_id) = referrer_id {
-            self.internal_add_referrer( &account_id, &referrer_id);
+        if let Some(referrer_id) = &config.referrer_id {
+            self.internal_add_referrer( &account_id, referrer_id);
         }
     }
 
@@ -286,8 +359,8 @@ impl Contract {
             self.internal_check_player_available(&player_1_id);
 
             // we can't play in parallel with someone else?
-            if let Some(player_id) = player_2_config.opponent_id {
-                assert_eq!(player_id, player_1_id, "Wrong account");
+            if let Some(player_id) = &player_2_config.opponent_id {
+                assert_eq!(*player_id, player_1_id, "Wrong account");
             }
 
             // Deposits from two players must be equal
@@ -299,7 +372,62 @@ impl Contract {
                 player_2_config.deposit
             );
 
-            let game_id = self.next_game_id;
+            // Both players must agree on board size and win length
+            assert_eq!(
+                player_1_config.rules,
+                player_2_config.rules,
+                "Mismatched game rules for players! You: {:?}, Opponent {:?}",
+                player_1_config.rules,
+                player_2_config.rules
+            );
+            assert_eq!(
+                player_1_config.variant,
+                player_2_config.variant,
+                "Mismatched game variants for players! You: {:?}, Opponent {:?}",
+                player_1_config.variant,
+                player_2_config.variant
+            );
+            assert_eq!(
+                player_1_config.opening,
+                player_2_config.opening,
+                "Mismatched openings for players! You: {:?}, Opponent {:?}",
+                player_1_config.opening,
+                player_2_config.opening
+            );
+            assert_eq!(
+                player_1_config.best_of,
+                player_2_config.best_of,
+                "Mismatched match lengths for players! You: best of {}, Opponent best of {}",
+                player_1_config.best_of,
+                player_2_config.best_of
+            );
+            assert_eq!(
+                player_1_config.takebacks,
+                player_2_config.takebacks,
+                "Mismatched takebacks for players! You: {}, Opponent {}",
+                player_1_config.takebacks,
+                player_2_config.takebacks
+            );
+            assert_eq!(
+                player_1_config.time_control,
+                player_2_config.time_control,
+                "Mismatched time controls for players! You: {:?}, Opponent {:?}",
+                player_1_config.time_control,
+                player_2_config.time_control
+            );
+            assert_eq!(
+                player_1_config.commitment.is_some(),
+                player_2_config.commitment.is_some(),
+                "Mismatched first player draws! Both players must commit a secret or none"
+            );
+            assert_eq!(
+                player_1_config.channel_key.is_some(),
+                player_2_config.channel_key.is_some(),
+                "Mismatched channel modes! Both players must register a channel key or none"
+            );
+            self.internal_check_rating_bounds(&player_2_config, &player_1_id);
+            self.internal_check_rating_bounds(&player_1_config, &player_2_id);
+
             // deposit * 2
             let balance = match player_2_config.deposit.checked_mul(2) {
                 Some(value) => value,
@@ -312,15 +440,33 @@ impl Contract {
             log!("game reward:{} in token {:?} ", balance, self.cheddar);
             
             let seed = near_sdk::env::random_seed();
-            let (first_player, second_player) = match seed[0] % 2 {
-                0 => (player_2_id.clone(), player_1_id.clone()),
-                _ => (player_1_id.clone(), player_2_id.clone())
+            let (first_player, second_player) = if player_2_config.commitment.is_some() {
+                // the order is drawn from the secrets once both are revealed
+                (player_1_id.clone(), player_2_id.clone())
+            } else {
+                match seed[0] % 2 {
+                    0 => (player_2_id.clone(), player_1_id.clone()),
+                    _ => (player_1_id.clone(), player_2_id.clone())
+                }
             };
-            let mut game = Game::create_game(game_id, first_player, second_player, reward);
-            game.change_state(GameState::Active);
-            self.games.insert(&game_id, &game);
-
-            self.next_game_id += 1;
+            let game_id = if player_2_config.best_of > 1 {
+                self.internal_start_match((first_player, second_player), reward, &player_2_config)
+            } else {
+                let game_id = self.next_game_id;
+                let mut game = Game::create_game(first_player, second_player, reward, player_2_config.rules, player_2_config.variant, player_2_config.opening);
+                game.takebacks_left = (player_2_config.takebacks, player_2_config.takebacks);
+                game.set_time_control(player_2_config.time_control);
+                game.change_state(GameState::Active);
+                self.games.insert(&game_id, &game);
+                self.next_game_id += 1;
+                game_id
+            };
+            if let (Some(commitment_1), Some(commitment_2)) = (player_1_config.commitment, player_2_config.commitment) {
+                self.internal_start_coin_toss(&game_id, [commitment_1, commitment_2]);
+            }
+            if let (Some(key_1), Some(key_2)) = (player_1_config.channel_key, player_2_config.channel_key) {
+                self.internal_open_channel(&game_id, &player_1_id, key_1, key_2);
+            }
             self.available_players.remove(&player_1_id);
             self.available_players.remove(&player_2_id);
 
@@ -354,8 +500,17 @@ impl Contract {
         }
     }
 
-    pub fn make_move(&mut self, game_id: &GameId, coords: Coords) -> Option<GameResult> {
+    /// Places a piece on `coords`. In gravity games the piece can be dropped
+    /// into column `col` instead, it falls to the lowest empty row.
+    pub fn make_move(&mut self, game_id: &GameId, coords: Option<Coords>, col: Option<u8>) -> Option<GameResult> {
         let cur_timestamp: Duration = nano_to_sec(env::block_timestamp()) as Duration;
+        let game = self.internal_get_game(game_id);
+        if game.is_out_of_time(cur_timestamp) {
+            // a move after the flag fell doesn't count
+            log!("Clock of @{} ran out", game.current_player_account_id());
+            self.internal_stop_expired_game(game_id, game.current_player_account_id());
+            return Some(self.get_game(game_id).game_result);
+        }
         //checkpoint
         self.internal_ping_expired_games(cur_timestamp);
 
@@ -363,69 +518,14 @@ impl Contract {
 
         assert_eq!(env::predecessor_account_id(), game.current_player_account_id(), "not your turn");
         assert_eq!(game.game_state, GameState::Active, "Current game isn't active");
-        match game.check_move(&coords) {
-            Ok(_) => {
-                // fill board tile with current player piece
-                game.board.insert(&coords, &game.current_piece);
-                // set the last move 
-                game.last_move = Some(coords.clone());
-                // switch piece to other one
-                game.current_piece = game.current_piece.other();
-                game.current_piece = game.current_piece;
-                // switch player
-                game.current_player_index = 1 - game.current_player_index;
-                game.update_winner(&coords);
-                if let Some(winner) = game.winner.clone() {
-                    // change game state to Finished
-                    game.change_state(GameState::Finished);
-                    self.internal_update_game(game_id, &game);
-                    // get winner account, if there is Tie - refund to both players
-                    // with crop service fee amount from it
-                    let winner_account:Option<&AccountId> = match winner {
-                        game::Winner::X => game.get_player_acc_by_piece(Piece::X),
-                        game::Winner::O => game.get_player_acc_by_piece(Piece::O),
-                        game::Winner::Tie => None,
-                    };
-               
-                    let balance = if winner_account.is_some() {
-                        // SOME WINNER
-                        log!("\nGame over! {} won!", winner_account.unwrap());
-                        self.internal_distribute_reward(game_id, winner_account)
-                    } else {
-                        // TIE
-                        log!("\nGame over! Tie!");
-                        self.internal_distribute_reward(game_id, None)
-                    };
-
-                    let game_result = match winner_account {
-                        Some(winner) => GameResult::Win(winner.clone()),
-                        None => GameResult::Tie,
-                    };
-
-                    let (player1, player2) = game.get_player_accounts();
-
-                    let game_to_store = GameLimitedView{
-                        game_result,
-                        player1,
-                        player2,
-                        reward_or_tie_refund: GameDeposit {
-                            balance
-                        },
-                        tiles: game.to_tiles(),
-                        last_move: Some((coords, game.current_piece.other())),
-                    };
-
-                    self.internal_store_game(game_id, &game_to_store);
-                    assert_eq!(
-                        game.game_state,
-                        GameState::Finished,
-                        "Cannot stop. Game in progress"
-                    );
-                    let game_result = game.get_winner();
-
-                    self.games.remove(game_id);
-                    
-                    return game_result;
+        assert!(game.opening_phase.is_none(), "Opening isn't finished yet");
+        assert!(game.channel.is_none(), "Moves of channel games are signed off-chain");
+        match game.move_coords(coords, col).and_then(|coords| game.check_move(&coords).map(|_| coords)) {
+            Ok(coords) => {
+                game.expire_offers(&env::predecessor_account_id());
+                game.place_stone(coords, cur_timestamp);
+                if game.winner.is_some() {
+                    return self.internal_finish_game(game_id, game);
                 };
             },
             Err(e) => match e {
@@ -435,50 +535,16 @@ impl Contract {
                 MoveError::TileFilled { other_piece, row, col } => panic!(
                     "The tile row: {} col: {} already contained another piece: {:?}", row, col, other_piece
                 ),
-            },
-        }
-        if game.game_state == GameState::Active {
-
-            game.total_turns += 1;
-            // previous turn timestamp
-            let previous_turn_timestamp = game.last_turn_timestamp;
-            // this turn timestamp
-            game.last_turn_timestamp = cur_timestamp;
-            // this game duration 
-            game.duration = cur_timestamp - game.initiated_at;
-
-            if previous_turn_timestamp == 0 {
-                if cur_timestamp - game.initiated_at > self.max_turn_duration {
-                    log!("Turn duration expired. Required:{} Current:{} ", self.max_turn_duration, cur_timestamp - game.initiated_at);
-                    // looser - current player
-                    self.internal_stop_expired_game(game_id, env::predecessor_account_id());
-                    return game.get_winner();
-                } else {
-                    self.internal_update_game(game_id, &game);
-                    return game.get_winner();
+                MoveError::Forbidden { kind, row, col } => panic!(
+                    "The move row: {} col: {} is forbidden: {:?}", row, col, kind
+                ),
+                MoveError::ColumnFull { col } => panic!("The column {} is full", col),
+                MoveError::InvalidSubBoard { .. } | MoveError::WrongSubBoard { .. } | MoveError::SubBoardFinished { .. } => {
+                    unreachable!("Games of this contract have no sub-boards")
                 }
-            }
-
-            // expired turn time scenario - too long movement from current player
-            if game.last_turn_timestamp - previous_turn_timestamp > self.max_turn_duration {
-                log!("Turn duration expired. Required:{} Current:{} ", self.max_turn_duration, game.last_turn_timestamp - previous_turn_timestamp);
-                // looser - current player
-                self.internal_stop_expired_game(game_id, env::predecessor_account_id());
-                return game.get_winner();
-            };
-
-            if game.duration <= self.max_game_duration {
-                self.internal_update_game(game_id, &game);
-                return game.get_winner();
-            } else {
-                log!("Game duration expired. Required:{} Current:{} ", self.max_game_duration, game.duration);
-                // looser - current player
-                self.internal_stop_expired_game(game_id, env::predecessor_account_id());
-                return game.get_winner();
-            }
-        } else {
-            panic!("Something wrong with game id: {} state", game_id)
+            },
         }
+        self.internal_end_turn(game_id, game, cur_timestamp)
 
     }
 
@@ -509,40 +575,100 @@ impl Contract {
             "Cannot stop. Game in progress"
         );
 
-        return self.store_game(game_id, &winner, &game.get_opponent(&winner), balance);
+        return self.store_game(game_id, &winner, balance, ResultReason::GiveUp);
+    }
+
+    /// Offers the opponent a draw, the offer stands until the opponent moves
+    pub fn offer_draw(&mut self, game_id: &GameId) {
+        let mut game: Game = self.internal_get_game(game_id);
+        assert_eq!(game.game_state, GameState::Active, "Current game isn't active");
+        let account_id = env::predecessor_account_id();
+        assert!(game.contains_player_account_id(&account_id), "You are not in this game. GameId: {} ", game_id);
+        assert!(game.draw_offer.is_none(), "Draw is already offered");
+        log!("@{} offers a draw in game {}", account_id, game_id);
+        game.draw_offer = Some(account_id);
+        self.internal_update_game(game_id, &game);
+    }
+
+    /// Accepts the draw offered by the opponent, the reward is refunded as in a tie
+    #[payable]
+    pub fn accept_draw(&mut self, game_id: &GameId) -> Option<GameResult> {
+        assert_one_yocto();
+        let mut game: Game = self.internal_get_game(game_id);
+        assert_eq!(game.game_state, GameState::Active, "Current game isn't active");
+        let account_id = env::predecessor_account_id();
+        assert!(game.contains_player_account_id(&account_id), "You are not in this game. GameId: {} ", game_id);
+        match &game.draw_offer {
+            Some(offer) if *offer != account_id => {},
+            _ => panic!("No draw offer from the opponent"),
+        }
+
+        log!("\nGame over! Draw by agreement!");
+        let balance = self.internal_distribute_reward(game_id, None);
+        game.change_state(GameState::Finished);
+        self.internal_update_game(game_id, &game);
+
+        let game_to_store = GameLimitedView::finished(&game, GameResult::Tie, ResultReason::DrawByAgreement, balance);
+        self.internal_store_game(game_id, &game_to_store);
+        self.games.remove(game_id);
+        Some(game_to_store.game_result)
+    }
+
+    /// Asks the opponent to take back the last move of the caller
+    pub fn request_takeback(&mut self, game_id: &GameId) {
+        let mut game: Game = self.internal_get_game(game_id);
+        assert_eq!(game.game_state, GameState::Active, "Current game isn't active");
+        let account_id = env::predecessor_account_id();
+        assert!(game.contains_player_account_id(&account_id), "You are not in this game. GameId: {} ", game_id);
+        assert_ne!(account_id, game.current_player_account_id(), "You can only take back your own last move");
+        assert!(game.previous_move.is_some(), "No move to take back");
+        assert!(*game.takebacks_left_mut(&account_id) > 0, "No takebacks left");
+        log!("@{} asks to take back the last move in game {}", account_id, game_id);
+        game.takeback_request = Some(account_id);
+        self.internal_update_game(game_id, &game);
+    }
+
+    /// Takes back the last move of the opponent, who gets the turn back with a fresh turn timer
+    pub fn accept_takeback(&mut self, game_id: &GameId) {
+        let mut game: Game = self.internal_get_game(game_id);
+        assert_eq!(game.game_state, GameState::Active, "Current game isn't active");
+        let account_id = env::predecessor_account_id();
+        assert!(game.contains_player_account_id(&account_id), "You are not in this game. GameId: {} ", game_id);
+        let requester = match &game.takeback_request {
+            Some(request) if *request != account_id => request.clone(),
+            _ => panic!("No takeback request from the opponent"),
+        };
+        *game.takebacks_left_mut(&requester) -= 1;
+        game.takeback_request = None;
+        game.take_back(nano_to_sec(env::block_timestamp()));
+        log!("Last move of @{} taken back in game {}", requester, game_id);
+        self.internal_update_game(game_id, &game);
     }
 
     pub fn claim_timeout_win(&mut self, game_id: &GameId) -> Option<GameResult> {
         let game: Game = self.internal_get_game(&game_id);
         let player = env::predecessor_account_id();
+        if game.game_state == GameState::NotStarted {
+            assert!(game.contains_player_account_id(&player), "No access");
+            if !game.is_reveal_expired(nano_to_sec(env::block_timestamp())) {
+                log!("can't claim the win, the reveal deadline didn't pass");
+                return None;
+            }
+            return Some(self.internal_expire_coin_toss(game_id));
+        }
         if game.claim_timeout_win(&player) == false {
             log!("can't claim the win, timeout didn't pass");
             return None;
         }
-        let looser = game.get_opponent(&player);
         let balance = self.internal_distribute_reward(game_id, Some(&player));
 
-        return self.store_game(game_id, &player, &looser, balance)
+        return self.store_game(game_id, &player, balance, ResultReason::Timeout)
     }
 
-    pub fn store_game(&mut self, game_id: &GameId, winner: &AccountId, looser: &AccountId, balance: U128) -> Option<GameResult> {
+    pub fn store_game(&mut self, game_id: &GameId, winner: &AccountId, balance: U128, reason: ResultReason) -> Option<GameResult> {
         let game: Game = self.internal_get_game(&game_id);
-        let last_move = game.last_move.clone().map(|coords|  {
-          let piece = game.board.get(&coords).unwrap();
-          return (coords, piece);
-        });
-
         self.games.remove(game_id);
-        let game_to_store = GameLimitedView{
-            game_result: GameResult::Win(winner.clone()),
-            player1: winner.clone(),
-            player2: looser.clone(),
-            reward_or_tie_refund: GameDeposit {
-                balance
-            },
-            tiles: game.to_tiles(),
-            last_move: last_move
-        };
+        let game_to_store = GameLimitedView::finished(&game, GameResult::Win(winner.clone()), reason, balance);
         self.internal_store_game(game_id, &game_to_store);
         return Some(game_to_store.game_result);
     }
@@ -560,6 +686,8 @@ mod tests {
     use near_sdk::test_utils::VMContextBuilder;
     use near_sdk::{testing_env, Balance, ONE_NEAR};
     use crate::views::{GameView, Tiles};
+    use crate::migration::*;
+    use std::convert::TryFrom;
 
     use super::*;
     const MIN_GAME_DURATION: u32 = 25 * 60;
@@ -627,11 +755,25 @@ mod tests {
             .predecessor_account_id(user.clone())
             .signer_account_id(user.clone())
             .build());
-        ctr.make_available(Some(GameConfigNear { 
-            opponent_id, 
+        ctr.make_available(Some(GameConfigNear {
+            opponent_id,
             referrer_id,
+            ..Default::default()
         }), amount, available_for);
     }
+    fn make_available_config(
+        ctx: &mut VMContextBuilder,
+        ctr: &mut Contract,
+        user: &AccountId,
+        amount: Balance,
+        game_config: GameConfigNear,
+    ) {
+        testing_env!(ctx
+            .predecessor_account_id(user.clone())
+            .signer_account_id(user.clone())
+            .build());
+        ctr.make_available(Some(game_config), amount, MAX_AVAILABLE_FOR);
+    }
     fn storage_deposit(
         ctx: &mut VMContextBuilder,
         ctr: &mut Contract,
@@ -702,7 +844,43 @@ mod tests {
         testing_env!(ctx
             .predecessor_account_id(user.clone())
             .build());
-        ctr.make_move(game_id, Coords{y: row, x: col})
+        ctr.make_move(game_id, Some(Coords{y: row, x: col}), None)
+    }
+    fn drop_piece(
+        ctx: &mut VMContextBuilder,
+        ctr: &mut Contract,
+        user: &AccountId,
+        game_id: &GameId,
+        col: u8
+    ) -> Option<GameResult> {
+        testing_env!(ctx
+            .predecessor_account_id(user.clone())
+            .build());
+        ctr.make_move(game_id, None, Some(col))
+    }
+    fn place_opening(
+        ctx: &mut VMContextBuilder,
+        ctr: &mut Contract,
+        user: &AccountId,
+        game_id: &GameId,
+        stones: &[(u8, u8)],
+    ) {
+        testing_env!(ctx
+            .predecessor_account_id(user.clone())
+            .build());
+        ctr.place_opening(game_id, stones.iter().map(|(x, y)| Coords { x: *x, y: *y }).collect())
+    }
+    fn choose_piece(
+        ctx: &mut VMContextBuilder,
+        ctr: &mut Contract,
+        user: &AccountId,
+        game_id: &GameId,
+        piece: Piece,
+    ) {
+        testing_env!(ctx
+            .predecessor_account_id(user.clone())
+            .build());
+        ctr.choose_piece(game_id, piece)
     }
     fn get_last_move(
         ctx: &mut VMContextBuilder,
@@ -724,7 +902,7 @@ mod tests {
         // 1 x ▢ ▢
         // 2 ▢ ▢ o
         // 3 ▢ ▢ ▢
-        let mut matrix: [[Option<Piece>; BOARD_SIZE as usize]; BOARD_SIZE as usize] =
+        let mut matrix: [[Option<Piece>; MAX_BOARD_SIZE as usize]; MAX_BOARD_SIZE as usize] =
             Default::default();
         for tile in tiles.x_coords.iter() {
             matrix[tile.y as usize][tile.x as usize] = Some(Piece::X);
@@ -756,7 +934,7 @@ mod tests {
 
     fn game_basics() -> Result<(VMContextBuilder, Contract), std::io::Error> {
         let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION)); // HERE
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
 
         let gc1 = GameConfigArgs { 
             opponent_id: Some(opponent()), 
@@ -769,18 +947,38 @@ mod tests {
         make_deposit(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, msg1.clone());
         make_available(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, Some(opponent()), None, MAX_AVAILABLE_FOR);
         make_available(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, Some(user()), None, MAX_AVAILABLE_FOR);
-        assert_eq!(ctr.get_available_players(), Vec::<(AccountId, GameConfigView)>::from([
+        assert_eq!(ctr.get_available_players(None), Vec::<(AccountId, GameConfigView)>::from([
             (user(), GameConfigView { 
                 deposit: U128(ONE_CHEDDAR), 
                 opponent_id: Some(opponent()), 
                 referrer_id: None,
-                created_at: 0
+                created_at: 0,
+                rules: DEFAULT_RULES,
+                variant: Variant::Freestyle,
+                opening: Opening::Standard,
+                best_of: 1,
+                takebacks: 0,
+                time_control: None,
+                commitment: None,
+                channel_key: None,
+                min_rating: None,
+                max_rating: None,
             }),
             (opponent(), GameConfigView {  
                 deposit: U128(ONE_CHEDDAR), 
                 opponent_id: Some(user()), 
                 referrer_id: None,
-                created_at: 0
+                created_at: 0,
+                rules: DEFAULT_RULES,
+                variant: Variant::Freestyle,
+                opening: Opening::Standard,
+                best_of: 1,
+                takebacks: 0,
+                time_control: None,
+                commitment: None,
+                channel_key: None,
+                min_rating: None,
+                max_rating: None,
             }),
         ]));
 
@@ -873,12 +1071,12 @@ mod tests {
         (&mut ctx, &mut ctr, &user());
         unregister_account(&mut ctx, &mut ctr, &user());
         unregister_account(&mut ctx, &mut ctr, &opponent());
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
     }
     #[test]
     fn test_make_available_unavailable() {
         let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
         let gc1 = GameConfigArgs { 
             opponent_id: Some(opponent()), 
             referrer_id: Some(referrer()) 
@@ -890,28 +1088,48 @@ mod tests {
         make_deposit(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, msg1.clone());
         make_available(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, Some(opponent()), None, MAX_AVAILABLE_FOR);
         make_available(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, Some(user()), None, MAX_AVAILABLE_FOR);
-        assert_eq!(ctr.get_available_players(), Vec::<(AccountId, GameConfigView)>::from([
+        assert_eq!(ctr.get_available_players(None), Vec::<(AccountId, GameConfigView)>::from([
             (user(), GameConfigView {  
                 deposit: U128(ONE_CHEDDAR), 
                 opponent_id: Some(opponent()), 
                 referrer_id: None,
-                created_at: 0
+                created_at: 0,
+                rules: DEFAULT_RULES,
+                variant: Variant::Freestyle,
+                opening: Opening::Standard,
+                best_of: 1,
+                takebacks: 0,
+                time_control: None,
+                commitment: None,
+                channel_key: None,
+                min_rating: None,
+                max_rating: None,
             }),
             (opponent(), GameConfigView {  
                 deposit: U128(ONE_CHEDDAR), 
                 opponent_id: Some(user()), 
                 referrer_id: None,
-                created_at: 0
+                created_at: 0,
+                rules: DEFAULT_RULES,
+                variant: Variant::Freestyle,
+                opening: Opening::Standard,
+                best_of: 1,
+                takebacks: 0,
+                time_control: None,
+                commitment: None,
+                channel_key: None,
+                min_rating: None,
+                max_rating: None,
             }),
         ]));
         make_unavailable(&mut ctx, &mut ctr, &user());
         make_unavailable(&mut ctx, &mut ctr, &opponent());
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
     }
     #[test]
     fn test_give_up() {
         let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
         let gc1 = GameConfigArgs { 
             opponent_id: Some(opponent()), 
             referrer_id: Some(referrer()) 
@@ -923,18 +1141,38 @@ mod tests {
         make_deposit(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, msg1.clone());
         make_available(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, Some(opponent()), None, MAX_AVAILABLE_FOR);
         make_available(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, Some(user()), None, MAX_AVAILABLE_FOR);
-        assert_eq!(ctr.get_available_players(), Vec::<(AccountId, GameConfigView)>::from([
+        assert_eq!(ctr.get_available_players(None), Vec::<(AccountId, GameConfigView)>::from([
             (user(), GameConfigView { 
                 deposit: U128(ONE_CHEDDAR), 
                 opponent_id: Some(opponent()), 
                 referrer_id: None,
-                created_at: 0 
+                created_at: 0,
+                rules: DEFAULT_RULES,
+                variant: Variant::Freestyle,
+                opening: Opening::Standard,
+                best_of: 1,
+                takebacks: 0,
+                time_control: None,
+                commitment: None,
+                channel_key: None,
+                min_rating: None,
+                max_rating: None,
             }),
             (opponent(), GameConfigView {  
                 deposit: U128(ONE_CHEDDAR), 
                 opponent_id: Some(user()), 
                 referrer_id: None,
-                created_at: 0 
+                created_at: 0,
+                rules: DEFAULT_RULES,
+                variant: Variant::Freestyle,
+                opening: Opening::Standard,
+                best_of: 1,
+                takebacks: 0,
+                time_control: None,
+                commitment: None,
+                channel_key: None,
+                min_rating: None,
+                max_rating: None,
             }),
         ]));
         testing_env!(ctx
@@ -960,7 +1198,7 @@ mod tests {
     #[test]
     fn test_game_basics() {
         let (mut ctx, mut ctr) = setup_contract(user(), Some(10), None,  Some(MIN_GAME_DURATION));
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
         let gc1 = GameConfigArgs { 
             opponent_id: Some(opponent()), 
             referrer_id: Some(referrer()) 
@@ -1022,7 +1260,7 @@ mod tests {
     #[test]
     fn test_game_basics_2() {
         let (mut ctx, mut ctr) = setup_contract(user(), Some(10), None,  Some(MIN_GAME_DURATION));
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
         let gc1 = GameConfigArgs { 
             opponent_id: Some(opponent()), 
             referrer_id: Some(referrer()) 
@@ -1075,7 +1313,7 @@ mod tests {
     #[test]
     fn test_tie_scenario() {
         let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
         let gc1 = GameConfigArgs { 
             opponent_id: Some(opponent()), 
             referrer_id: None 
@@ -1145,7 +1383,7 @@ mod tests {
     #[test]
     fn test_stop_game_too_early() {
         let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
         let gc1 = GameConfigArgs { 
             opponent_id: Some(opponent()), 
             referrer_id: None 
@@ -1189,7 +1427,7 @@ mod tests {
     #[should_panic(expected="No access")]
     fn test_stop_game_wrong_access() {
         let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
         let gc1 = GameConfigArgs { 
             opponent_id: Some(opponent()), 
             referrer_id: None 
@@ -1238,7 +1476,7 @@ mod tests {
     // #[test]
     // fn test_expired_game() {
     //     let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION_SEC));
-    //     assert!(ctr.get_available_players().is_empty());
+    //     assert!(ctr.get_available_players(None).is_empty());
     //     let gc1 = GameConfigArgs { 
     //         opponent_id: Some(opponent()), 
     //         referrer_id: None 
@@ -1305,7 +1543,7 @@ mod tests {
     // #[test]
     // fn test_stop_game() {
     //     let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  None);
-    //     assert!(ctr.get_available_players().is_empty());
+    //     assert!(ctr.get_available_players(None).is_empty());
     //     let gc1 = GameConfigArgs { 
     //         opponent_id: Some(opponent()), 
     //         referrer_id: None 
@@ -1383,9 +1621,9 @@ mod tests {
 
         println!("ContractParams: {:#?}", ctr.get_contract_params());
         println!("TotalStatsNum: {:#?}", ctr.get_total_stats_num());
-        println!("AccountsPlayed: {:#?}", ctr.get_accounts_played());
+        println!("AccountsPlayed: {:#?}", ctr.get_accounts_played(0, 100));
         println!("UserPenalties: {:#?}", ctr.get_user_penalties(&user()));
-        println!("PenaltyUsers: {:#?}", ctr.get_penalty_users());
+        println!("PenaltyUsers: {:#?}", ctr.get_penalty_users(None, 100));
 
         let msg = String::from("test");
         storage_deposit(&mut ctx, &mut ctr, &user(), ONE_NEAR);
@@ -1398,12 +1636,12 @@ mod tests {
         make_available(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, None, None, AVAILABLE_FOR_DEFAULT);
         make_available(&mut ctx, &mut ctr, &"third".parse().unwrap(), ONE_CHEDDAR, None, None, AVAILABLE_FOR_DEFAULT);
 
-        assert_eq!(ctr.get_available_players().len(), 3);
+        assert_eq!(ctr.get_available_players(None).len(), 3);
         testing_env!(ctx
             .block_timestamp(sec_to_nano(ctr.max_game_duration + MAX_TIME_TO_BE_AVAILABLE))
             .build()
         );
-        assert_eq!(ctr.get_available_players().len(), 3);
+        assert_eq!(ctr.get_available_players(None).len(), 3);
 
         // test ping expired players
         testing_env!(ctx
@@ -1415,8 +1653,8 @@ mod tests {
         make_deposit(&mut ctx, &mut ctr, &"fourth".parse().unwrap(), ONE_CHEDDAR, msg.clone());
         make_available(&mut ctx, &mut ctr, &"fourth".parse().unwrap(), ONE_CHEDDAR, None, None, AVAILABLE_FOR_DEFAULT);
 
-        assert_eq!(ctr.get_available_players().len(), 1);
-        assert_eq!(ctr.get_available_players()[0].0, "fourth".parse().unwrap());
+        assert_eq!(ctr.get_available_players(None).len(), 1);
+        assert_eq!(ctr.get_available_players(None)[0].0, "fourth".parse().unwrap());
 
 
         make_available(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, None, None, AVAILABLE_FOR_DEFAULT);
@@ -1486,7 +1724,7 @@ mod tests {
         assert!(new_looser_num_penalties - looser_num_penalties == 1);
 
         assert!(
-            ctr.get_penalty_users()
+            ctr.get_penalty_users(None, 100)
                 .iter()
                 .map(|(acc, _)| acc.clone())
                 .collect::<Vec<AccountId>>()
@@ -1498,7 +1736,7 @@ mod tests {
     #[test]
     fn test_claim_timeout_win() {
         let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
         let gc1 = GameConfigArgs { 
             opponent_id: Some(opponent()), 
             referrer_id: None 
@@ -1543,7 +1781,7 @@ mod tests {
     #[test]
     fn test_claim_timeout_win_when_no_timeout() {
         let (mut ctx, mut ctr) = setup_contract(user(), Some(10), None,  Some(MIN_GAME_DURATION));
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
         let gc1 = GameConfigArgs { 
             opponent_id: Some(opponent()), 
             referrer_id: None 
@@ -1583,15 +1821,140 @@ mod tests {
         ctr.claim_timeout_win(&game_id);
         assert!(game.game_state == GameState::Active);
     }
+    #[test]
+    fn test_custom_rules_game() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        storage_deposit(&mut ctx, &mut ctr, &user(), ONE_NEAR);
+        storage_deposit(&mut ctx, &mut ctr, &opponent(), ONE_NEAR);
+        make_deposit(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, String::new());
+        make_deposit(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, String::new());
+        make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear { board_size: Some(9), win_length: Some(4), ..Default::default() });
+        make_available_config(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, GameConfigNear { board_size: Some(9), win_length: Some(4), ..Default::default() });
+        assert_eq!(ctr.get_available_players(None)[0].1.rules, MnkRules::new(9, 9, 4));
+
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        let game = ctr.internal_get_game(&game_id);
+        assert_eq!(GameView::from(&game).rules, MnkRules::new(9, 9, 4));
+        let player_1 = game.current_player_account_id();
+        let player_2 = game.next_player_account_id();
+
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 8, 5);
+        make_move(&mut ctx, &mut ctr, &player_2, &game_id, 0, 0);
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 8, 6);
+        make_move(&mut ctx, &mut ctr, &player_2, &game_id, 0, 1);
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 8, 7);
+        make_move(&mut ctx, &mut ctr, &player_2, &game_id, 0, 2);
+        let winner = make_move(&mut ctx, &mut ctr, &player_1, &game_id, 8, 8);
+        assert_eq!(winner, Some(GameResult::Win(player_1)));
+        assert_eq!(ctr.get_game(&game_id).rules, MnkRules::new(9, 9, 4));
+    }
+    #[test]
+    #[should_panic(expected = "Mismatched game rules for players!")]
+    fn test_start_game_mismatched_rules() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        storage_deposit(&mut ctx, &mut ctr, &user(), ONE_NEAR);
+        storage_deposit(&mut ctx, &mut ctr, &opponent(), ONE_NEAR);
+        make_deposit(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, String::new());
+        make_deposit(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, String::new());
+        make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear { board_size: Some(19), ..Default::default() });
+        make_available_config(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, GameConfigNear::default());
+        start_game(&mut ctx, &mut ctr, &user(), &opponent());
+    }
+    fn start_game_with_opening(opening: Opening) -> (VMContextBuilder, Contract, GameId) {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        storage_deposit(&mut ctx, &mut ctr, &user(), ONE_NEAR);
+        storage_deposit(&mut ctx, &mut ctr, &opponent(), ONE_NEAR);
+        make_deposit(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, String::new());
+        make_deposit(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, String::new());
+        make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear { opening: Some(opening), ..Default::default() });
+        make_available_config(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, GameConfigNear { opening: Some(opening), ..Default::default() });
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        (ctx, ctr, game_id)
+    }
+    #[test]
+    fn test_swap_opening() {
+        let (mut ctx, mut ctr, game_id) = start_game_with_opening(Opening::Swap);
+        let game = ctr.internal_get_game(&game_id);
+        let proposer = game.current_player_account_id();
+        let chooser = game.next_player_account_id();
+        assert_eq!(GameView::from(&game).opening_phase, Some(OpeningPhase::PlaceThree));
+
+        place_opening(&mut ctx, &mut ctr, &proposer, &game_id, &[(10, 10), (11, 10), (11, 11)]);
+        let game = ctr.internal_get_game(&game_id);
+        assert_eq!(game.current_player_account_id(), chooser);
+        assert_eq!(GameView::from(&game).opening_phase, Some(OpeningPhase::FirstChoice));
+        let tiles = game.to_tiles();
+        assert_eq!(tiles.o_coords, vec![Coords { x: 10, y: 10 }, Coords { x: 11, y: 11 }]);
+        assert_eq!(tiles.x_coords, vec![Coords { x: 11, y: 10 }]);
+
+        // the chooser takes O, so the proposer continues with X
+        choose_piece(&mut ctx, &mut ctr, &chooser, &game_id, Piece::O);
+        let game = ctr.internal_get_game(&game_id);
+        assert_eq!(game.players, (chooser.clone(), proposer.clone()));
+        assert_eq!(game.opening_phase, None);
+        assert_eq!(game.current_piece, Piece::X);
+        assert_eq!(game.current_player_account_id(), proposer);
+        make_move(&mut ctx, &mut ctr, &proposer, &game_id, 0, 0);
+        make_move(&mut ctx, &mut ctr, &chooser, &game_id, 12, 12);
+        assert_eq!(
+            ctr.get_current_tiles(&game_id).o_coords.last(),
+            Some(&Coords { x: 12, y: 12 })
+        );
+    }
+    #[test]
+    fn test_swap2_opening() {
+        let (mut ctx, mut ctr, game_id) = start_game_with_opening(Opening::Swap2);
+        let game = ctr.internal_get_game(&game_id);
+        let proposer = game.current_player_account_id();
+        let opponent = game.next_player_account_id();
+
+        place_opening(&mut ctx, &mut ctr, &proposer, &game_id, &[(10, 10), (11, 10), (11, 11)]);
+        // the opponent adds X and O and lets the proposer choose
+        place_opening(&mut ctx, &mut ctr, &opponent, &game_id, &[(12, 12), (9, 9)]);
+        let game = ctr.internal_get_game(&game_id);
+        assert_eq!(game.opening_phase, Some(OpeningPhase::SecondChoice));
+        assert_eq!(game.current_player_account_id(), proposer);
+        assert_eq!(game.board.pieces_num(), 5);
+
+        choose_piece(&mut ctx, &mut ctr, &proposer, &game_id, Piece::X);
+        let game = ctr.internal_get_game(&game_id);
+        assert_eq!(game.players, (opponent.clone(), proposer.clone()));
+        assert_eq!(game.current_player_account_id(), proposer);
+        make_move(&mut ctx, &mut ctr, &proposer, &game_id, 0, 0);
+    }
+    #[test]
+    #[should_panic(expected = "Opening isn't finished yet")]
+    fn test_make_move_during_opening() {
+        let (mut ctx, mut ctr, game_id) = start_game_with_opening(Opening::Swap);
+        let proposer = ctr.internal_get_game(&game_id).current_player_account_id();
+        make_move(&mut ctx, &mut ctr, &proposer, &game_id, 0, 0);
+    }
+    #[test]
+    #[should_panic(expected = "Opening stones can't be placed now")]
+    fn test_swap_opening_no_extra_stones() {
+        let (mut ctx, mut ctr, game_id) = start_game_with_opening(Opening::Swap);
+        let game = ctr.internal_get_game(&game_id);
+        let proposer = game.current_player_account_id();
+        let chooser = game.next_player_account_id();
+        place_opening(&mut ctx, &mut ctr, &proposer, &game_id, &[(10, 10), (11, 10), (11, 11)]);
+        place_opening(&mut ctx, &mut ctr, &chooser, &game_id, &[(12, 12), (9, 9)]);
+    }
+    #[test]
+    #[should_panic(expected = "win length must be between 4 and 6")]
+    fn test_make_available_invalid_win_length() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        storage_deposit(&mut ctx, &mut ctr, &user(), ONE_NEAR);
+        make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear { board_size: Some(15), win_length: Some(7), ..Default::default() });
+    }
     #[test] 
     fn test_player_piece_binding() {
-        let game = Game::create_game(1, user(), opponent(), GameDeposit {balance: U128(5000) });
+        let game = Game::create_game(user(), opponent(), GameDeposit {balance: U128(5000) }, DEFAULT_RULES, Variant::Freestyle, Opening::Standard);
         assert_eq!(game.current_piece, Piece::O);
     }
     #[test]
     fn test_get_last_move() {
         let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
-        assert!(ctr.get_available_players().is_empty());
+        assert!(ctr.get_available_players(None).is_empty());
         let gc1 = GameConfigArgs { 
             opponent_id: Some(opponent()), 
             referrer_id: None 
@@ -1632,4 +1995,1315 @@ mod tests {
         assert_eq!(last_piece, Piece::X);
         
     }
+    #[test]
+    fn test_clear_game_boards() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        testing_env!(ctx.predecessor_account_id(ctx.context.current_account_id.clone()).build());
+        let storage_used = env::storage_usage();
+        // boards left by games created before `Game.board` was stored inline
+        for game_id in 0..2 {
+            let mut board: UnorderedMap<Coords, Piece> = UnorderedMap::new(StorageKey::GameBoard { game_id });
+            board.insert(&Coords { x: 0, y: 0 }, &Piece::O);
+            board.insert(&Coords { x: 1, y: 1 }, &Piece::X);
+        }
+        ctr.next_game_id = 2;
+        let boards_storage = env::storage_usage() - storage_used;
+
+        assert_eq!(ctr.clear_game_boards(0, 10).0, boards_storage);
+        assert_eq!(env::storage_usage(), storage_used);
+        assert_eq!(ctr.clear_game_boards(0, 10).0, 0);
+    }
+    #[test]
+    fn test_migrate() {
+        let mut ctx = VMContextBuilder::new();
+        testing_env!(ctx.predecessor_account_id(ctx.context.current_account_id.clone()).build());
+        let mut board = UnorderedMap::new(StorageKey::GameBoard { game_id: 0 });
+        board.insert(&Coords { x: 2, y: 2 }, &Piece::O);
+        board.insert(&Coords { x: 3, y: 3 }, &Piece::X);
+        board.insert(&Coords { x: 1, y: 2 }, &Piece::O);
+        // 1.0.0 stats, without the match counters
+        type StatsV1 = (Option<AccountId>, UnorderedSet<AffiliateId>, u64, u64, u64, Balance, Balance);
+        let mut old_stats: UnorderedMap<AccountId, StatsV1> = UnorderedMap::new(StorageKey::Stats);
+        old_stats.insert(&user(), &(None, UnorderedSet::new(StorageKey::Affiliates { account_id: user() }), 4, 3, 1, ONE_CHEDDAR, 0));
+        old_stats.insert(&opponent(), &(None, UnorderedSet::new(StorageKey::Affiliates { account_id: opponent() }), 4, 1, 2, 0, 0));
+        let mut old = ContractV1 {
+            cheddar: acc_cheddar(),
+            min_deposit: MIN_DEPOSIT_CHEDDAR,
+            games: UnorderedMap::new(StorageKey::Games),
+            available_players: UnorderedMap::new(StorageKey::Players),
+            stats: UnorderedMap::try_from_slice(&old_stats.try_to_vec().unwrap()).unwrap(),
+            next_game_id: 2,
+            service_fee: 0,
+            max_game_duration: MAX_GAME_DURATION,
+            referrer_fee_share: 500,
+            last_update_timestamp: 0,
+            max_turn_duration: 2 * 60,
+            max_stored_games: 50,
+            stored_games: UnorderedMap::new(StorageKey::StoredGames),
+            registered_players: UnorderedMap::new(StorageKey::RegisteredPlayers),
+        };
+        old.games.insert(&0, &GameV1 {
+            game_state: GameState::Active,
+            players: (user(), opponent()),
+            current_piece: Piece::X,
+            current_player_index: 1,
+            reward: GameDeposit { balance: (2 * ONE_CHEDDAR).into() },
+            total_turns: 3,
+            initiated_at: 100,
+            last_turn_timestamp: 160,
+            current_duration: 60,
+            last_move: Some(Coords { x: 1, y: 2 }),
+            winner: None,
+            board,
+            duration: 0,
+        });
+        old.available_players.insert(&referrer(), &GameConfigV1 {
+            deposit: ONE_CHEDDAR,
+            opponent_id: None,
+            referrer_id: None,
+            created_at: 100,
+            available_until: 100 + AVAILABLE_FOR_DEFAULT,
+        });
+        old.stored_games.insert(&1, &GameLimitedViewV1 {
+            game_result: GameResult::Win(opponent()),
+            player1: user(),
+            player2: opponent(),
+            reward_or_tie_refund: GameDeposit { balance: (2 * ONE_CHEDDAR).into() },
+            tiles: Tiles { o_coords: vec![Coords { x: 0, y: 0 }], x_coords: vec![] },
+            last_move: Some((Coords { x: 0, y: 0 }, Piece::O)),
+        });
+        env::state_write(&old);
+
+        let mut ctr = Contract::migrate();
+        assert_eq!(ctr.get_current_tiles(&0), Tiles {
+            o_coords: vec![Coords { x: 1, y: 2 }, Coords { x: 2, y: 2 }],
+            x_coords: vec![Coords { x: 3, y: 3 }],
+        });
+        let moves: Vec<Coords> = ctr.get_game_moves(&0, 0, 10).into_iter().map(|m| m.coords).collect();
+        assert_eq!(moves, vec![Coords { x: 2, y: 2 }, Coords { x: 3, y: 3 }, Coords { x: 1, y: 2 }]);
+        assert_eq!(ctr.get_current_player(&0), opponent());
+        assert_eq!(ctr.internal_get_game(&0).rules, DEFAULT_RULES);
+        let offers = ctr.get_available_players(None);
+        assert_eq!(offers.len(), 1);
+        assert_eq!(offers[0].0, referrer());
+        assert_eq!(offers[0].1.best_of, 1);
+        let stored = ctr.get_game(&1);
+        assert_eq!(stored.game_result, GameResult::Win(opponent()));
+        assert_eq!(stored.reason, ResultReason::Timeout);
+
+        // the old board is kept until it's swept
+        assert!(ctr.clear_game_boards(0, 2).0 > 0);
+        assert_eq!(ctr.get_game_moves(&0, 0, 10).len(), 3);
+
+        let stats = ctr.get_stats(&user());
+        assert_eq!((stats.games_played, stats.victories_num, stats.matches_played), (4, 3, 0));
+        ctr.internal_update_stats(&user(), UpdateStatsAction::AddPlayedMatch, None, None);
+        let stats = ctr.get_stats(&user());
+        assert_eq!((stats.games_played, stats.victories_num, stats.matches_played), (4, 3, 1));
+        assert_eq!(ctr.get_user_penalties(&user()).penalties_num, 1);
+
+        // 1.0.0 accounts are listed once the leaderboards are backfilled
+        let penalty_users = |ctr: &Contract| -> Vec<AccountId> {
+            ctr.get_penalty_users(None, 10).into_iter().map(|(account_id, _)| account_id).collect()
+        };
+        assert!(penalty_users(&ctr).is_empty());
+        assert_eq!(ctr.backfill_leaderboards(0, 1), U64(1));
+        assert_eq!(ctr.backfill_leaderboards(1, 1), U64(0));
+        assert_eq!(penalty_users(&ctr), vec![opponent(), user()]);
+        let next_page: Vec<AccountId> = ctr.get_penalty_users(Some(opponent()), 10).into_iter().map(|(account_id, _)| account_id).collect();
+        assert_eq!(next_page, vec![user()]);
+        assert_eq!(ctr.get_leaderboard_len(Leaderboard::Victories), 2);
+    }
+    #[test]
+    fn test_misere_line_loses() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        storage_deposit(&mut ctx, &mut ctr, &user(), ONE_NEAR);
+        storage_deposit(&mut ctx, &mut ctr, &opponent(), ONE_NEAR);
+        make_deposit(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, String::new());
+        make_deposit(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, String::new());
+        make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear { misere: Some(true), ..Default::default() });
+        make_available_config(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, GameConfigNear { misere: Some(true), ..Default::default() });
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        let game = ctr.internal_get_game(&game_id);
+        let player_1 = game.current_player_account_id();
+        let player_2 = game.next_player_account_id();
+        assert!(game.rules.misere);
+
+        // O O O O O
+        // _ _ _ _ _
+        // X X X X _
+        for col in 0..4 {
+            make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, col);
+            make_move(&mut ctx, &mut ctr, &player_2, &game_id, 2, col);
+        }
+        let result = make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 4);
+        // player 1 completed the line and lost
+        assert_eq!(result, Some(GameResult::Win(player_2.clone())));
+        let stored = ctr.get_game(&game_id);
+        assert_eq!(stored.reason, ResultReason::MisereLine);
+        assert_eq!(ctr.get_stats(&player_2).victories_num, 1);
+        assert_eq!(ctr.get_stats(&player_1).victories_num, 0);
+    }
+    fn start_gravity_game() -> (VMContextBuilder, Contract, GameId) {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        storage_deposit(&mut ctx, &mut ctr, &user(), ONE_NEAR);
+        storage_deposit(&mut ctx, &mut ctr, &opponent(), ONE_NEAR);
+        make_deposit(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, String::new());
+        make_deposit(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, String::new());
+        make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear { board_size: Some(7), board_height: Some(6), win_length: Some(4), gravity: Some(true), ..Default::default() });
+        make_available_config(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, GameConfigNear { board_size: Some(7), board_height: Some(6), win_length: Some(4), gravity: Some(true), ..Default::default() });
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        (ctx, ctr, game_id)
+    }
+    #[test]
+    fn test_gravity_game() {
+        let (mut ctx, mut ctr, game_id) = start_gravity_game();
+        let game = ctr.internal_get_game(&game_id);
+        assert_eq!(game.rules, MnkRules::CONNECT_FOUR);
+        let player_1 = game.current_player_account_id();
+        let player_2 = game.next_player_account_id();
+
+        for _ in 0..3 {
+            assert_eq!(drop_piece(&mut ctx, &mut ctr, &player_1, &game_id, 0), None);
+            assert_eq!(drop_piece(&mut ctx, &mut ctr, &player_2, &game_id, 1), None);
+        }
+        let game = ctr.internal_get_game(&game_id);
+        // pieces are stacked from the bottom row
+        assert_eq!(game.board.piece(&Coords { x: 0, y: 5 }), Some(Piece::O));
+        assert_eq!(game.board.piece(&Coords { x: 1, y: 3 }), Some(Piece::X));
+        assert_eq!(game.board.piece(&Coords { x: 0, y: 2 }), None);
+        // four in a column
+        let result = drop_piece(&mut ctx, &mut ctr, &player_1, &game_id, 0);
+        assert_eq!(result, Some(GameResult::Win(player_1)));
+    }
+    #[test]
+    #[should_panic(expected = "Provided position is invalid: row: 0 col: 2")]
+    fn test_gravity_floating_move() {
+        let (mut ctx, mut ctr, game_id) = start_gravity_game();
+        let player_1 = ctr.internal_get_game(&game_id).current_player_account_id();
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 2);
+    }
+    #[test]
+    #[should_panic(expected = "The column 0 is full")]
+    fn test_gravity_full_column() {
+        let (mut ctx, mut ctr, game_id) = start_gravity_game();
+        let game = ctr.internal_get_game(&game_id);
+        let player_1 = game.current_player_account_id();
+        let player_2 = game.next_player_account_id();
+        for _ in 0..3 {
+            drop_piece(&mut ctx, &mut ctr, &player_1, &game_id, 0);
+            drop_piece(&mut ctx, &mut ctr, &player_2, &game_id, 0);
+        }
+        drop_piece(&mut ctx, &mut ctr, &player_1, &game_id, 0);
+    }
+    fn start_match(best_of: u8) -> (VMContextBuilder, Contract, GameId) {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        storage_deposit(&mut ctx, &mut ctr, &user(), ONE_NEAR);
+        storage_deposit(&mut ctx, &mut ctr, &opponent(), ONE_NEAR);
+        make_deposit(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, String::new());
+        make_deposit(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, String::new());
+        make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear { best_of: Some(best_of), ..Default::default() });
+        make_available_config(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, GameConfigNear { best_of: Some(best_of), ..Default::default() });
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        (ctx, ctr, game_id)
+    }
+    /// The player with O makes five in a row, returns the winner
+    fn win_with_o(ctx: &mut VMContextBuilder, ctr: &mut Contract, game_id: &GameId) -> AccountId {
+        let game = ctr.internal_get_game(game_id);
+        let (player_1, player_2) = game.get_player_accounts();
+        for col in 0..4 {
+            make_move(ctx, ctr, &player_1, game_id, 0, col);
+            make_move(ctx, ctr, &player_2, game_id, 1, col);
+        }
+        assert_eq!(make_move(ctx, ctr, &player_1, game_id, 0, 4), Some(GameResult::Win(player_1.clone())));
+        player_1
+    }
+    #[test]
+    fn test_best_of_three_match() {
+        let (mut ctx, mut ctr, game_id) = start_match(3);
+        let match_id = ctr.internal_get_game(&game_id).match_id.expect("match game");
+        let (player_1, player_2) = ctr.internal_get_game(&game_id).get_player_accounts();
+        let balance_1 = ctr.get_cheddar_balance(&player_1);
+        let balance_2 = ctr.get_cheddar_balance(&player_2);
+
+        assert_eq!(win_with_o(&mut ctx, &mut ctr, &game_id), player_1);
+        // nothing is paid until the match is decided
+        assert_eq!(ctr.get_game(&game_id).reward_or_tie_refund.balance, U128(0));
+        assert_eq!(ctr.get_cheddar_balance(&player_1), balance_1);
+        let game_match = ctr.get_match(match_id);
+        assert_eq!(game_match.wins, (1, 0));
+        assert_eq!(game_match.state, GameState::Active);
+        assert_eq!(ctr.get_active_matches(0, 10).len(), 1);
+
+        // the next game starts right away with swapped pieces
+        let next_game_id = *game_match.games.last().unwrap();
+        assert_ne!(next_game_id, game_id);
+        let next_game = ctr.internal_get_game(&next_game_id);
+        assert_eq!(next_game.get_player_accounts(), (player_2.clone(), player_1.clone()));
+        assert_eq!(next_game.match_id, Some(match_id));
+
+        // player 2 now plays O and wins, player 1 gives up the decider
+        assert_eq!(win_with_o(&mut ctx, &mut ctr, &next_game_id), player_2);
+        assert_eq!(ctr.get_match(match_id).wins, (1, 1));
+        let last_game_id = *ctr.get_match(match_id).games.last().unwrap();
+        assert_eq!(ctr.internal_get_game(&last_game_id).get_player_accounts(), (player_1.clone(), player_2.clone()));
+        testing_env!(ctx.attached_deposit(ONE_YOCTO).predecessor_account_id(player_1.clone()).build());
+        ctr.give_up(&last_game_id);
+
+        let game_match = ctr.get_match(match_id);
+        assert_eq!(game_match.wins, (1, 2));
+        assert_eq!(game_match.games.len(), 3);
+        assert_eq!(game_match.state, GameState::Finished);
+        assert_eq!(game_match.result, Some(GameResult::Win(player_2.clone())));
+        assert!(ctr.get_active_matches(0, 10).is_empty());
+        assert!(ctr.get_active_games().is_empty());
+        // the whole stake is paid once
+        assert_eq!(ctr.get_game(&last_game_id).reward_or_tie_refund.balance, U128(2 * ONE_CHEDDAR));
+        assert_eq!(ctr.get_cheddar_balance(&player_2), balance_2 + 2 * ONE_CHEDDAR);
+        assert_eq!(ctr.get_cheddar_balance(&player_1), balance_1);
+
+        let stats_1 = ctr.get_stats(&player_1);
+        let stats_2 = ctr.get_stats(&player_2);
+        assert_eq!((stats_1.games_played, stats_1.victories_num), (3, 1));
+        assert_eq!((stats_2.games_played, stats_2.victories_num), (3, 2));
+        assert_eq!((stats_1.matches_played, stats_1.match_victories_num), (1, 0));
+        assert_eq!((stats_2.matches_played, stats_2.match_victories_num), (1, 1));
+    }
+    #[test]
+    fn test_match_decided_early() {
+        let (mut ctx, mut ctr, game_id) = start_match(5);
+        let match_id = ctr.internal_get_game(&game_id).match_id.unwrap();
+        // the first player wins the first game and the opponent gives up the next two
+        win_with_o(&mut ctx, &mut ctr, &game_id);
+        let opponent_id = ctr.get_match(match_id).players.1;
+        for _ in 0..2 {
+            let game_id = *ctr.get_match(match_id).games.last().unwrap();
+            testing_env!(ctx.attached_deposit(ONE_YOCTO).predecessor_account_id(opponent_id.clone()).build());
+            ctr.give_up(&game_id);
+        }
+        let game_match = ctr.get_match(match_id);
+        // 3 wins out of 5 are enough
+        assert_eq!(game_match.games.len(), 3);
+        assert_eq!(game_match.wins, (3, 0));
+        assert_eq!(game_match.state, GameState::Finished);
+        assert_eq!(game_match.result, Some(GameResult::Win(game_match.players.0.clone())));
+    }
+    #[test]
+    #[should_panic(expected = "Mismatched match lengths for players! You: best of 3, Opponent best of 5")]
+    fn test_start_game_mismatched_best_of() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear { best_of: Some(3), ..Default::default() });
+        make_available_config(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, GameConfigNear { best_of: Some(5), ..Default::default() });
+        start_game(&mut ctx, &mut ctr, &user(), &opponent());
+    }
+    #[test]
+    #[should_panic(expected = "best_of must be one of [1, 3, 5, 7]")]
+    fn test_make_available_invalid_best_of() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear { best_of: Some(2), ..Default::default() });
+    }
+    fn offer_draw(ctx: &mut VMContextBuilder, ctr: &mut Contract, user: &AccountId, game_id: &GameId) {
+        testing_env!(ctx.predecessor_account_id(user.clone()).build());
+        ctr.offer_draw(game_id)
+    }
+    fn accept_draw(ctx: &mut VMContextBuilder, ctr: &mut Contract, user: &AccountId, game_id: &GameId) -> Option<GameResult> {
+        testing_env!(ctx.attached_deposit(ONE_YOCTO).predecessor_account_id(user.clone()).build());
+        ctr.accept_draw(game_id)
+    }
+    #[test]
+    fn test_accept_draw() {
+        let (mut ctx, mut ctr, game_id) = start_match(1);
+        let (player_1, player_2) = ctr.internal_get_game(&game_id).get_player_accounts();
+        let balance_1 = ctr.get_cheddar_balance(&player_1);
+        let balance_2 = ctr.get_cheddar_balance(&player_2);
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 0);
+        // the offer survives the own move of the offering player
+        offer_draw(&mut ctx, &mut ctr, &player_2, &game_id);
+        make_move(&mut ctx, &mut ctr, &player_2, &game_id, 1, 1);
+        assert_eq!(ctr.get_active_games()[0].1.draw_offer, Some(player_2.clone()));
+
+        assert_eq!(accept_draw(&mut ctx, &mut ctr, &player_1, &game_id), Some(GameResult::Tie));
+        let stored = ctr.get_game(&game_id);
+        assert_eq!(stored.game_result, GameResult::Tie);
+        assert_eq!(stored.reason, ResultReason::DrawByAgreement);
+        assert_eq!(stored.reward_or_tie_refund.balance, U128(ONE_CHEDDAR));
+        assert_eq!(ctr.get_cheddar_balance(&player_1), balance_1 + ONE_CHEDDAR);
+        assert_eq!(ctr.get_cheddar_balance(&player_2), balance_2 + ONE_CHEDDAR);
+        assert!(ctr.get_active_games().is_empty());
+    }
+    #[test]
+    #[should_panic(expected = "No draw offer from the opponent")]
+    fn test_draw_offer_expires() {
+        let (mut ctx, mut ctr, game_id) = start_match(1);
+        let (player_1, player_2) = ctr.internal_get_game(&game_id).get_player_accounts();
+        offer_draw(&mut ctx, &mut ctr, &player_2, &game_id);
+        // player 1 moves instead of accepting
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 0);
+        assert_eq!(ctr.internal_get_game(&game_id).draw_offer, None);
+        accept_draw(&mut ctx, &mut ctr, &player_1, &game_id);
+    }
+    #[test]
+    #[should_panic(expected = "No draw offer from the opponent")]
+    fn test_accept_own_draw_offer() {
+        let (mut ctx, mut ctr, game_id) = start_match(1);
+        let player_1 = ctr.internal_get_game(&game_id).current_player_account_id();
+        offer_draw(&mut ctx, &mut ctr, &player_1, &game_id);
+        accept_draw(&mut ctx, &mut ctr, &player_1, &game_id);
+    }
+    fn start_game_with_takebacks(takebacks: u8) -> (VMContextBuilder, Contract, GameId) {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear { takebacks: Some(takebacks), ..Default::default() });
+        make_available_config(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, GameConfigNear { takebacks: Some(takebacks), ..Default::default() });
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        (ctx, ctr, game_id)
+    }
+    fn request_takeback(ctx: &mut VMContextBuilder, ctr: &mut Contract, user: &AccountId, game_id: &GameId) {
+        testing_env!(ctx.predecessor_account_id(user.clone()).build());
+        ctr.request_takeback(game_id)
+    }
+    fn accept_takeback(ctx: &mut VMContextBuilder, ctr: &mut Contract, user: &AccountId, game_id: &GameId) {
+        testing_env!(ctx.predecessor_account_id(user.clone()).build());
+        ctr.accept_takeback(game_id)
+    }
+    #[test]
+    fn test_takeback() {
+        let (mut ctx, mut ctr, game_id) = start_game_with_takebacks(1);
+        let (player_1, player_2) = ctr.internal_get_game(&game_id).get_player_accounts();
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 0);
+        testing_env!(ctx.block_timestamp(sec_to_nano(60)).build());
+        make_move(&mut ctx, &mut ctr, &player_2, &game_id, 1, 1);
+
+        request_takeback(&mut ctx, &mut ctr, &player_2, &game_id);
+        testing_env!(ctx.block_timestamp(sec_to_nano(90)).build());
+        accept_takeback(&mut ctx, &mut ctr, &player_1, &game_id);
+
+        let game = ctr.internal_get_game(&game_id);
+        assert_eq!(game.board.piece(&Coords { x: 1, y: 1 }), None);
+        assert_eq!(game.board.piece(&Coords { x: 0, y: 0 }), Some(Piece::O));
+        assert_eq!(game.current_player_account_id(), player_2);
+        assert_eq!(game.current_piece, Piece::X);
+        assert_eq!(game.last_move.map(|coords| (coords.x, coords.y)), Some((0, 0)));
+        assert_eq!(game.last_turn_timestamp, 90);
+        assert_eq!(game.takebacks_left, (1, 0));
+        assert_eq!(game.takeback_request, None);
+        assert_eq!(game.moves.len(), 1);
+
+        // player 2 moves again, elsewhere
+        make_move(&mut ctx, &mut ctr, &player_2, &game_id, 2, 2);
+        let game = ctr.internal_get_game(&game_id);
+        assert_eq!(game.board.piece(&Coords { x: 2, y: 2 }), Some(Piece::X));
+        assert_eq!(game.current_player_account_id(), player_1);
+    }
+    #[test]
+    #[should_panic(expected = "No takebacks left")]
+    fn test_takeback_limit() {
+        let (mut ctx, mut ctr, game_id) = start_game_with_takebacks(1);
+        let (player_1, player_2) = ctr.internal_get_game(&game_id).get_player_accounts();
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 0);
+        request_takeback(&mut ctx, &mut ctr, &player_1, &game_id);
+        accept_takeback(&mut ctx, &mut ctr, &player_2, &game_id);
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 1);
+        request_takeback(&mut ctx, &mut ctr, &player_1, &game_id);
+    }
+    #[test]
+    #[should_panic(expected = "No takeback request from the opponent")]
+    fn test_takeback_request_expires() {
+        let (mut ctx, mut ctr, game_id) = start_game_with_takebacks(2);
+        let (player_1, player_2) = ctr.internal_get_game(&game_id).get_player_accounts();
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 0);
+        request_takeback(&mut ctx, &mut ctr, &player_1, &game_id);
+        // player 2 moves instead of accepting
+        make_move(&mut ctx, &mut ctr, &player_2, &game_id, 1, 1);
+        accept_takeback(&mut ctx, &mut ctr, &player_2, &game_id);
+    }
+    #[test]
+    #[should_panic(expected = "You can only take back your own last move")]
+    fn test_takeback_opponent_move() {
+        let (mut ctx, mut ctr, game_id) = start_game_with_takebacks(1);
+        let (player_1, player_2) = ctr.internal_get_game(&game_id).get_player_accounts();
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 0);
+        request_takeback(&mut ctx, &mut ctr, &player_2, &game_id);
+    }
+    fn start_clock_game(time_control: TimeControl) -> (VMContextBuilder, Contract, GameId) {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        storage_deposit(&mut ctx, &mut ctr, &user(), ONE_NEAR);
+        storage_deposit(&mut ctx, &mut ctr, &opponent(), ONE_NEAR);
+        make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear { time_control: Some(time_control), ..Default::default() });
+        make_available_config(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, GameConfigNear { time_control: Some(time_control), ..Default::default() });
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        (ctx, ctr, game_id)
+    }
+    #[test]
+    fn test_clock_increment() {
+        let (mut ctx, mut ctr, game_id) = start_clock_game(TimeControl { initial: 300, increment: 5 });
+        let (player_1, player_2) = ctr.internal_get_game(&game_id).get_player_accounts();
+        // longer than `max_turn_duration`, only the clock counts
+        testing_env!(ctx.block_timestamp(sec_to_nano(200)).build());
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 0);
+        testing_env!(ctx.block_timestamp(sec_to_nano(250)).build());
+        make_move(&mut ctx, &mut ctr, &player_2, &game_id, 1, 1);
+        let game = ctr.internal_get_game(&game_id);
+        assert_eq!(game.game_state, GameState::Active);
+        assert_eq!(game.clock.unwrap().remaining, [105, 255]);
+
+        testing_env!(ctx.block_timestamp(sec_to_nano(300)).build());
+        assert_eq!(ctr.get_active_games()[0].1.time_left, Some((55, 255)));
+    }
+    #[test]
+    fn test_clock_runs_out() {
+        let (mut ctx, mut ctr, game_id) = start_clock_game(TimeControl { initial: 300, increment: 5 });
+        let (player_1, player_2) = ctr.internal_get_game(&game_id).get_player_accounts();
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 0);
+        testing_env!(ctx.block_timestamp(sec_to_nano(299)).build());
+        make_move(&mut ctx, &mut ctr, &player_2, &game_id, 1, 1);
+        // player 1 has 305 seconds left
+        testing_env!(ctx.block_timestamp(sec_to_nano(299 + 306)).build());
+        let result = make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 1);
+        assert_eq!(result, Some(GameResult::Win(player_2.clone())));
+        let stored = ctr.get_game(&game_id);
+        assert_eq!(stored.reason, ResultReason::Timeout);
+        assert!(ctr.get_active_games().is_empty());
+    }
+    #[test]
+    fn test_claim_timeout_win_with_clock() {
+        let (mut ctx, mut ctr, game_id) = start_clock_game(TimeControl { initial: 600, increment: 0 });
+        let (player_1, player_2) = ctr.internal_get_game(&game_id).get_player_accounts();
+        // more than `TIMEOUT_WIN`, but the clock didn't run out
+        testing_env!(ctx.predecessor_account_id(player_2.clone()).block_timestamp(sec_to_nano(TIMEOUT_WIN + 1)).build());
+        assert_eq!(ctr.claim_timeout_win(&game_id), None);
+        testing_env!(ctx.predecessor_account_id(player_2.clone()).block_timestamp(sec_to_nano(601)).build());
+        assert_eq!(ctr.claim_timeout_win(&game_id), Some(GameResult::Win(player_2.clone())));
+        assert_eq!(ctr.get_stats(&player_1).victories_num, 0);
+    }
+    #[test]
+    #[should_panic(expected = "initial clock time must be between 60 and 14400sec")]
+    fn test_make_available_invalid_clock() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear { time_control: Some(TimeControl { initial: 10, increment: 0 }), ..Default::default() });
+    }
+    #[test]
+    fn test_game_moves() {
+        let (mut ctx, mut ctr, game_id) = start_match(1);
+        let (player_1, player_2) = ctr.internal_get_game(&game_id).get_player_accounts();
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 0);
+        testing_env!(ctx.block_timestamp(sec_to_nano(30)).build());
+        make_move(&mut ctx, &mut ctr, &player_2, &game_id, 1, 0);
+
+        let moves = ctr.get_game_moves(&game_id, 0, 10);
+        assert_eq!(moves, vec![
+            MoveRecord { coords: Coords { x: 0, y: 0 }, piece: Piece::O, timestamp: 0 },
+            MoveRecord { coords: Coords { x: 0, y: 1 }, piece: Piece::X, timestamp: 30 },
+        ]);
+        assert_eq!(ctr.get_game_moves(&game_id, 1, 1), moves[1..].to_vec());
+        assert!(ctr.get_game_moves(&game_id, 2, 10).is_empty());
+
+        // the finished game keeps the whole log
+        for col in 1..4 {
+            make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, col);
+            make_move(&mut ctx, &mut ctr, &player_2, &game_id, 1, col);
+        }
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 0, 4);
+        let stored = ctr.get_game(&game_id);
+        assert_eq!(stored.game_result, GameResult::Win(player_1.clone()));
+        assert_eq!(stored.moves.len(), 9);
+        assert_eq!(stored.moves[..2].to_vec(), moves);
+        assert_eq!(ctr.get_game_moves(&game_id, 8, 10), vec![
+            MoveRecord { coords: Coords { x: 4, y: 0 }, piece: Piece::O, timestamp: 30 },
+        ]);
+    }
+    #[test]
+    fn test_game_notation() {
+        let (mut ctx, mut ctr, game_id) = start_match(1);
+        win_with_o(&mut ctx, &mut ctr, &game_id);
+        let notation = ctr.get_game_notation(&game_id);
+        assert_eq!(notation, "a1 a2 b1 b2 c1 c2 d1 d2 e1");
+        assert!(ctr.verify_game_record(&game_id, notation));
+        assert!(ctr.verify_game_record(&game_id, "A1 A2 B1 B2\nC1 C2 D1 D2 E1".to_string()));
+        // not the recorded sequence
+        assert!(!ctr.verify_game_record(&game_id, "a1 a2 b1 b2 c1 c2 d1 d2".to_string()));
+        assert!(!ctr.verify_game_record(&game_id, "b1 a2 a1 b2 c1 c2 d1 d2 e1".to_string()));
+    }
+    #[test]
+    fn test_verify_game_record_draw() {
+        let (mut ctx, mut ctr, game_id) = start_match(1);
+        let (player_1, player_2) = ctr.internal_get_game(&game_id).get_player_accounts();
+        make_move(&mut ctx, &mut ctr, &player_1, &game_id, 12, 12);
+        make_move(&mut ctx, &mut ctr, &player_2, &game_id, 24, 0);
+        offer_draw(&mut ctx, &mut ctr, &player_1, &game_id);
+        accept_draw(&mut ctx, &mut ctr, &player_2, &game_id);
+        assert_eq!(ctr.get_game_notation(&game_id), "m13 a25");
+        assert!(ctr.verify_game_record(&game_id, "m13 a25".to_string()));
+    }
+    #[test]
+    fn test_verify_game_record_invalid_notation() {
+        let (mut ctx, mut ctr, game_id) = start_match(1);
+        win_with_o(&mut ctx, &mut ctr, &game_id);
+        assert!(!ctr.verify_game_record(&game_id, "a1 z1".to_string()));
+        assert!(!ctr.verify_game_record(&game_id, "a1 a0".to_string()));
+    }
+    #[test]
+    fn test_verify_game_record_wrong_winner() {
+        let (mut ctx, mut ctr, game_id) = start_match(1);
+        let winner = win_with_o(&mut ctx, &mut ctr, &game_id);
+        let notation = ctr.get_game_notation(&game_id);
+        let mut game = ctr.get_game(&game_id);
+        assert_eq!(game.player1, winner);
+        // the record credits the win to the player of X
+        game.game_result = GameResult::Win(game.player2.clone());
+        ctr.stored_games.insert(&game_id, &game);
+        assert!(!ctr.verify_game_record(&game_id, notation));
+    }
+    fn start_coin_toss_game(secrets: (u128, u128)) -> (VMContextBuilder, Contract, GameId) {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        storage_deposit(&mut ctx, &mut ctr, &user(), ONE_NEAR);
+        storage_deposit(&mut ctx, &mut ctr, &opponent(), ONE_NEAR);
+        make_deposit(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, String::new());
+        make_deposit(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, String::new());
+        make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear { commitment: Some(CoinToss::commitment(&user(), secrets.0)), ..Default::default() });
+        make_available_config(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, GameConfigNear { commitment: Some(CoinToss::commitment(&opponent(), secrets.1)), ..Default::default() });
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        (ctx, ctr, game_id)
+    }
+    fn reveal_secret(ctx: &mut VMContextBuilder, ctr: &mut Contract, user: &AccountId, game_id: &GameId, secret: u128) {
+        testing_env!(ctx.predecessor_account_id(user.clone()).build());
+        ctr.reveal_secret(game_id, U128(secret))
+    }
+    #[test]
+    fn test_coin_toss() {
+        let (mut ctx, mut ctr, game_id) = start_coin_toss_game((6, 3));
+        let game = ctr.internal_get_game(&game_id);
+        assert_eq!(game.game_state, GameState::NotStarted);
+        assert_eq!(game.coin_toss.unwrap().reveal_deadline, REVEAL_TIMEOUT);
+
+        reveal_secret(&mut ctx, &mut ctr, &user(), &game_id, 6);
+        assert_eq!(ctr.internal_get_game(&game_id).game_state, GameState::NotStarted);
+        testing_env!(ctx.block_timestamp(sec_to_nano(100)).build());
+        reveal_secret(&mut ctx, &mut ctr, &opponent(), &game_id, 3);
+
+        // 6 ^ 3 is odd: the opponent moves first
+        let game = ctr.internal_get_game(&game_id);
+        assert_eq!(game.game_state, GameState::Active);
+        assert_eq!(game.players, (opponent(), user()));
+        assert_eq!(game.initiated_at, 100);
+        make_move(&mut ctx, &mut ctr, &opponent(), &game_id, 0, 0);
+        assert_eq!(ctr.internal_get_game(&game_id).board.piece(&Coords { x: 0, y: 0 }), Some(Piece::O));
+    }
+    #[test]
+    fn test_coin_toss_even() {
+        let (mut ctx, mut ctr, game_id) = start_coin_toss_game((6, 2));
+        reveal_secret(&mut ctx, &mut ctr, &opponent(), &game_id, 2);
+        reveal_secret(&mut ctx, &mut ctr, &user(), &game_id, 6);
+        let game = ctr.internal_get_game(&game_id);
+        assert_eq!(game.game_state, GameState::Active);
+        assert_eq!(game.players, (user(), opponent()));
+    }
+    #[test]
+    #[should_panic(expected = "Secret doesn't match the commitment")]
+    fn test_reveal_wrong_secret() {
+        let (mut ctx, mut ctr, game_id) = start_coin_toss_game((6, 3));
+        reveal_secret(&mut ctx, &mut ctr, &user(), &game_id, 3);
+    }
+    #[test]
+    fn test_coin_toss_forfeit() {
+        let (mut ctx, mut ctr, game_id) = start_coin_toss_game((6, 3));
+        reveal_secret(&mut ctx, &mut ctr, &user(), &game_id, 6);
+        testing_env!(ctx.predecessor_account_id(user()).block_timestamp(sec_to_nano(REVEAL_TIMEOUT)).build());
+        assert_eq!(ctr.claim_timeout_win(&game_id), None);
+
+        testing_env!(ctx.predecessor_account_id(user()).block_timestamp(sec_to_nano(REVEAL_TIMEOUT + 1)).build());
+        assert_eq!(ctr.claim_timeout_win(&game_id), Some(GameResult::Win(user())));
+        assert_eq!(ctr.get_game(&game_id).reason, ResultReason::Timeout);
+        assert_eq!(ctr.get_stats(&opponent()).penalties_num, 1);
+        assert!(ctr.get_active_games().is_empty());
+    }
+    #[test]
+    fn test_coin_toss_not_revealed() {
+        let (mut ctx, mut ctr, game_id) = start_coin_toss_game((6, 3));
+        let balance = ctr.get_cheddar_balance(&user());
+        testing_env!(ctx.predecessor_account_id(opponent()).block_timestamp(sec_to_nano(REVEAL_TIMEOUT + 1)).build());
+        assert_eq!(ctr.claim_timeout_win(&game_id), Some(GameResult::Tie));
+        // the game never started: full refund without fee, rating or results
+        assert_eq!(ctr.get_cheddar_balance(&user()), balance + ONE_CHEDDAR);
+        assert_eq!(ctr.get_stats(&user()).penalties_num, 0);
+        assert_eq!(ctr.get_rating(user(), None), Rating::default());
+        assert_eq!(ctr.get_last_games()[0].1.reward_or_tie_refund.balance, U128(ONE_CHEDDAR));
+    }
+    #[test]
+    fn test_coin_toss_not_revealed_cancels_match() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        storage_deposit(&mut ctx, &mut ctr, &user(), ONE_NEAR);
+        storage_deposit(&mut ctx, &mut ctr, &opponent(), ONE_NEAR);
+        make_deposit(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, String::new());
+        make_deposit(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, String::new());
+        make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear { best_of: Some(3), commitment: Some(CoinToss::commitment(&user(), 6)), ..Default::default() });
+        make_available_config(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, GameConfigNear { best_of: Some(3), commitment: Some(CoinToss::commitment(&opponent(), 3)), ..Default::default() });
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        let match_id = ctr.internal_get_game(&game_id).match_id.unwrap();
+        let balance = ctr.get_cheddar_balance(&opponent());
+
+        testing_env!(ctx.predecessor_account_id(user()).block_timestamp(sec_to_nano(REVEAL_TIMEOUT + 1)).build());
+        assert_eq!(ctr.claim_timeout_win(&game_id), Some(GameResult::Tie));
+        let game_match = ctr.get_match(match_id);
+        assert_eq!(game_match.state, GameState::Finished);
+        assert_eq!(game_match.result, None);
+        assert_eq!(game_match.games, vec![game_id]);
+        assert!(ctr.get_active_matches(0, 10).is_empty());
+        assert!(ctr.get_active_games().is_empty());
+        assert_eq!(ctr.get_cheddar_balance(&opponent()), balance + ONE_CHEDDAR);
+        assert_eq!(ctr.get_stats(&opponent()).victories_num, 0);
+    }
+    #[test]
+    #[should_panic(expected = "Mismatched first player draws!")]
+    fn test_start_game_mismatched_commitments() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear { commitment: Some(CoinToss::commitment(&user(), 6)), ..Default::default() });
+        make_available_config(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, GameConfigNear::default());
+        start_game(&mut ctx, &mut ctr, &user(), &opponent());
+    }
+    fn channel_keypair(seed: u8) -> ed25519_dalek::Keypair {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = (&secret).into();
+        ed25519_dalek::Keypair { secret, public }
+    }
+    fn channel_key(keypair: &ed25519_dalek::Keypair) -> PublicKey {
+        // curve type byte 0 is ed25519
+        PublicKey::try_from([&[0u8][..], keypair.public.as_bytes()].concat()).unwrap()
+    }
+    /// returns the keypairs of `players.0` and `players.1`
+    fn start_channel_game() -> (VMContextBuilder, Contract, GameId, (AccountId, AccountId), [ed25519_dalek::Keypair; 2]) {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        storage_deposit(&mut ctx, &mut ctr, &user(), ONE_NEAR);
+        storage_deposit(&mut ctx, &mut ctr, &opponent(), ONE_NEAR);
+        make_deposit(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, String::new());
+        make_deposit(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, String::new());
+        make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear { channel_key: Some(channel_key(&channel_keypair(1))), ..Default::default() });
+        make_available_config(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, GameConfigNear { channel_key: Some(channel_key(&channel_keypair(2))), ..Default::default() });
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        let players = ctr.internal_get_game(&game_id).get_player_accounts();
+        let keypairs = if players.0 == user() {
+            [channel_keypair(1), channel_keypair(2)]
+        } else {
+            [channel_keypair(2), channel_keypair(1)]
+        };
+        (ctx, ctr, game_id, players, keypairs)
+    }
+    /// signatures of the state by the last mover and of the state before it by the opponent
+    fn sign_channel_state(keypairs: &[ed25519_dalek::Keypair; 2], game_id: GameId, moves: &[Coords]) -> Vec<Base64VecU8> {
+        use ed25519_dalek::Signer;
+        (1..=moves.len())
+            .rev()
+            .take(2)
+            .map(|len| {
+                let signature = keypairs[(len - 1) % 2].sign(&channel_message(game_id, &moves[..len]));
+                Base64VecU8(signature.to_bytes().to_vec())
+            })
+            .collect()
+    }
+    fn submit_channel_state(
+        ctx: &mut VMContextBuilder,
+        ctr: &mut Contract,
+        user: &AccountId,
+        game_id: &GameId,
+        moves: Vec<Coords>,
+        signatures: Vec<Base64VecU8>,
+    ) -> Option<GameResult> {
+        testing_env!(ctx.predecessor_account_id(user.clone()).build());
+        ctr.submit_channel_state(game_id, moves, signatures)
+    }
+    fn coords_list(coords: &[(u8, u8)]) -> Vec<Coords> {
+        coords.iter().map(|(x, y)| Coords { x: *x, y: *y }).collect()
+    }
+    #[test]
+    fn test_channel_win() {
+        let (mut ctx, mut ctr, game_id, players, keypairs) = start_channel_game();
+        assert!(ctr.get_active_games()[0].1.channel.is_some());
+        // O fills row 0, X plays row 5
+        let moves = coords_list(&[(0, 0), (0, 5), (1, 0), (1, 5), (2, 0), (2, 5), (3, 0), (3, 5), (4, 0)]);
+        let signatures = sign_channel_state(&keypairs, game_id, &moves);
+        let result = submit_channel_state(&mut ctx, &mut ctr, &players.1, &game_id, moves, signatures);
+
+        assert_eq!(result, Some(GameResult::Win(players.0.clone())));
+        let game = ctr.get_game(&game_id);
+        assert_eq!(game.reason, ResultReason::Line);
+        assert_eq!(game.moves.len(), 9);
+        assert!(ctr.get_active_games().is_empty());
+        let notation = ctr.get_game_notation(&game_id);
+        assert!(ctr.verify_game_record(&game_id, notation));
+    }
+    #[test]
+    fn test_channel_dispute() {
+        let (mut ctx, mut ctr, game_id, players, keypairs) = start_channel_game();
+        let moves = coords_list(&[(7, 7), (8, 8)]);
+        let signatures = sign_channel_state(&keypairs, game_id, &moves);
+        testing_env!(ctx.block_timestamp(sec_to_nano(100)).build());
+        assert_eq!(submit_channel_state(&mut ctx, &mut ctr, &players.1, &game_id, moves, signatures), None);
+        let game = ctr.internal_get_game(&game_id);
+        assert_eq!(game.current_player_account_id(), players.0);
+        assert_eq!(game.channel.unwrap().challenge_deadline, Some(100 + CHALLENGE_PERIOD));
+
+        testing_env!(ctx.predecessor_account_id(players.1.clone()).block_timestamp(sec_to_nano(100 + CHALLENGE_PERIOD)).build());
+        assert_eq!(ctr.claim_timeout_win(&game_id), None);
+        // `players.0` didn't answer with a later state
+        testing_env!(ctx.predecessor_account_id(players.1.clone()).block_timestamp(sec_to_nano(101 + CHALLENGE_PERIOD)).build());
+        assert_eq!(ctr.claim_timeout_win(&game_id), Some(GameResult::Win(players.1.clone())));
+        assert_eq!(ctr.get_game(&game_id).reason, ResultReason::Timeout);
+    }
+    #[test]
+    fn test_channel_answer() {
+        let (mut ctx, mut ctr, game_id, players, keypairs) = start_channel_game();
+        // X stopped answering off-chain, O sends the first move
+        let moves = coords_list(&[(7, 7)]);
+        let signatures = sign_channel_state(&keypairs, game_id, &moves);
+        submit_channel_state(&mut ctx, &mut ctr, &players.0, &game_id, moves, signatures);
+
+        testing_env!(ctx.block_timestamp(sec_to_nano(CHALLENGE_PERIOD)).build());
+        let moves = coords_list(&[(7, 7), (8, 8)]);
+        let signatures = sign_channel_state(&keypairs, game_id, &moves);
+        submit_channel_state(&mut ctx, &mut ctr, &players.1, &game_id, moves, signatures);
+        let game = ctr.internal_get_game(&game_id);
+        assert_eq!(game.current_player_account_id(), players.0);
+        assert_eq!(game.channel.unwrap().challenge_deadline, Some(2 * CHALLENGE_PERIOD));
+        assert_eq!(game.board.piece(&Coords { x: 8, y: 8 }), Some(Piece::X));
+        assert_eq!(game.moves.len(), 2);
+    }
+    #[test]
+    #[should_panic(expected = "Channel state must be later than the submitted one")]
+    fn test_channel_older_state() {
+        let (mut ctx, mut ctr, game_id, players, keypairs) = start_channel_game();
+        let moves = coords_list(&[(7, 7), (8, 8)]);
+        let signatures = sign_channel_state(&keypairs, game_id, &moves);
+        submit_channel_state(&mut ctx, &mut ctr, &players.1, &game_id, moves, signatures);
+        let moves = coords_list(&[(7, 7)]);
+        let signatures = sign_channel_state(&keypairs, game_id, &moves);
+        submit_channel_state(&mut ctx, &mut ctr, &players.0, &game_id, moves, signatures);
+    }
+    #[test]
+    #[should_panic(expected = "Invalid signature of the channel state after move 1")]
+    fn test_channel_forged_move() {
+        let (mut ctx, mut ctr, game_id, players, keypairs) = start_channel_game();
+        // `players.1` replaces the opponent's move signed on (7, 7)
+        let mut signatures = sign_channel_state(&keypairs, game_id, &coords_list(&[(7, 7), (8, 8)]));
+        signatures[0] = sign_channel_state(&keypairs, game_id, &coords_list(&[(0, 0), (8, 8)])).remove(0);
+        submit_channel_state(&mut ctx, &mut ctr, &players.1, &game_id, coords_list(&[(0, 0), (8, 8)]), signatures);
+    }
+    #[test]
+    #[should_panic(expected = "Moves of channel games are signed off-chain")]
+    fn test_make_move_in_channel_game() {
+        let (mut ctx, mut ctr, game_id, players, _) = start_channel_game();
+        make_move(&mut ctx, &mut ctr, &players.0, &game_id, 7, 7);
+    }
+    #[test]
+    #[should_panic(expected = "Mismatched channel modes!")]
+    fn test_start_game_mismatched_channel() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear { channel_key: Some(channel_key(&channel_keypair(1))), ..Default::default() });
+        make_available_config(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, GameConfigNear::default());
+        start_game(&mut ctx, &mut ctr, &user(), &opponent());
+    }
+    fn join_queue(ctx: &mut VMContextBuilder, ctr: &mut Contract, user: &AccountId, bet: Balance) -> Option<GameId> {
+        join_queue_min_stake(ctx, ctr, user, bet, None)
+    }
+    fn join_queue_min_stake(ctx: &mut VMContextBuilder, ctr: &mut Contract, user: &AccountId, bet: Balance, min_stake: Option<Balance>) -> Option<GameId> {
+        testing_env!(ctx.predecessor_account_id(user.clone()).build());
+        ctr.join_queue(bet, min_stake)
+    }
+    #[test]
+    fn test_join_queue() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        assert_eq!(join_queue(&mut ctx, &mut ctr, &user(), 2 * ONE_CHEDDAR), None);
+        assert_eq!(ctr.get_queue_status()[0].depth, 1);
+        // another bracket
+        assert_eq!(join_queue(&mut ctx, &mut ctr, &referrer(), 10 * ONE_CHEDDAR), None);
+        assert_eq!(ctr.get_queue_status()[1].depth, 1);
+
+        let game_id = join_queue(&mut ctx, &mut ctr, &opponent(), 2 * ONE_CHEDDAR).unwrap();
+        let game = ctr.internal_get_game(&game_id);
+        assert!(game.contains_player_account_id(&user()) && game.contains_player_account_id(&opponent()));
+        assert_eq!(game.game_state, GameState::Active);
+        assert_eq!(game.reward().balance, U128(4 * ONE_CHEDDAR));
+        let status = ctr.get_queue_status();
+        assert_eq!((status[0].depth, status[1].depth), (0, 1));
+        assert_eq!(status[1].min_bet, U128(10 * ONE_CHEDDAR));
+        assert_eq!(status[1].max_bet, Some(U128(100 * ONE_CHEDDAR)));
+        assert!(ctr.get_available_players(None).is_empty());
+    }
+    #[test]
+    fn test_join_queue_min_stake() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        assert_eq!(join_queue(&mut ctx, &mut ctr, &user(), 99 * ONE_CHEDDAR), None);
+        // a lower bet in the same bracket isn't paired with a player who didn't accept it
+        assert_eq!(join_queue_min_stake(&mut ctx, &mut ctr, &opponent(), 10 * ONE_CHEDDAR, Some(10 * ONE_CHEDDAR)), None);
+        assert_eq!(ctr.get_queue_status()[1].depth, 2);
+        assert_eq!(join_queue_min_stake(&mut ctx, &mut ctr, &referrer(), 30 * ONE_CHEDDAR, Some(20 * ONE_CHEDDAR)), None);
+
+        // the third player skips the stakes of 50 and 10, 30 suits both
+        let player: AccountId = "third".parse().unwrap();
+        let game_id = join_queue_min_stake(&mut ctx, &mut ctr, &player, 50 * ONE_CHEDDAR, Some(20 * ONE_CHEDDAR)).unwrap();
+        let game = ctr.internal_get_game(&game_id);
+        assert!(game.contains_player_account_id(&referrer()));
+        assert_eq!(game.reward().balance, U128(60 * ONE_CHEDDAR));
+        assert_eq!(ctr.internal_queue_bracket(&user()), Some(1));
+        assert_eq!(ctr.internal_queue_bracket(&opponent()), Some(1));
+    }
+    #[test]
+    #[should_panic(expected = "min_stake must be between")]
+    fn test_join_queue_min_stake_above_bet() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        join_queue_min_stake(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, Some(2 * ONE_CHEDDAR));
+    }
+    #[test]
+    fn test_join_queue_skips_busy_player() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        join_queue(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR);
+        // the user offers a game to anyone instead of waiting
+        make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear::default());
+        assert_eq!(join_queue(&mut ctx, &mut ctr, &referrer(), ONE_CHEDDAR), None);
+        assert_eq!(ctr.internal_queue_bracket(&user()), None);
+        let game_id = join_queue(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR).unwrap();
+        assert!(ctr.internal_get_game(&game_id).contains_player_account_id(&referrer()));
+        assert_eq!(ctr.get_available_players(None).len(), 1);
+    }
+    #[test]
+    fn test_join_queue_skips_playing_player() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        join_queue(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR);
+        // the user accepts an offer while waiting in the queue
+        make_available_config(&mut ctx, &mut ctr, &referrer(), ONE_CHEDDAR, GameConfigNear::default());
+        make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear::default());
+        start_game(&mut ctx, &mut ctr, &user(), &referrer());
+        assert_eq!(join_queue(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR), None);
+        assert_eq!(ctr.internal_queue_bracket(&user()), None);
+        assert_eq!(ctr.internal_queue_bracket(&opponent()), Some(0));
+    }
+    #[test]
+    fn test_join_queue_expired_entry() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        join_queue(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR);
+        testing_env!(ctx.block_timestamp(sec_to_nano(MAX_AVAILABLE_FOR + 1)).build());
+        assert_eq!(ctr.get_queue_status()[0].depth, 0);
+        // the expired entry no longer blocks the user
+        assert_eq!(ctr.internal_queue_bracket(&user()), None);
+        assert_eq!(join_queue(&mut ctx, &mut ctr, &user(), 10 * ONE_CHEDDAR), None);
+        assert_eq!(ctr.get_queue_status()[0].depth, 0);
+        assert_eq!(join_queue(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR), None);
+        assert_eq!(ctr.internal_queue_bracket(&user()), Some(1));
+        // the expired entry was dropped from its bracket
+        assert_eq!(ctr.queue.get(&0).unwrap().len(), 1);
+    }
+    #[test]
+    #[should_panic(expected = "Already in the queue")]
+    fn test_join_queue_twice() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        join_queue(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR);
+        join_queue(&mut ctx, &mut ctr, &user(), 10 * ONE_CHEDDAR);
+    }
+    #[test]
+    fn test_leave_queue() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        join_queue(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR);
+        testing_env!(ctx.predecessor_account_id(user()).build());
+        ctr.leave_queue();
+        assert_eq!(ctr.get_queue_status()[0].depth, 0);
+        assert_eq!(join_queue(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR), None);
+    }
+    #[test]
+    fn test_ratings() {
+        let (mut ctx, mut ctr, game_id) = start_match(1);
+        let (player_1, player_2) = ctr.internal_get_game(&game_id).get_player_accounts();
+        assert_eq!(ctr.get_rating(player_1.clone(), None), Rating::default());
+        win_with_o(&mut ctx, &mut ctr, &game_id);
+
+        let (rating_1, rating_2) = (ctr.get_rating(player_1.clone(), None), ctr.get_rating(player_2.clone(), None));
+        assert!(rating_1.rating > 1500.0 && rating_2.rating < 1500.0);
+        assert!((rating_1.rating - 1500.0 - (1500.0 - rating_2.rating)).abs() < 0.000_001);
+        assert!(rating_1.deviation < 350.0);
+        let history = ctr.get_rating_history(player_1.clone(), None);
+        assert_eq!(history.len(), 1);
+        assert_eq!((history[0].game_id, history[0].rating), (game_id, rating_1.rating));
+        // other rules are rated separately
+        let pool = RatingPool { rules: MnkRules::new(15, 15, 5), variant: Variant::Freestyle };
+        assert_eq!(ctr.get_rating(player_1, Some(pool)), Rating::default());
+    }
+    #[test]
+    fn test_ratings_tie_and_timeout() {
+        let (mut ctx, mut ctr, game_id) = start_match(1);
+        let (player_1, player_2) = ctr.internal_get_game(&game_id).get_player_accounts();
+        offer_draw(&mut ctx, &mut ctr, &player_2, &game_id);
+        accept_draw(&mut ctx, &mut ctr, &player_1, &game_id);
+        let rating_1 = ctr.get_rating(player_1.clone(), None);
+        assert!((rating_1.rating - 1500.0).abs() < 0.000_001);
+        assert!(rating_1.deviation < 350.0);
+
+        // player 1 doesn't move in the second game
+        make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear { best_of: Some(1), ..Default::default() });
+        make_available_config(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, GameConfigNear { best_of: Some(1), ..Default::default() });
+        let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        let game = ctr.internal_get_game(&game_id);
+        let (loser, winner) = (game.current_player_account_id(), game.next_player_account_id());
+        testing_env!(ctx.predecessor_account_id(winner.clone()).block_timestamp(sec_to_nano(TIMEOUT_WIN + 1)).build());
+        assert_eq!(ctr.claim_timeout_win(&game_id), Some(GameResult::Win(winner.clone())));
+        assert!(ctr.get_rating(winner.clone(), None).rating > 1500.0);
+        assert!(ctr.get_rating(loser, None).rating < 1500.0);
+        assert_eq!(ctr.get_rating_history(winner, None).len(), 2);
+    }
+    /// Plays a game won by O, returns the winner and the loser
+    fn rated_players(ctx: &mut VMContextBuilder, ctr: &mut Contract, game_id: &GameId) -> (AccountId, AccountId) {
+        let (player_1, player_2) = ctr.internal_get_game(game_id).get_player_accounts();
+        win_with_o(ctx, ctr, game_id);
+        (player_1, player_2)
+    }
+    #[test]
+    fn test_available_players_by_rating() {
+        let (mut ctx, mut ctr, game_id) = start_match(1);
+        let (winner, loser) = rated_players(&mut ctx, &mut ctr, &game_id);
+        make_available_config(&mut ctx, &mut ctr, &loser, ONE_CHEDDAR, GameConfigNear { max_rating: Some(1600), ..Default::default() });
+        make_available_config(&mut ctx, &mut ctr, &winner, ONE_CHEDDAR, GameConfigNear { min_rating: Some(1600), ..Default::default() });
+        assert_eq!(ctr.get_available_players(None).len(), 2);
+        let offers = |ctr: &Contract, account_id: &AccountId| -> Vec<AccountId> {
+            ctr.get_available_players(Some(account_id.clone())).into_iter().map(|(offer_id, _)| offer_id).collect()
+        };
+        assert_eq!(offers(&ctr, &winner), vec![winner.clone()]);
+        assert_eq!(offers(&ctr, &loser), vec![loser.clone()]);
+        // unrated players are rated 1500
+        assert_eq!(offers(&ctr, &acc_cheddar()), vec![loser]);
+    }
+    #[test]
+    #[should_panic(expected="is outside the bounds of the offer: None..Some(1600)")]
+    fn test_start_game_rating_above_bound() {
+        let (mut ctx, mut ctr, game_id) = start_match(1);
+        let (winner, loser) = rated_players(&mut ctx, &mut ctr, &game_id);
+        make_available_config(&mut ctx, &mut ctr, &loser, ONE_CHEDDAR, GameConfigNear { max_rating: Some(1600), ..Default::default() });
+        make_available_config(&mut ctx, &mut ctr, &winner, ONE_CHEDDAR, GameConfigNear::default());
+        start_game(&mut ctx, &mut ctr, &winner, &loser);
+    }
+    #[test]
+    #[should_panic(expected="is outside the bounds of the offer: Some(1400)..None")]
+    fn test_start_game_own_rating_bound() {
+        // the bounds of the player starting the game apply to the opponent as well
+        let (mut ctx, mut ctr, game_id) = start_match(1);
+        let (winner, loser) = rated_players(&mut ctx, &mut ctr, &game_id);
+        make_available_config(&mut ctx, &mut ctr, &loser, ONE_CHEDDAR, GameConfigNear::default());
+        make_available_config(&mut ctx, &mut ctr, &winner, ONE_CHEDDAR, GameConfigNear { min_rating: Some(1400), ..Default::default() });
+        start_game(&mut ctx, &mut ctr, &winner, &loser);
+    }
+    #[test]
+    #[should_panic(expected="min_rating 1600 is above max_rating 1400")]
+    fn test_make_available_invalid_rating_bounds() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear { min_rating: Some(1600), max_rating: Some(1400), ..Default::default() });
+    }
+    #[test]
+    fn test_leaderboards() {
+        let (mut ctx, mut ctr, game_id) = start_match(1);
+        let (winner, loser) = rated_players(&mut ctx, &mut ctr, &game_id);
+        let accounts = |ctr: &Contract, board: Leaderboard| -> Vec<AccountId> {
+            ctr.get_leaderboard(board, None, 10).into_iter().map(|entry| entry.account_id).collect()
+        };
+        assert_eq!(accounts(&ctr, Leaderboard::Victories), vec![winner.clone(), loser.clone()]);
+        assert_eq!(accounts(&ctr, Leaderboard::TotalReward), vec![winner.clone(), loser.clone()]);
+        assert_eq!(accounts(&ctr, Leaderboard::Rating(RatingPool::default())), vec![winner.clone(), loser.clone()]);
+        // the next page starts after the last entry of the first one
+        let first_page = ctr.get_leaderboard(Leaderboard::Victories, None, 1);
+        assert_eq!(ctr.get_leaderboard(Leaderboard::Victories, first_page.into_iter().last(), 10), vec![
+            LeaderboardEntry { rank: 2, account_id: loser.clone(), score: U128(0) },
+        ]);
+        let top_rating = &ctr.get_leaderboard(Leaderboard::Rating(RatingPool::default()), None, 1)[0];
+        assert_eq!(top_rating.score.0, ctr.get_rating(winner, None).rating.round() as u128);
+        // not enough games for the win rate, no penalties
+        assert_eq!(ctr.get_leaderboard_len(Leaderboard::WinRate), 0);
+        assert!(ctr.get_penalty_users(None, 10).is_empty());
+        assert_eq!(ctr.get_accounts_played(1, 10).len(), 1);
+    }
+    #[test]
+    fn test_win_rate_leaderboard() {
+        let (mut ctx, mut ctr, game_id) = start_match(1);
+        win_with_o(&mut ctx, &mut ctr, &game_id);
+        for _ in 1..MIN_GAMES_FOR_WIN_RATE {
+            assert_eq!(ctr.get_leaderboard_len(Leaderboard::WinRate), 0);
+            make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear { best_of: Some(1), ..Default::default() });
+            make_available_config(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, GameConfigNear { best_of: Some(1), ..Default::default() });
+            let game_id = start_game(&mut ctx, &mut ctr, &user(), &opponent());
+            win_with_o(&mut ctx, &mut ctr, &game_id);
+        }
+        let leaderboard = ctr.get_leaderboard(Leaderboard::WinRate, None, 10);
+        assert_eq!(leaderboard.len(), 2);
+        assert!(leaderboard[0].score >= leaderboard[1].score);
+        let victories = ctr.get_stats(&leaderboard[0].account_id).victories_num;
+        assert_eq!(leaderboard[0].score.0, (victories * BASIS_P as u64 / MIN_GAMES_FOR_WIN_RATE) as u128);
+        assert_eq!(leaderboard[0].score.0 + leaderboard[1].score.0, BASIS_P as u128);
+    }
+    fn create_season(ctx: &mut VMContextBuilder, ctr: &mut Contract, start: Timestamp, end: Timestamp) -> SeasonId {
+        testing_env!(ctx.predecessor_account_id(ctx.context.current_account_id.clone()).build());
+        ctr.create_season(start, end, 5000, vec![7000, 3000])
+    }
+    fn claim_season_prize(ctx: &mut VMContextBuilder, ctr: &mut Contract, user: &AccountId, season_id: SeasonId) -> U128 {
+        testing_env!(ctx.predecessor_account_id(user.clone()).build());
+        ctr.claim_season_prize(season_id)
+    }
+    #[test]
+    fn test_season_prizes() {
+        let (mut ctx, mut ctr, game_id) = start_match(1);
+        let season_id = create_season(&mut ctx, &mut ctr, 0, 100);
+        assert_eq!(ctr.get_current_season().map(|(season_id, _)| season_id), Some(season_id));
+        let (winner, loser) = rated_players(&mut ctx, &mut ctr, &game_id);
+
+        let fee = 2 * ONE_CHEDDAR / BASIS_P as u128 * MIN_FEES as u128;
+        let prize_pool = fee / BASIS_P as u128 * 5000;
+        assert_eq!(ctr.get_season(season_id).prize_pool, U128(prize_pool));
+        assert_eq!(ctr.get_season_stats(season_id, winner.clone()), SeasonStats { games_num: 1, victories_num: 1 });
+        assert_eq!(ctr.get_season_stats(season_id, loser.clone()), SeasonStats { games_num: 1, victories_num: 0 });
+        // lifetime stats aren't affected
+        assert_eq!(ctr.get_stats(&winner).victories_num, 1);
+
+        testing_env!(ctx.block_timestamp(sec_to_nano(100)).build());
+        assert!(ctr.get_current_season().is_none());
+        let balance = ctr.get_cheddar_balance(&winner);
+        let first_prize = prize_pool / BASIS_P as u128 * 7000;
+        assert_eq!(claim_season_prize(&mut ctx, &mut ctr, &winner, season_id), U128(first_prize));
+        assert_eq!(ctr.get_cheddar_balance(&winner), balance + first_prize);
+        assert_eq!(ctr.get_season_results(season_id), vec![
+            SeasonPrize { rank: 1, account_id: winner.clone(), prize: U128(first_prize), claimed: true },
+            SeasonPrize { rank: 2, account_id: loser.clone(), prize: U128(prize_pool / BASIS_P as u128 * 3000), claimed: false },
+        ]);
+        assert_eq!(claim_season_prize(&mut ctx, &mut ctr, &loser, season_id), U128(prize_pool / BASIS_P as u128 * 3000));
+        assert_eq!(ctr.get_season(season_id).claimed, vec![winner, loser]);
+    }
+    #[test]
+    #[should_panic(expected="The prize is already claimed")]
+    fn test_claim_season_prize_twice() {
+        let (mut ctx, mut ctr, game_id) = start_match(1);
+        let season_id = create_season(&mut ctx, &mut ctr, 0, 100);
+        let (winner, _) = rated_players(&mut ctx, &mut ctr, &game_id);
+        testing_env!(ctx.block_timestamp(sec_to_nano(100)).build());
+        claim_season_prize(&mut ctx, &mut ctr, &winner, season_id);
+        claim_season_prize(&mut ctx, &mut ctr, &winner, season_id);
+    }
+    #[test]
+    #[should_panic(expected="The season isn't over")]
+    fn test_claim_season_prize_too_early() {
+        let (mut ctx, mut ctr, game_id) = start_match(1);
+        let season_id = create_season(&mut ctx, &mut ctr, 0, 100);
+        let (winner, _) = rated_players(&mut ctx, &mut ctr, &game_id);
+        claim_season_prize(&mut ctx, &mut ctr, &winner, season_id);
+    }
+    #[test]
+    fn test_seasons() {
+        let (mut ctx, mut ctr, game_id) = start_match(1);
+        create_season(&mut ctx, &mut ctr, 10, 100);
+        // the game is settled before the season starts
+        let (winner, _) = rated_players(&mut ctx, &mut ctr, &game_id);
+        assert_eq!(ctr.get_season(0).prize_pool, U128(0));
+        assert_eq!(ctr.get_season_stats(0, winner), SeasonStats::default());
+        assert!(ctr.get_season_results(0).is_empty());
+
+        create_season(&mut ctx, &mut ctr, 100, 200);
+        testing_env!(ctx.block_timestamp(sec_to_nano(150)).build());
+        assert_eq!(ctr.get_current_season().map(|(season_id, _)| season_id), Some(1));
+        let seasons = ctr.get_seasons(1, 10);
+        assert_eq!(seasons.len(), 1);
+        assert_eq!((seasons[0].1.start, seasons[0].1.end), (100, 200));
+    }
+    #[test]
+    #[should_panic(expected="Season overlaps season 0")]
+    fn test_create_overlapping_season() {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MIN_FEES), None,  Some(MIN_GAME_DURATION));
+        create_season(&mut ctx, &mut ctr, 0, 100);
+        create_season(&mut ctx, &mut ctr, 50, 150);
+    }
+    fn tournament_player(i: usize) -> AccountId {
+        format!("player{}.near", i).parse().unwrap()
+    }
+    /// Creates a tournament of `size` players with an entry fee of 1 CHEDDAR starting at 100 sec
+    /// and registers `registered` players, the first one registers first
+    fn setup_tournament(size: u8, registered: usize) -> (VMContextBuilder, Contract, TournamentId) {
+        let (mut ctx, mut ctr) = setup_contract(user(), Some(MAX_FEES as u32), None,  Some(MIN_GAME_DURATION));
+        testing_env!(ctx.predecessor_account_id(ctx.context.current_account_id.clone()).build());
+        let tournament_id = ctr.create_tournament(TournamentConfig {
+            size,
+            entry_fee: U128(ONE_CHEDDAR),
+            start: 100,
+            prizes: vec![6000, 3000, 1000],
+            rules: None,
+            variant: None,
+            opening: None,
+            time_control: None,
+        });
+        for i in 0..registered {
+            register_tournament_player(&mut ctx, &mut ctr, &tournament_player(i), tournament_id);
+        }
+        (ctx, ctr, tournament_id)
+    }
+    fn register_tournament_player(ctx: &mut VMContextBuilder, ctr: &mut Contract, user: &AccountId, tournament_id: TournamentId) {
+        if !ctr.is_user_registered(user) {
+            storage_deposit(ctx, ctr, user, ONE_NEAR);
+        }
+        let msg = near_sdk::serde_json::to_string(&TournamentEntryArgs { tournament_id }).expect("err serialize");
+        make_deposit(ctx, ctr, user, ONE_CHEDDAR, msg);
+    }
+    fn start_tournament(ctx: &mut VMContextBuilder, ctr: &mut Contract, tournament_id: TournamentId) -> Vec<GameId> {
+        testing_env!(ctx.block_timestamp(sec_to_nano(100)).predecessor_account_id(user()).build());
+        ctr.start_tournament(tournament_id)
+    }
+    /// In every running game of the round the loser gives up
+    fn play_tournament_round(ctx: &mut VMContextBuilder, ctr: &mut Contract, tournament_id: TournamentId, better_seed_wins: bool) {
+        let tournament = ctr.get_tournament(tournament_id);
+        // tied games were replaced by their replay
+        let running_games: Vec<&TournamentGame> = tournament
+            .games
+            .iter()
+            .filter(|game| game.round == tournament.round && ctr.games.get(&game.game_id).is_some())
+            .collect();
+        for game in running_games {
+            let (better_seed, worse_seed) = (game.seeds.0.min(game.seeds.1), game.seeds.0.max(game.seeds.1));
+            let loser = if better_seed_wins { worse_seed } else { better_seed };
+            testing_env!(ctx
+                .attached_deposit(ONE_YOCTO)
+                .predecessor_account_id(tournament.players[loser as usize].clone())
+                .build());
+            ctr.give_up(&game.game_id);
+        }
+    }
+    #[test]
+    fn test_bracket_order() {
+        assert_eq!(bracket_order(8), vec![0, 7, 3, 4, 1, 6, 2, 5]);
+        let bracket = bracket_order(32);
+        // the two best seeds are in different halves of the bracket
+        assert_eq!((bracket[0], bracket[16]), (0, 1));
+        let mut seeds = bracket.clone();
+        seeds.sort_unstable();
+        assert_eq!(seeds, (0..32).collect::<Vec<u8>>());
+    }
+    #[test]
+    fn test_tournament() {
+        let (mut ctx, mut ctr, tournament_id) = setup_tournament(8, 7);
+        // the last player to register is the best rated one
+        let mut player_rating = PlayerRating::default();
+        player_rating.rating.rating = 1800.0;
+        ctr.ratings.insert(&(RatingPool::default(), tournament_player(7)), &player_rating);
+        register_tournament_player(&mut ctx, &mut ctr, &tournament_player(7), tournament_id);
+
+        let game_ids = start_tournament(&mut ctx, &mut ctr, tournament_id);
+        assert_eq!(game_ids.len(), 4);
+        let tournament = ctr.get_tournament(tournament_id);
+        assert_eq!(tournament.state, TournamentState::Active);
+        let seeded: Vec<AccountId> = [7, 0, 1, 2, 3, 4, 5, 6].iter().map(|i| tournament_player(*i)).collect();
+        assert_eq!(tournament.players, seeded);
+        let entry_fees = 8 * ONE_CHEDDAR;
+        let prize_pool = entry_fees - entry_fees / BASIS_P as u128 * MAX_FEES as u128;
+        assert_eq!(tournament.prize_pool, U128(prize_pool));
+        // the best seed plays the worst one and moves first in the first round
+        assert_eq!(tournament.games[0].seeds, (0, 7));
+        assert_eq!(ctr.internal_get_game(&game_ids[0]).get_player_accounts(), (seeded[0].clone(), seeded[7].clone()));
+        assert_eq!(ctr.internal_get_game(&game_ids[0]).tournament_id, Some(tournament_id));
+
+        play_tournament_round(&mut ctx, &mut ctr, tournament_id, true);
+        let tournament = ctr.get_tournament(tournament_id);
+        assert_eq!((tournament.round, tournament.bracket.clone()), (2, vec![0, 3, 1, 2]));
+        // the worse seed moves first in the second round
+        let game = &tournament.games[4];
+        assert_eq!((game.round, game.seeds), (2, (0, 3)));
+        assert_eq!(ctr.internal_get_game(&game.game_id).get_player_accounts(), (seeded[3].clone(), seeded[0].clone()));
+        // eliminated players can play other games
+        assert_eq!(join_queue(&mut ctx, &mut ctr, &seeded[7], ONE_CHEDDAR), None);
+        play_tournament_round(&mut ctx, &mut ctr, tournament_id, true);
+        // the second seed wins the final
+        play_tournament_round(&mut ctx, &mut ctr, tournament_id, false);
+
+        let tournament = ctr.get_tournament(tournament_id);
+        assert_eq!(tournament.state, TournamentState::Finished);
+        assert_eq!(tournament.games.len(), 7);
+        let standings: Vec<AccountId> = [1, 0, 2, 3, 4, 5, 6, 7].iter().map(|seed| seeded[*seed].clone()).collect();
+        assert_eq!(tournament.standings, standings);
+        assert_eq!(ctr.get_cheddar_balance(&standings[0]), prize_pool / BASIS_P as u128 * 6000);
+        assert_eq!(ctr.get_cheddar_balance(&standings[1]), prize_pool / BASIS_P as u128 * 3000);
+        assert_eq!(ctr.get_cheddar_balance(&standings[2]), prize_pool / BASIS_P as u128 * 1000);
+        assert_eq!(ctr.get_cheddar_balance(&standings[3]), 0);
+        assert_eq!(ctr.get_stats(&standings[0]).victories_num, 3);
+        assert_eq!(ctr.get_tournaments(0, 10).len(), 1);
+        assert!(ctr.tournament_players.is_empty());
+    }
+    #[test]
+    #[should_panic(expected="Player @player0.near plays in tournament 0")]
+    fn test_tournament_player_joins_queue() {
+        let (mut ctx, mut ctr, _) = setup_tournament(8, 1);
+        join_queue(&mut ctx, &mut ctr, &tournament_player(0), ONE_CHEDDAR);
+    }
+    #[test]
+    #[should_panic(expected="Player @player0.near plays in tournament 0")]
+    fn test_register_two_tournaments() {
+        let (mut ctx, mut ctr, _) = setup_tournament(8, 1);
+        testing_env!(ctx.predecessor_account_id(ctx.context.current_account_id.clone()).build());
+        let tournament_id = ctr.create_tournament(TournamentConfig {
+            size: 8,
+            entry_fee: U128(ONE_CHEDDAR),
+            start: 100,
+            prizes: vec![10000],
+            rules: None,
+            variant: None,
+            opening: None,
+            time_control: None,
+        });
+        register_tournament_player(&mut ctx, &mut ctr, &tournament_player(0), tournament_id);
+    }
+    #[test]
+    #[should_panic(expected="Player @user already start another game")]
+    fn test_register_tournament_while_playing() {
+        let (mut ctx, mut ctr, tournament_id) = setup_tournament(8, 0);
+        make_available_config(&mut ctx, &mut ctr, &user(), ONE_CHEDDAR, GameConfigNear::default());
+        make_available_config(&mut ctx, &mut ctr, &opponent(), ONE_CHEDDAR, GameConfigNear::default());
+        start_game(&mut ctx, &mut ctr, &user(), &opponent());
+        register_tournament_player(&mut ctx, &mut ctr, &user(), tournament_id);
+    }
+    #[test]
+    fn test_tournament_tie_break() {
+        let (mut ctx, mut ctr, tournament_id) = setup_tournament(8, 8);
+        let game_ids = start_tournament(&mut ctx, &mut ctr, tournament_id);
+        let (player_1, player_2) = ctr.internal_get_game(&game_ids[0]).get_player_accounts();
+        offer_draw(&mut ctx, &mut ctr, &player_1, &game_ids[0]);
+        accept_draw(&mut ctx, &mut ctr, &player_2, &game_ids[0]);
+
+        // the tied game is replayed with swapped pieces
+        let tournament = ctr.get_tournament(tournament_id);
+        let replay = tournament.games.last().unwrap().clone();
+        assert!(replay.replay);
+        assert_eq!((replay.round, replay.seeds, tournament.games[0].winner), (1, (0, 7), None));
+        assert_eq!(ctr.internal_get_game(&replay.game_id).get_player_accounts(), (player_2.clone(), player_1.clone()));
+
+        // a tied replay advances the better seed
+        offer_draw(&mut ctx, &mut ctr, &player_1, &replay.game_id);
+        accept_draw(&mut ctx, &mut ctr, &player_2, &replay.game_id);
+        let tournament = ctr.get_tournament(tournament_id);
+        assert_eq!(tournament.games.last().unwrap().winner, Some(0));
+        assert_eq!(tournament.games.len(), 5);
+        play_tournament_round(&mut ctx, &mut ctr, tournament_id, false);
+        assert_eq!(ctr.get_tournament(tournament_id).round, 2);
+    }
+    #[test]
+    fn test_tournament_cancelled() {
+        let (mut ctx, mut ctr, tournament_id) = setup_tournament(8, 3);
+        assert!(start_tournament(&mut ctx, &mut ctr, tournament_id).is_empty());
+        assert_eq!(ctr.get_tournament(tournament_id).state, TournamentState::Cancelled);
+        for i in 0..3 {
+            assert_eq!(ctr.get_cheddar_balance(&tournament_player(i)), ONE_CHEDDAR);
+        }
+        assert!(ctr.tournament_players.is_empty());
+    }
+    #[test]
+    #[should_panic(expected="Tournament is full")]
+    fn test_register_full_tournament() {
+        let (mut ctx, mut ctr, tournament_id) = setup_tournament(8, 8);
+        register_tournament_player(&mut ctx, &mut ctr, &user(), tournament_id);
+    }
+    #[test]
+    #[should_panic(expected="Tournament registration is closed")]
+    fn test_register_started_tournament() {
+        let (mut ctx, mut ctr, tournament_id) = setup_tournament(8, 3);
+        testing_env!(ctx.block_timestamp(sec_to_nano(100)).build());
+        register_tournament_player(&mut ctx, &mut ctr, &user(), tournament_id);
+    }
+    #[test]
+    #[should_panic(expected="tournament size must be one of [8, 16, 32]")]
+    fn test_create_tournament_invalid_size() {
+        setup_tournament(10, 0);
+    }
 }
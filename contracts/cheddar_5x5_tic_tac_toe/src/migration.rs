@@ -0,0 +1,217 @@
+use crate::*;
+use crate::views::Tiles;
+
+/// `Game` of the 1.0.0 contract, its board was an `UnorderedMap` stored under `StorageKey::GameBoard`
+#[derive(BorshSerialize, BorshDeserialize)]
+pub(crate) struct GameV1 {
+    pub game_state: GameState,
+    pub players: (AccountId, AccountId),
+    pub current_piece: Piece,
+    pub current_player_index: u8,
+    pub reward: GameDeposit,
+    pub total_turns: u8,
+    pub initiated_at: Timestamp,
+    pub last_turn_timestamp: Timestamp,
+    pub current_duration: Duration,
+    pub last_move: Option<Coords>,
+    pub winner: Option<Winner>,
+    pub board: UnorderedMap<Coords, Piece>,
+    pub duration: Duration,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub(crate) struct GameConfigV1 {
+    pub deposit: Balance,
+    pub opponent_id: Option<AccountId>,
+    pub referrer_id: Option<AccountId>,
+    pub created_at: Timestamp,
+    pub available_until: Timestamp,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub(crate) struct GameLimitedViewV1 {
+    pub game_result: GameResult,
+    pub player1: AccountId,
+    pub player2: AccountId,
+    pub reward_or_tie_refund: GameDeposit,
+    pub tiles: Tiles,
+    pub last_move: Option<(Coords, Piece)>,
+}
+
+/// State of the 1.0.0 contract
+#[derive(BorshSerialize, BorshDeserialize)]
+pub(crate) struct ContractV1 {
+    pub cheddar: AccountId,
+    pub min_deposit: Balance,
+    pub games: UnorderedMap<GameId, GameV1>,
+    pub available_players: UnorderedMap<AccountId, GameConfigV1>,
+    pub stats: UnorderedMap<AccountId, Stats>,
+    pub next_game_id: GameId,
+    pub service_fee: u16,
+    pub max_game_duration: u64,
+    pub referrer_fee_share: u16,
+    pub last_update_timestamp: Timestamp,
+    pub max_turn_duration: Duration,
+    pub max_stored_games: u8,
+    pub stored_games: UnorderedMap<GameId, GameLimitedViewV1>,
+    pub registered_players: UnorderedMap<AccountId, Vault>,
+}
+
+impl From<GameV1> for Game {
+    /// Copies the board into `Game.board` and `Game.moves`, the old map is left in storage.
+    /// Stones are never removed from a 1.0.0 board, so its order is the order of the moves;
+    /// move times weren't recorded and are set to `last_turn_timestamp`.
+    fn from(game: GameV1) -> Self {
+        let mut board = BitBoard::default();
+        let moves = game
+            .board
+            .iter()
+            .map(|(coords, piece)| {
+                board.insert(&coords, piece);
+                MoveRecord { coords, piece, timestamp: game.last_turn_timestamp }
+            })
+            .collect();
+        Game {
+            game_state: game.game_state,
+            players: game.players,
+            current_piece: game.current_piece,
+            current_player_index: game.current_player_index,
+            reward: game.reward,
+            total_turns: game.total_turns,
+            initiated_at: game.initiated_at,
+            last_turn_timestamp: game.last_turn_timestamp,
+            current_duration: game.current_duration,
+            last_move: game.last_move,
+            winner: game.winner,
+            board,
+            duration: game.duration,
+            rules: DEFAULT_RULES,
+            variant: Variant::Freestyle,
+            opening: Opening::Standard,
+            opening_phase: None,
+            match_id: None,
+            tournament_id: None,
+            draw_offer: None,
+            takebacks_left: (0, 0),
+            takeback_request: None,
+            previous_move: None,
+            clock: None,
+            moves,
+            opening_stones: 0,
+            coin_toss: None,
+            channel: None,
+        }
+    }
+}
+
+impl From<GameConfigV1> for GameConfig {
+    fn from(config: GameConfigV1) -> Self {
+        GameConfig {
+            deposit: config.deposit,
+            opponent_id: config.opponent_id,
+            referrer_id: config.referrer_id,
+            created_at: config.created_at,
+            available_until: config.available_until,
+            rules: DEFAULT_RULES,
+            variant: Variant::Freestyle,
+            opening: Opening::Standard,
+            best_of: 1,
+            takebacks: 0,
+            time_control: None,
+            commitment: None,
+            channel_key: None,
+            min_rating: None,
+            max_rating: None,
+        }
+    }
+}
+
+impl From<GameLimitedViewV1> for GameLimitedView {
+    /// 1.0.0 didn't record the reason of a result: a win is a `Line` if the winner
+    /// placed the last stone, otherwise the loser gave up or ran out of time (`Timeout`)
+    fn from(view: GameLimitedViewV1) -> Self {
+        let reason = match &view.game_result {
+            GameResult::Tie => ResultReason::NoMovesLeft,
+            GameResult::Win(winner) => {
+                let winner_piece = if *winner == view.player1 { Piece::O } else { Piece::X };
+                match view.last_move {
+                    Some((_, piece)) if piece == winner_piece => ResultReason::Line,
+                    _ => ResultReason::Timeout,
+                }
+            }
+        };
+        GameLimitedView {
+            game_result: view.game_result,
+            reason,
+            player1: view.player1,
+            player2: view.player2,
+            reward_or_tie_refund: view.reward_or_tie_refund,
+            tiles: view.tiles,
+            last_move: view.last_move,
+            rules: DEFAULT_RULES,
+            match_id: None,
+            tournament_id: None,
+            moves: Vec::new(),
+            variant: Variant::Freestyle,
+            opening_stones: 0,
+        }
+    }
+}
+
+/// Rewrites every value of `map` under the same storage prefix with the new layout
+fn migrate_map<K, V1, V>(mut map: UnorderedMap<K, V1>, prefix: StorageKey) -> UnorderedMap<K, V>
+where
+    K: BorshSerialize + BorshDeserialize,
+    V1: BorshSerialize + BorshDeserialize,
+    V: BorshSerialize + BorshDeserialize + From<V1>,
+{
+    let entries = map.to_vec();
+    map.clear();
+    let mut new_map = UnorderedMap::new(prefix);
+    for (key, value) in entries {
+        new_map.insert(&key, &V::from(value));
+    }
+    new_map
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Upgrades the state of the 1.0.0 contract. In-flight games, open offers and stored
+    /// games are rewritten with the defaults of the options added since (5-in-a-row,
+    /// freestyle, standard opening, single game). Boards of the games stay under
+    /// `StorageKey::GameBoard` until they are swept with `clear_game_boards`.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let old: ContractV1 = env::state_read().expect("Contract state not found");
+        Self {
+            cheddar: old.cheddar,
+            min_deposit: old.min_deposit,
+            games: migrate_map(old.games, StorageKey::Games),
+            available_players: migrate_map(old.available_players, StorageKey::Players),
+            stats: old.stats,
+            next_game_id: old.next_game_id,
+            service_fee: old.service_fee,
+            max_game_duration: old.max_game_duration,
+            referrer_fee_share: old.referrer_fee_share,
+            last_update_timestamp: old.last_update_timestamp,
+            max_turn_duration: old.max_turn_duration,
+            max_stored_games: old.max_stored_games,
+            stored_games: migrate_map(old.stored_games, StorageKey::StoredGames),
+            registered_players: old.registered_players,
+            matches: UnorderedMap::new(StorageKey::Matches),
+            next_match_id: 0,
+            queue: UnorderedMap::new(StorageKey::Queue),
+            ratings: UnorderedMap::new(StorageKey::Ratings),
+            leaderboards: UnorderedMap::new(StorageKey::Leaderboards),
+            seasons: UnorderedMap::new(StorageKey::Seasons),
+            next_season_id: 0,
+            season_stats: UnorderedMap::new(StorageKey::SeasonStats),
+            tournaments: UnorderedMap::new(StorageKey::Tournaments),
+            next_tournament_id: 0,
+            active_matches: UnorderedSet::new(StorageKey::ActiveMatches),
+            tournament_players: UnorderedMap::new(StorageKey::TournamentPlayers),
+            queued_players: UnorderedMap::new(StorageKey::QueuedPlayers),
+        }
+    }
+}
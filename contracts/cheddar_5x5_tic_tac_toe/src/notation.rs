@@ -0,0 +1,100 @@
+use crate::*;
+
+/// Columns `a` to `y` of the notation, one per `Coords.x`
+const COLUMNS: &[u8; 25] = b"abcdefghijklmnopqrstuvwxy";
+
+/// Column letter and row number of a tile, `{x: 7, y: 7}` is `h8`
+pub(crate) fn coords_to_notation(coords: &Coords) -> String {
+    format!("{}{}", COLUMNS[coords.x as usize] as char, coords.y + 1)
+}
+
+/// Tile of a single move like `h8`, `None` if it isn't valid notation
+fn notation_to_coords(token: &str) -> Option<Coords> {
+    let mut chars = token.chars();
+    let column = chars.next()?.to_ascii_lowercase();
+    let x = COLUMNS.iter().position(|c| *c as char == column)?;
+    let row: u8 = chars.as_str().parse().ok()?;
+    if row == 0 || row as usize > COLUMNS.len() {
+        return None;
+    }
+    Some(Coords { x: x as u8, y: row - 1 })
+}
+
+/// Moves separated by whitespace, e.g. `h8 i9 h10`, `None` if a move isn't valid notation
+pub(crate) fn parse_notation(notation: &str) -> Option<Vec<Coords>> {
+    notation.split_whitespace().map(notation_to_coords).collect()
+}
+
+/// Replays `moves` from an empty board, O moves first and pieces alternate.
+/// Returns `Err` on an illegal move or a move after the game was decided.
+fn replay(game: &GameLimitedView, moves: &[Coords]) -> Result<Option<Winner>, MoveError> {
+    let mut board = BitBoard::default();
+    let mut piece = Piece::O;
+    let mut winner = None;
+    for (index, coords) in moves.iter().enumerate() {
+        if winner.is_some() {
+            return Err(MoveError::GameOver);
+        }
+        game.rules.check_move(&board, coords)?;
+        // Renju restrictions apply to O from the first move after the opening
+        if game.variant == Variant::Renju && piece == Piece::O && index >= game.opening_stones as usize {
+            if let Some(kind) = forbidden_move(&game.rules, &board, coords, Piece::O) {
+                return Err(MoveError::Forbidden { kind, row: coords.y, col: coords.x });
+            }
+        }
+        board.insert(coords, piece);
+        winner = game.rules.game_result(&board, coords, piece);
+        piece = piece.other();
+    }
+    Ok(winner)
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Moves of a stored game in gomoku notation: columns `a`-`y`, rows `1`-`25`
+    /// counted from `y = 0`, separated by spaces
+    pub fn get_game_notation(&self, game_id: &GameId) -> String {
+        self.get_game(game_id)
+            .moves
+            .iter()
+            .map(|record| coords_to_notation(&record.coords))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Returns true if `notation` is the move sequence of a stored game and replaying
+    /// it with the rules of the game leads to the recorded result, false on invalid notation
+    pub fn verify_game_record(&self, game_id: &GameId, notation: String) -> bool {
+        let game = self.get_game(game_id);
+        let moves = match parse_notation(&notation) {
+            Some(moves) => moves,
+            None => return false,
+        };
+        if moves.len() != game.moves.len()
+            || moves
+                .iter()
+                .zip(game.moves.iter())
+                .any(|(coords, record)| (coords.x, coords.y) != (record.coords.x, record.coords.y))
+        {
+            return false;
+        }
+        match replay(&game, &moves) {
+            // the board decided the game, `player1` plays O
+            Ok(Some(winner)) => {
+                let game_result = match winner {
+                    Winner::O => GameResult::Win(game.player1.clone()),
+                    Winner::X => GameResult::Win(game.player2.clone()),
+                    Winner::Tie => GameResult::Tie,
+                };
+                game.reason == game.rules.result_reason(winner) && game.game_result == game_result
+            }
+            // the game ended off the board
+            Ok(None) => match game.reason {
+                ResultReason::DrawByAgreement => game.game_result == GameResult::Tie,
+                ResultReason::GiveUp | ResultReason::Timeout => game.game_result != GameResult::Tie,
+                _ => false,
+            },
+            Err(_) => false,
+        }
+    }
+}
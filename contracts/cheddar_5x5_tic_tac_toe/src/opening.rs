@@ -0,0 +1,116 @@
+use crate::*;
+
+/// How the first stones are placed. With `Swap` and `Swap2` the first player only
+/// proposes an opening and the opponent picks the piece, which balances
+/// the first move advantage.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Opening {
+    /// players take turns from the first move
+    #[default]
+    Standard,
+    /// first player places O, X, O; second player picks the piece to play
+    Swap,
+    /// like `Swap`, but instead of picking a piece the second player can place
+    /// X and O more and let the first player pick the piece
+    Swap2,
+}
+
+impl Opening {
+    pub fn first_phase(self) -> Option<OpeningPhase> {
+        match self {
+            Opening::Standard => None,
+            Opening::Swap | Opening::Swap2 => Some(OpeningPhase::PlaceThree),
+        }
+    }
+}
+
+/// Step of the opening waiting for `Game.current_player_index`
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum OpeningPhase {
+    /// first player places O, X, O
+    PlaceThree,
+    /// second player picks a piece, in `Swap2` it can place X, O instead
+    FirstChoice,
+    /// `Swap2` only: first player picks a piece after X, O were added
+    SecondChoice,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Places the opening stones, alternating O and X starting with O:
+    /// three stones in `OpeningPhase::PlaceThree`, two more (X, O) when the
+    /// `Swap2` opponent doesn't want to pick a piece yet
+    pub fn place_opening(&mut self, game_id: &GameId, stones: Vec<Coords>) {
+        let cur_timestamp: Duration = nano_to_sec(env::block_timestamp()) as Duration;
+        self.internal_ping_expired_games(cur_timestamp);
+
+        let mut game = self.internal_get_game(game_id);
+        assert_eq!(env::predecessor_account_id(), game.current_player_account_id(), "not your turn");
+        assert_eq!(game.game_state, GameState::Active, "Current game isn't active");
+
+        let (first_piece, next_phase) = match game.opening_phase {
+            Some(OpeningPhase::PlaceThree) => {
+                assert_eq!(stones.len(), 3, "Expected 3 opening stones, got {}", stones.len());
+                (Piece::O, OpeningPhase::FirstChoice)
+            }
+            Some(OpeningPhase::FirstChoice) if game.opening == Opening::Swap2 => {
+                assert_eq!(stones.len(), 2, "Expected 2 opening stones, got {}", stones.len());
+                (Piece::X, OpeningPhase::SecondChoice)
+            }
+            _ => panic!("Opening stones can't be placed now"),
+        };
+        let mut piece = first_piece;
+        for coords in stones.iter() {
+            if let Err(e) = game.check_move(coords) {
+                panic!("Invalid opening stone: {:?}", e);
+            }
+            game.board.insert(coords, piece);
+            game.moves.push(MoveRecord { coords: coords.clone(), piece, timestamp: cur_timestamp });
+            piece = piece.other();
+        }
+        game.last_move = stones.last().cloned();
+        game.opening_stones += stones.len() as u8;
+        game.opening_phase = Some(next_phase);
+        // the other player picks a piece or places more stones
+        game.current_player_index = 1 - game.current_player_index;
+        self.internal_end_turn(game_id, game, cur_timestamp);
+    }
+
+    /// Ends the opening: the caller plays with `piece` and X moves next
+    pub fn choose_piece(&mut self, game_id: &GameId, piece: Piece) {
+        let cur_timestamp: Duration = nano_to_sec(env::block_timestamp()) as Duration;
+        self.internal_ping_expired_games(cur_timestamp);
+
+        let mut game = self.internal_get_game(game_id);
+        let player = env::predecessor_account_id();
+        assert_eq!(player, game.current_player_account_id(), "not your turn");
+        assert_eq!(game.game_state, GameState::Active, "Current game isn't active");
+        assert!(
+            matches!(
+                game.opening_phase,
+                Some(OpeningPhase::FirstChoice) | Some(OpeningPhase::SecondChoice)
+            ),
+            "Piece can't be chosen now"
+        );
+
+        // `players.0` always plays O
+        let opponent = game.next_player_account_id();
+        let players = match piece {
+            Piece::O => (player.clone(), opponent),
+            Piece::X => (opponent, player.clone()),
+        };
+        if players.0 != game.players.0 {
+            if let Some(clock) = game.clock.as_mut() {
+                clock.swap_players();
+            }
+        }
+        game.players = players;
+        log!("{} plays {}", player, piece);
+        game.opening_phase = None;
+        game.current_piece = Piece::X;
+        game.current_player_index = 1;
+        self.internal_end_turn(game_id, game, cur_timestamp);
+    }
+}
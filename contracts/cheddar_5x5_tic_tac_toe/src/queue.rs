@@ -0,0 +1,183 @@
+use near_sdk::collections::TreeMap;
+
+use crate::*;
+
+/// 1 CHEDDAR, the token has 24 decimals
+const ONE_CHEDDAR: Balance = 1_000_000_000_000_000_000_000_000;
+
+/// Waiting players with a stake the caller can't play `join_queue` skips before joining the queue
+pub(crate) const MAX_QUEUE_SKIPPED: u32 = 20;
+
+/// Lowest bet of each stake bracket, players are only paired within a bracket
+pub(crate) const STAKE_BRACKETS: [Balance; 4] = [MIN_BET_CHEDDAR, 10 * ONE_CHEDDAR, 100 * ONE_CHEDDAR, 1000 * ONE_CHEDDAR];
+
+/// Player waiting in the matchmaking queue
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct QueueEntry {
+    pub bet: Balance,
+    /// lowest stake the player accepts, `bet` unless set in `join_queue`
+    pub min_stake: Balance,
+}
+
+impl QueueEntry {
+    /// Stake of a game between both players, `None` if one of them doesn't accept it
+    fn stake_with(&self, other: &QueueEntry) -> Option<Balance> {
+        let stake = self.bet.min(other.bet);
+        (stake >= self.min_stake && stake >= other.min_stake).then_some(stake)
+    }
+}
+
+/// Players waiting in a stake bracket ordered by `(joined_at, account_id)`, the oldest one first.
+/// `joined_at` is a timestamp in seconds, the entry expires `MAX_AVAILABLE_FOR` later.
+pub(crate) type QueueIndex = TreeMap<(Timestamp, AccountId), QueueEntry>;
+
+/// Players waiting in a stake bracket. Players are paired at equal bets, or at the lower
+/// bet of the two if it isn't below the `min_stake` of either player.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct QueueStatus {
+    pub min_bet: U128,
+    /// lowest bet of the next bracket, `None` for the highest one
+    pub max_bet: Option<U128>,
+    /// players waiting in the bracket
+    pub depth: u32,
+}
+
+/// Index of the stake bracket of `bet`
+pub(crate) fn stake_bracket(bet: Balance) -> u8 {
+    STAKE_BRACKETS.iter().rposition(|min_bet| bet >= *min_bet).expect("Bet is below the lowest stake bracket") as u8
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Pairs the caller with the player waiting longest in the stake bracket of `bet` who plays
+    /// for the same stake, and starts the game with the default rules. Both players stake `bet`
+    /// unless they accept a lower stake down to `min_stake`: then they stake the lower of the
+    /// two bets. Without a matching player the caller joins the queue and `None` is returned.
+    pub fn join_queue(&mut self, bet: Balance, min_stake: Option<Balance>) -> Option<GameId> {
+        let cur_timestamp = env::block_timestamp();
+        // checkpoint
+        self.internal_ping_expired_players(cur_timestamp);
+
+        let account_id = env::predecessor_account_id();
+        assert!(bet >= MIN_BET_CHEDDAR, "Bet is too small. Required at least: {}", MIN_BET_CHEDDAR);
+        let min_stake = min_stake.unwrap_or(bet);
+        assert!(
+            (MIN_BET_CHEDDAR..=bet).contains(&min_stake),
+            "min_stake must be between {} and the bet",
+            MIN_BET_CHEDDAR
+        );
+        let caller_entry = QueueEntry { bet, min_stake };
+        assert!(self.available_players.get(&account_id).is_none(), "Already in the waiting list the list");
+        assert!(self.internal_queue_bracket(&account_id).is_none(), "Already in the queue");
+        self.internal_check_player_available(&account_id);
+        // an expired entry of the caller is replaced
+        self.internal_remove_from_queue(&account_id);
+
+        let bracket = stake_bracket(bet);
+        let now = nano_to_sec(cur_timestamp);
+        let mut queue = self.internal_get_queue(bracket);
+        // entries are visited from the oldest one: expired entries and entries whose player
+        // made an offer or went on to play elsewhere are dropped on the way
+        let mut cursor: Option<(Timestamp, AccountId)> = None;
+        let mut skipped = 0;
+        let (opponent_id, stake) = loop {
+            let next = match &cursor {
+                Some(key) => queue.higher(key),
+                None => queue.min(),
+            };
+            let key = match next {
+                Some(key) if skipped < MAX_QUEUE_SKIPPED => key,
+                _ => {
+                    queue.insert(&(now, account_id.clone()), &caller_entry);
+                    self.queue.insert(&bracket, &queue);
+                    self.queued_players.insert(&account_id, &(bracket, now));
+                    log!("@{} is waiting in stake bracket {}", account_id, bracket);
+                    return None;
+                }
+            };
+            let (joined_at, opponent_id) = key.clone();
+            if now > joined_at + MAX_AVAILABLE_FOR
+                || self.available_players.get(&opponent_id).is_some()
+                || self.internal_is_playing(&opponent_id)
+            {
+                queue.remove(&key);
+                self.queued_players.remove(&opponent_id);
+                continue;
+            }
+            match queue.get(&key).and_then(|entry| entry.stake_with(&caller_entry)) {
+                Some(stake) => {
+                    queue.remove(&key);
+                    self.queued_players.remove(&opponent_id);
+                    break (opponent_id, stake);
+                }
+                None => {
+                    skipped += 1;
+                    cursor = Some(key);
+                }
+            }
+        };
+        self.queue.insert(&bracket, &queue);
+        log!("@{} and @{} stake {}", opponent_id, account_id, stake);
+        self.available_players.insert(&opponent_id, &GameConfig::with_only_token_params(stake));
+        self.available_players.insert(&account_id, &GameConfig::with_only_token_params(stake));
+        Some(self.start_game(opponent_id))
+    }
+
+    /// Leaves the matchmaking queue
+    pub fn leave_queue(&mut self) {
+        let account_id = env::predecessor_account_id();
+        assert!(self.internal_queue_bracket(&account_id).is_some(), "Not in the queue");
+        self.internal_remove_from_queue(&account_id);
+    }
+
+    /// Number of players waiting in each stake bracket
+    pub fn get_queue_status(&self) -> Vec<QueueStatus> {
+        let now = nano_to_sec(env::block_timestamp());
+        STAKE_BRACKETS
+            .iter()
+            .enumerate()
+            .map(|(bracket, min_bet)| QueueStatus {
+                min_bet: (*min_bet).into(),
+                max_bet: STAKE_BRACKETS.get(bracket + 1).map(|max_bet| (*max_bet).into()),
+                depth: self.queue.get(&(bracket as u8)).map_or(0, |queue| {
+                    // expired entries are the oldest ones, until the next `join_queue` drops them
+                    let expired = queue
+                        .iter()
+                        .take_while(|((joined_at, _), _)| now > joined_at + MAX_AVAILABLE_FOR)
+                        .count() as u64;
+                    (queue.len() - expired) as u32
+                }),
+            })
+            .collect()
+    }
+}
+
+impl Contract {
+    fn internal_get_queue(&self, bracket: u8) -> QueueIndex {
+        self.queue
+            .get(&bracket)
+            .unwrap_or_else(|| QueueIndex::new(StorageKey::QueueBracket { bracket }))
+    }
+
+    /// Stake bracket the player is waiting in, `None` once the entry expired
+    pub(crate) fn internal_queue_bracket(&self, account_id: &AccountId) -> Option<u8> {
+        let now = nano_to_sec(env::block_timestamp());
+        match self.queued_players.get(account_id) {
+            Some((bracket, joined_at)) if now <= joined_at + MAX_AVAILABLE_FOR => Some(bracket),
+            _ => None,
+        }
+    }
+
+    /// Removes the entry of the player, expired or not
+    fn internal_remove_from_queue(&mut self, account_id: &AccountId) {
+        if let Some((bracket, joined_at)) = self.queued_players.remove(account_id) {
+            let mut queue = self.internal_get_queue(bracket);
+            queue.remove(&(joined_at, account_id.clone()));
+            self.queue.insert(&bracket, &queue);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use crate::*;
+
+/// Ratings are tracked separately for each board and variant
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct RatingPool {
+    pub rules: MnkRules,
+    pub variant: Variant,
+}
+
+impl Default for RatingPool {
+    fn default() -> Self {
+        Self { rules: DEFAULT_RULES, variant: Variant::Freestyle }
+    }
+}
+
+impl From<&Game> for RatingPool {
+    fn from(game: &Game) -> Self {
+        Self { rules: game.rules, variant: game.variant }
+    }
+}
+
+impl From<&GameConfig> for RatingPool {
+    fn from(config: &GameConfig) -> Self {
+        Self { rules: config.rules, variant: config.variant }
+    }
+}
+
+/// Rating of a player after a game, timestamp in seconds
+pub type RatingSnapshot = tic_tac_toe_rules::RatingSnapshot<Timestamp>;
+
+/// Rating of a player in a `RatingPool`
+pub type PlayerRating = tic_tac_toe_rules::PlayerRating<Timestamp>;
+
+#[near_bindgen]
+impl Contract {
+    /// Glicko-2 rating of the player in `pool`, the default rules by default.
+    /// Unrated players get `Rating::default()`.
+    pub fn get_rating(&self, account_id: AccountId, pool: Option<RatingPool>) -> Rating {
+        self.internal_get_rating(&account_id, pool.unwrap_or_default()).rating
+    }
+
+    /// Rating of the player after each of their latest games in `pool`, oldest first
+    pub fn get_rating_history(&self, account_id: AccountId, pool: Option<RatingPool>) -> Vec<RatingSnapshot> {
+        self.internal_get_rating(&account_id, pool.unwrap_or_default()).history
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_get_rating(&self, account_id: &AccountId, pool: RatingPool) -> PlayerRating {
+        self.ratings.get(&(pool, account_id.clone())).unwrap_or_default()
+    }
+
+    /// Panics if the rating of `account_id` is outside the bounds of `config`
+    pub(crate) fn internal_check_rating_bounds(&self, config: &GameConfig, account_id: &AccountId) {
+        let rating = self.internal_get_rating(account_id, RatingPool::from(config)).rating;
+        assert!(
+            config.accepts_rating(&rating),
+            "Rating {:.0} of @{} is outside the bounds of the offer: {:?}..{:?}",
+            rating.rating,
+            account_id,
+            config.min_rating,
+            config.max_rating
+        );
+    }
+
+    /// Updates the ratings of both players once a game settles, `winner` is `None` on a tie
+    pub(crate) fn internal_rate_game(&mut self, game_id: &GameId, winner: Option<&AccountId>) {
+        let game = self.internal_get_game(game_id);
+        let pool = RatingPool::from(&game);
+        let (player1, player2) = game.get_player_accounts();
+        let mut rating1 = self.internal_get_rating(&player1, pool);
+        let mut rating2 = self.internal_get_rating(&player2, pool);
+        let old_scores = (rating1.leaderboard_score(), rating2.leaderboard_score());
+        rating1.rate_game(&mut rating2, winner.map(|winner| *winner == player1), *game_id, nano_to_sec(env::block_timestamp()));
+        for (account_id, player_rating, old_score) in [(player1, rating1, old_scores.0), (player2, rating2, old_scores.1)] {
+            self.internal_update_leaderboard(Leaderboard::Rating(pool), &account_id, old_score, player_rating.leaderboard_score());
+            self.ratings.insert(&(pool, account_id), &player_rating);
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use crate::*;
+
+pub type SeasonId = u64;
+
+/// Most prize places of a season
+pub(crate) const MAX_SEASON_PRIZES: usize = 100;
+
+/// Owner-defined season: a share of the service fee of every game settled during the
+/// season goes to its prize pool, the top accounts of `Leaderboard::Season` claim it once it ends
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct Season {
+    /// timestamp in seconds
+    pub start: Timestamp,
+    /// timestamp in seconds, the season runs until `end` excluded
+    pub end: Timestamp,
+    /// share of the service fee kept by the contract, after the referrer fee, in `BASIS_P`
+    pub fee_share: u16,
+    /// share of the prize pool of each place in `BASIS_P`, the first place first
+    pub prizes: Vec<u16>,
+    pub prize_pool: Balance,
+    /// accounts which claimed their prize
+    pub claimed: Vec<AccountId>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct SeasonView {
+    pub start: Timestamp,
+    pub end: Timestamp,
+    pub fee_share: u16,
+    pub prizes: Vec<u16>,
+    pub prize_pool: U128,
+    pub claimed: Vec<AccountId>,
+}
+
+impl From<&Season> for SeasonView {
+    fn from(season: &Season) -> Self {
+        Self {
+            start: season.start,
+            end: season.end,
+            fee_share: season.fee_share,
+            prizes: season.prizes.clone(),
+            prize_pool: season.prize_pool.into(),
+            claimed: season.claimed.clone(),
+        }
+    }
+}
+
+/// Stats of an account in a season, the lifetime ones are in `Stats`
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct SeasonStats {
+    pub games_num: u64,
+    pub victories_num: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct SeasonPrize {
+    /// 1 for the first place
+    pub rank: u64,
+    pub account_id: AccountId,
+    pub prize: U128,
+    pub claimed: bool,
+}
+
+impl Season {
+    pub fn is_active(&self, ts: Timestamp) -> bool {
+        (self.start..self.end).contains(&ts)
+    }
+
+    /// Prize of the place `rank`, 0 for the first place
+    pub fn prize(&self, rank: usize) -> Balance {
+        self.prize_pool
+            .checked_div(BASIS_P.into())
+            .unwrap_or(0)
+            .checked_mul(self.prizes[rank] as u128)
+            .expect("multiplication overflow")
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Schedules a season in `start..end` (seconds), seasons can't overlap.
+    /// `fee_share` and `prizes` are in `BASIS_P`, see `Season`.
+    #[private]
+    pub fn create_season(&mut self, start: Timestamp, end: Timestamp, fee_share: u16, prizes: Vec<u16>) -> SeasonId {
+        assert!(start < end, "Season must end after it starts");
+        validate_season(fee_share, &prizes);
+        if let Some(last_season_id) = self.next_season_id.checked_sub(1) {
+            let last_season = self.internal_get_season(last_season_id);
+            assert!(start >= last_season.end, "Season overlaps season {}", last_season_id);
+        }
+        let season_id = self.next_season_id;
+        self.seasons.insert(&season_id, &Season {
+            start,
+            end,
+            fee_share,
+            prizes,
+            prize_pool: 0,
+            claimed: vec![],
+        });
+        self.next_season_id += 1;
+        season_id
+    }
+
+    /// Credits the prize of the caller's place in an ended season
+    pub fn claim_season_prize(&mut self, season_id: SeasonId) -> U128 {
+        let account_id = env::predecessor_account_id();
+        let mut season = self.internal_get_season(season_id);
+        assert!(nano_to_sec(env::block_timestamp()) >= season.end, "The season isn't over");
+        assert!(!season.claimed.contains(&account_id), "The prize is already claimed");
+        let rank = self
+            .get_leaderboard(Leaderboard::Season(season_id), None, season.prizes.len() as u64)
+            .iter()
+            .position(|entry| entry.account_id == account_id)
+            .expect("Not in the prize places of the season");
+        let prize = season.prize(rank);
+        season.claimed.push(account_id.clone());
+        self.seasons.insert(&season_id, &season);
+
+        log!("@{} claimed the prize of place {} in season {}: {}", account_id, rank + 1, season_id, prize);
+        self.internal_transfer(&account_id, prize);
+        prize.into()
+    }
+
+    pub fn get_season(&self, season_id: SeasonId) -> SeasonView {
+        SeasonView::from(&self.internal_get_season(season_id))
+    }
+
+    /// Season running now, if any
+    pub fn get_current_season(&self) -> Option<(SeasonId, SeasonView)> {
+        let season_id = self.internal_current_season_id(nano_to_sec(env::block_timestamp()))?;
+        Some((season_id, self.get_season(season_id)))
+    }
+
+    pub fn get_seasons(&self, from_index: u64, limit: u64) -> Vec<(SeasonId, SeasonView)> {
+        (from_index..self.next_season_id)
+            .take(limit as usize)
+            .map(|season_id| (season_id, self.get_season(season_id)))
+            .collect()
+    }
+
+    pub fn get_season_stats(&self, season_id: SeasonId, account_id: AccountId) -> SeasonStats {
+        self.season_stats.get(&(season_id, account_id)).unwrap_or_default()
+    }
+
+    /// Prize places of a season, final once the season ends
+    pub fn get_season_results(&self, season_id: SeasonId) -> Vec<SeasonPrize> {
+        let season = self.internal_get_season(season_id);
+        self.get_leaderboard(Leaderboard::Season(season_id), None, season.prizes.len() as u64)
+            .into_iter()
+            .enumerate()
+            .map(|(rank, entry)| SeasonPrize {
+                rank: entry.rank,
+                prize: season.prize(rank).into(),
+                claimed: season.claimed.contains(&entry.account_id),
+                account_id: entry.account_id,
+            })
+            .collect()
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_get_season(&self, season_id: SeasonId) -> Season {
+        self.seasons.get(&season_id).expect("Season not found")
+    }
+
+    /// Seasons don't overlap, the running one is the latest started one if it didn't end
+    pub(crate) fn internal_current_season_id(&self, ts: Timestamp) -> Option<SeasonId> {
+        (0..self.next_season_id)
+            .rev()
+            .find(|season_id| self.internal_get_season(*season_id).start <= ts)
+            .filter(|season_id| self.internal_get_season(*season_id).is_active(ts))
+    }
+
+    /// Counts a settled game in the season stats of both players
+    pub(crate) fn internal_record_season_game(&mut self, players: &(AccountId, AccountId), winner: Option<&AccountId>) {
+        let season_id = match self.internal_current_season_id(nano_to_sec(env::block_timestamp())) {
+            Some(season_id) => season_id,
+            None => return,
+        };
+        for account_id in [&players.0, &players.1] {
+            let key = (season_id, account_id.clone());
+            let mut stats = self.season_stats.get(&key).unwrap_or_default();
+            let old_score = (stats.games_num > 0).then(|| stats.victories_num.into());
+            stats.games_num += 1;
+            if winner == Some(account_id) {
+                stats.victories_num += 1;
+            }
+            self.season_stats.insert(&key, &stats);
+            self.internal_update_leaderboard(Leaderboard::Season(season_id), account_id, old_score, Some(stats.victories_num.into()));
+        }
+    }
+
+    /// Adds the `fee_share` of `fee` to the prize pool of the running season
+    pub(crate) fn internal_fund_season(&mut self, fee: Balance) {
+        let season_id = match self.internal_current_season_id(nano_to_sec(env::block_timestamp())) {
+            Some(season_id) => season_id,
+            None => return,
+        };
+        let mut season = self.internal_get_season(season_id);
+        season.prize_pool += fee
+            .checked_div(BASIS_P.into())
+            .unwrap_or(0)
+            .checked_mul(season.fee_share as u128)
+            .expect("multiplication overflow");
+        self.seasons.insert(&season_id, &season);
+    }
+}
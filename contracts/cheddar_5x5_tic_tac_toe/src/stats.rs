@@ -9,9 +9,12 @@ pub enum UpdateStatsAction {
     AddTotalReward,
     AddAffiliateReward,
     AddPenaltyGame,
+    AddPlayedMatch,
+    AddWonMatch,
 }
 
-#[derive(BorshSerialize, BorshDeserialize)]
+/// Stats written by the 1.0.0 contract end after `total_affiliate_reward`, see `BorshDeserialize`
+#[derive(BorshSerialize)]
 pub struct Stats {
     pub referrer_id: Option<AccountId>,
     pub affiliates: UnorderedSet<AffiliateId>,
@@ -20,6 +23,38 @@ pub struct Stats {
     pub penalties_num: u64,
     pub total_reward: Balance,
     pub total_affiliate_reward: Balance,
+    pub matches_num: u64,
+    pub match_victories_num: u64,
+}
+
+impl BorshDeserialize for Stats {
+    /// The match counters were added after 1.0.0, they are 0 in stats written before
+    /// and are stored with the next update of the account
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let referrer_id = BorshDeserialize::deserialize(buf)?;
+        let affiliates = BorshDeserialize::deserialize(buf)?;
+        let games_num = BorshDeserialize::deserialize(buf)?;
+        let victories_num = BorshDeserialize::deserialize(buf)?;
+        let penalties_num = BorshDeserialize::deserialize(buf)?;
+        let total_reward = BorshDeserialize::deserialize(buf)?;
+        let total_affiliate_reward = BorshDeserialize::deserialize(buf)?;
+        let (matches_num, match_victories_num) = if buf.is_empty() {
+            (0, 0)
+        } else {
+            (BorshDeserialize::deserialize(buf)?, BorshDeserialize::deserialize(buf)?)
+        };
+        Ok(Self {
+            referrer_id,
+            affiliates,
+            games_num,
+            victories_num,
+            penalties_num,
+            total_reward,
+            total_affiliate_reward,
+            matches_num,
+            match_victories_num,
+        })
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -39,6 +74,8 @@ pub struct StatsView {
     pub penalties_num: u64,
     pub total_reward: Balance,
     pub total_affiliate_reward: Balance,
+    pub matches_played: u64,
+    pub match_victories_num: u64,
 }
 #[near_bindgen]
 impl Contract {
@@ -50,7 +87,9 @@ impl Contract {
             victories_num: stats.victories_num, 
             penalties_num: stats.penalties_num, 
             total_reward: stats.total_reward, 
-            total_affiliate_reward: stats.total_affiliate_reward 
+            total_affiliate_reward: stats.total_affiliate_reward,
+            matches_played: stats.matches_num,
+            match_victories_num: stats.match_victories_num,
         }
     }
     pub fn get_user_penalties(&self, account_id: &AccountId) -> UserPenalties {
@@ -61,9 +100,12 @@ impl Contract {
         self.stats.len() as _
     }
     /// return vector of accounts played game even once upon a time
-    pub fn get_accounts_played(&self) -> Vec<AccountId> {
+    pub fn get_accounts_played(&self, from_index: u64, limit: u64) -> Vec<AccountId> {
         self.stats
-            .keys()
+            .keys_as_vector()
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
             .collect()
     }
 }
@@ -78,6 +120,8 @@ impl Stats {
             penalties_num: 0,
             total_reward: 0,
             total_affiliate_reward: 0,
+            matches_num: 0,
+            match_victories_num: 0,
         }
     }
 }
@@ -97,6 +141,7 @@ impl Contract {
         balance: Option<Balance>
     ) {
             let mut stats = self.internal_get_stats(account_id);
+            let old_scores = Leaderboard::STATS.map(|board| stats.leaderboard_score(board));
             match action {
                 UpdateStatsAction::AddPlayedGame => {
                     stats.games_num += 1;
@@ -125,7 +170,16 @@ impl Contract {
                 UpdateStatsAction::AddPenaltyGame => {
                     stats.penalties_num += 1;
                 },
+                UpdateStatsAction::AddPlayedMatch => {
+                    stats.matches_num += 1;
+                },
+                UpdateStatsAction::AddWonMatch => {
+                    stats.match_victories_num += 1;
+                },
             }
             self.stats.insert(account_id, &stats);
+            for (board, old_score) in Leaderboard::STATS.iter().zip(old_scores.iter()) {
+                self.internal_update_leaderboard(*board, account_id, *old_score, stats.leaderboard_score(*board));
+            }
     }
 }
\ No newline at end of file
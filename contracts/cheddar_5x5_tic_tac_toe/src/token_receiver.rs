@@ -13,6 +13,14 @@ pub struct GameConfigArgs {
     pub referrer_id: Option<AccountId>,
 }
 
+/// `ft_transfer_call` msg paying the entry fee of a tournament,
+/// transfers with any other msg are deposited to the vault
+#[derive(Deserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TournamentEntryArgs {
+    pub tournament_id: TournamentId,
+}
+
 /// FT Receiver
 /// token deposits are done through NEP-141 ft_transfer_call to the contract.
 #[near_bindgen]
@@ -40,6 +48,10 @@ impl FungibleTokenReceiver for Contract {
             "Only cheddar {} is accepted for deposits",
             self.cheddar
         );
+        if let Ok(args) = near_sdk::serde_json::from_str::<TournamentEntryArgs>(&msg) {
+            let refund = self.internal_register_tournament_player(args.tournament_id, &sender_id, amount.0);
+            return PromiseOrValue::Value(refund.into());
+        }
         assert!(
             amount.0 >= self.min_deposit,
             "deposited amount must be more than {}",
@@ -0,0 +1,374 @@
+use std::cmp::Reverse;
+
+use crate::*;
+
+pub type TournamentId = u64;
+
+/// Allowed number of players in a tournament
+pub(crate) const TOURNAMENT_SIZES: [u8; 3] = [8, 16, 32];
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub enum TournamentState {
+    /// players register until `start`
+    Registration,
+    Active,
+    Finished,
+    /// the tournament wasn't full at `start`, entry fees were refunded
+    Cancelled,
+}
+
+/// Tournament settings chosen by the organizer
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TournamentConfig {
+    /// number of players, one of `TOURNAMENT_SIZES`
+    pub size: u8,
+    /// CHEDDAR paid by each player to register
+    pub entry_fee: U128,
+    /// timestamp in seconds, the registration closes and the first round starts then
+    pub start: Timestamp,
+    /// share of the prize pool of each place in `BASIS_P`, the first place first
+    pub prizes: Vec<u16>,
+    /// `DEFAULT_RULES` by default
+    pub rules: Option<MnkRules>,
+    pub variant: Option<Variant>,
+    pub opening: Option<Opening>,
+    pub time_control: Option<TimeControl>,
+}
+
+/// Game of a tournament round between two seeds
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct TournamentGame {
+    pub game_id: GameId,
+    /// 1 for the first round
+    pub round: u8,
+    /// seeds of both players, indexes in `Tournament.players`
+    pub seeds: (u8, u8),
+    /// replay of a tied game with swapped pieces
+    pub replay: bool,
+    /// seed advancing to the next round, `None` while the game runs or after a tie which is replayed
+    pub winner: Option<u8>,
+}
+
+/// Single-elimination tournament, players pay the entry fee with `ft_transfer_call`.
+/// The games of each round are created once the previous round is over, a tied game is
+/// replayed once with swapped pieces and the better seed advances if the replay ties too.
+/// The better seed of a game moves first in odd rounds, the other one in even rounds.
+/// Registered players can't play other games until they are eliminated.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct Tournament {
+    pub size: u8,
+    pub entry_fee: U128,
+    /// timestamp in seconds
+    pub start: Timestamp,
+    pub prizes: Vec<u16>,
+    pub rules: MnkRules,
+    pub variant: Variant,
+    pub opening: Opening,
+    pub time_control: Option<TimeControl>,
+    /// players in registration order, ordered by seed from the best one once the tournament starts
+    pub players: Vec<AccountId>,
+    pub state: TournamentState,
+    /// entry fees without the service fee, set once the tournament starts
+    pub prize_pool: U128,
+    /// current round, 0 before the tournament starts
+    pub round: u8,
+    /// seeds still in the tournament in bracket order, neighbours play each other
+    pub bracket: Vec<u8>,
+    pub games: Vec<TournamentGame>,
+    /// players from the champion, ordered by the round they were eliminated in and then by seed
+    pub standings: Vec<AccountId>,
+}
+
+/// Seeds in bracket order for `size` players, the two best seeds can only meet in the final
+pub(crate) fn bracket_order(size: u8) -> Vec<u8> {
+    let mut order = vec![0];
+    while order.len() < size as usize {
+        let last_seed = order.len() as u8 * 2 - 1;
+        order = order.iter().flat_map(|seed| [*seed, last_seed - seed]).collect();
+    }
+    order
+}
+
+impl Tournament {
+    fn seed(&self, account_id: &AccountId) -> u8 {
+        self.players
+            .iter()
+            .position(|player| player == account_id)
+            .unwrap_or_else(|| panic!("Account @{} doesn't play in this tournament", account_id)) as u8
+    }
+
+    fn player(&self, seed: u8) -> AccountId {
+        self.players[seed as usize].clone()
+    }
+
+    /// Seeds which won their game in the current round
+    fn round_winners(&self) -> Vec<u8> {
+        self.games
+            .iter()
+            .filter(|game| game.round == self.round)
+            .filter_map(|game| game.winner)
+            .collect()
+    }
+
+    fn final_standings(&self) -> Vec<AccountId> {
+        let mut eliminated: Vec<(u8, u8)> = self
+            .games
+            .iter()
+            .filter_map(|game| {
+                let winner = game.winner?;
+                let loser = if winner == game.seeds.0 { game.seeds.1 } else { game.seeds.0 };
+                Some((game.round, loser))
+            })
+            .collect();
+        eliminated.sort_by_key(|(round, seed)| (Reverse(*round), *seed));
+        self.bracket
+            .iter()
+            .chain(eliminated.iter().map(|(_, seed)| seed))
+            .map(|seed| self.player(*seed))
+            .collect()
+    }
+
+    /// Prize of the place `rank`, 0 for the champion
+    pub fn prize(&self, rank: usize) -> Balance {
+        self.prize_pool
+            .0
+            .checked_div(BASIS_P.into())
+            .unwrap_or(0)
+            .checked_mul(self.prizes[rank] as u128)
+            .expect("multiplication overflow")
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Schedules a tournament, players register until `config.start`
+    #[private]
+    pub fn create_tournament(&mut self, config: TournamentConfig) -> TournamentId {
+        assert!(config.start > nano_to_sec(env::block_timestamp()), "Tournament must start in the future");
+        assert!(config.entry_fee.0 >= MIN_BET_CHEDDAR, "Entry fee is too small. Required at least: {}", MIN_BET_CHEDDAR);
+        let rules = config.rules.unwrap_or(DEFAULT_RULES);
+        validate_rules(&rules);
+        validate_tournament(config.size, &config.prizes);
+        if let Some(time_control) = &config.time_control {
+            validate_time_control(time_control);
+        }
+        let tournament_id = self.next_tournament_id;
+        self.tournaments.insert(&tournament_id, &Tournament {
+            size: config.size,
+            entry_fee: config.entry_fee,
+            start: config.start,
+            prizes: config.prizes,
+            rules,
+            variant: config.variant.unwrap_or_default(),
+            opening: config.opening.unwrap_or_default(),
+            time_control: config.time_control,
+            players: Vec::with_capacity(config.size as usize),
+            state: TournamentState::Registration,
+            prize_pool: U128(0),
+            round: 0,
+            bracket: vec![],
+            games: vec![],
+            standings: vec![],
+        });
+        self.next_tournament_id += 1;
+        tournament_id
+    }
+
+    /// Closes the registration once the tournament starts. Seeds the players by rating and
+    /// starts the first round, or cancels the tournament and refunds the entry fees if it isn't full.
+    /// Returns the games of the first round.
+    pub fn start_tournament(&mut self, tournament_id: TournamentId) -> Vec<GameId> {
+        let mut tournament = self.get_tournament(tournament_id);
+        assert!(matches!(tournament.state, TournamentState::Registration), "Tournament is already started");
+        assert!(nano_to_sec(env::block_timestamp()) >= tournament.start, "Tournament hasn't started yet");
+
+        if tournament.players.len() < tournament.size as usize {
+            log!("Tournament {} is cancelled with {} of {} players", tournament_id, tournament.players.len(), tournament.size);
+            for account_id in tournament.players.iter() {
+                self.internal_transfer(account_id, tournament.entry_fee.0);
+                self.tournament_players.remove(account_id);
+            }
+            tournament.state = TournamentState::Cancelled;
+            self.tournaments.insert(&tournament_id, &tournament);
+            return vec![];
+        }
+
+        // the sort is stable, players with the same rating keep the registration order
+        let pool = RatingPool { rules: tournament.rules, variant: tournament.variant };
+        let mut players = std::mem::take(&mut tournament.players);
+        players.sort_by_key(|account_id| Reverse(rating_score(&self.internal_get_rating(account_id, pool).rating)));
+        tournament.players = players;
+
+        let entry_fees = tournament.entry_fee.0 * tournament.size as u128;
+        let fees_amount = entry_fees
+            .checked_div(BASIS_P.into())
+            .unwrap_or(0)
+            .checked_mul(self.service_fee as u128)
+            .expect("multiplication overflow");
+        self.internal_fund_season(fees_amount);
+        tournament.prize_pool = (entry_fees - fees_amount).into();
+        tournament.state = TournamentState::Active;
+        tournament.bracket = bracket_order(tournament.size);
+        let game_ids = self.internal_start_tournament_round(tournament_id, &mut tournament);
+        self.tournaments.insert(&tournament_id, &tournament);
+        game_ids
+    }
+
+    pub fn get_tournament(&self, tournament_id: TournamentId) -> Tournament {
+        self.tournaments.get(&tournament_id).expect("Tournament not found")
+    }
+
+    pub fn get_tournaments(&self, from_index: u64, limit: u64) -> Vec<(TournamentId, Tournament)> {
+        (from_index..self.next_tournament_id)
+            .take(limit as usize)
+            .map(|tournament_id| (tournament_id, self.get_tournament(tournament_id)))
+            .collect()
+    }
+}
+
+impl Contract {
+    /// Registers `account_id` paying `amount` of CHEDDAR, returns the amount above the entry fee
+    pub(crate) fn internal_register_tournament_player(
+        &mut self,
+        tournament_id: TournamentId,
+        account_id: &AccountId,
+        amount: Balance,
+    ) -> Balance {
+        let mut tournament = self.get_tournament(tournament_id);
+        assert!(
+            matches!(tournament.state, TournamentState::Registration) && nano_to_sec(env::block_timestamp()) < tournament.start,
+            "Tournament registration is closed"
+        );
+        assert!(tournament.players.len() < tournament.size as usize, "Tournament is full");
+        assert!(!tournament.players.contains(account_id), "Already registered in the tournament");
+        assert!(self.available_players.get(account_id).is_none(), "Already in the waiting list the list");
+        assert!(self.internal_queue_bracket(account_id).is_none(), "Already in the queue");
+        self.internal_check_player_available(account_id);
+        // prizes are paid to the vault
+        assert!(self.is_user_registered(account_id), "Player @{} is not registered", account_id);
+        assert!(amount >= tournament.entry_fee.0, "Entry fee is {}", tournament.entry_fee.0);
+        tournament.players.push(account_id.clone());
+        self.tournaments.insert(&tournament_id, &tournament);
+        self.tournament_players.insert(account_id, &tournament_id);
+        log!("@{} registered in tournament {} ({} of {})", account_id, tournament_id, tournament.players.len(), tournament.size);
+        amount - tournament.entry_fee.0
+    }
+
+    /// Starts a game for each pair of neighbours in the bracket
+    fn internal_start_tournament_round(&mut self, tournament_id: TournamentId, tournament: &mut Tournament) -> Vec<GameId> {
+        tournament.round += 1;
+        log!("Tournament {}: round {} started", tournament_id, tournament.round);
+        let pairs: Vec<(u8, u8)> = tournament.bracket.chunks(2).map(|pair| (pair[0], pair[1])).collect();
+        pairs
+            .into_iter()
+            .map(|seeds| {
+                // the better seed moves first in odd rounds
+                let first_player = match tournament.round % 2 {
+                    1 => seeds.0.min(seeds.1),
+                    _ => seeds.0.max(seeds.1),
+                };
+                self.internal_start_tournament_game(tournament_id, tournament, seeds, first_player, false)
+            })
+            .collect()
+    }
+
+    fn internal_start_tournament_game(
+        &mut self,
+        tournament_id: TournamentId,
+        tournament: &mut Tournament,
+        seeds: (u8, u8),
+        first_player: u8,
+        replay: bool,
+    ) -> GameId {
+        let game_id = self.next_game_id;
+        self.next_game_id += 1;
+        let second_player = if first_player == seeds.0 { seeds.1 } else { seeds.0 };
+        let (player_1, player_2) = (tournament.player(first_player), tournament.player(second_player));
+        let mut game = Game::create_game(
+            player_1.clone(),
+            player_2.clone(),
+            GameDeposit { balance: U128(0) },
+            tournament.rules,
+            tournament.variant,
+            tournament.opening,
+        );
+        game.tournament_id = Some(tournament_id);
+        game.set_time_control(tournament.time_control);
+        game.change_state(GameState::Active);
+        self.games.insert(&game_id, &game);
+        tournament.games.push(TournamentGame { game_id, round: tournament.round, seeds, replay, winner: None });
+        self.internal_update_stats(&player_1, UpdateStatsAction::AddPlayedGame, None, None);
+        self.internal_update_stats(&player_2, UpdateStatsAction::AddPlayedGame, None, None);
+        game_id
+    }
+
+    /// Records the result of a tournament game, replays a first tie and starts the next round
+    /// once all games of the round are decided. The prizes are paid after the final.
+    pub(crate) fn internal_record_tournament_game(
+        &mut self,
+        tournament_id: TournamentId,
+        game_id: &GameId,
+        players: &(AccountId, AccountId),
+        winner: Option<&AccountId>,
+    ) -> U128 {
+        let mut tournament = self.get_tournament(tournament_id);
+        let index = tournament
+            .games
+            .iter()
+            .position(|game| game.game_id == *game_id)
+            .expect("Game isn't part of the tournament");
+        let seeds = tournament.games[index].seeds;
+        let winner_seed = match winner {
+            Some(winner_id) => {
+                self.internal_update_stats(winner_id, UpdateStatsAction::AddWonGame, None, None);
+                tournament.seed(winner_id)
+            }
+            None if !tournament.games[index].replay => {
+                log!("Tournament {}: game {} is tied, it's replayed with swapped pieces", tournament_id, game_id);
+                let first_player = tournament.seed(&players.1);
+                self.internal_start_tournament_game(tournament_id, &mut tournament, seeds, first_player, true);
+                self.tournaments.insert(&tournament_id, &tournament);
+                return U128(0);
+            }
+            // the replay is tied too
+            None => seeds.0.min(seeds.1),
+        };
+        tournament.games[index].winner = Some(winner_seed);
+        log!("Tournament {}: @{} advances", tournament_id, tournament.player(winner_seed));
+        let loser_seed = if winner_seed == seeds.0 { seeds.1 } else { seeds.0 };
+        self.tournament_players.remove(&tournament.player(loser_seed));
+
+        let winners = tournament.round_winners();
+        if winners.len() == tournament.bracket.len() / 2 {
+            tournament.bracket.retain(|seed| winners.contains(seed));
+            if tournament.bracket.len() == 1 {
+                self.internal_finish_tournament(tournament_id, &mut tournament);
+            } else {
+                self.internal_start_tournament_round(tournament_id, &mut tournament);
+            }
+        }
+        self.tournaments.insert(&tournament_id, &tournament);
+        U128(0)
+    }
+
+    /// Pays the prize of each place to the vault of the player
+    fn internal_finish_tournament(&mut self, tournament_id: TournamentId, tournament: &mut Tournament) {
+        tournament.standings = tournament.final_standings();
+        tournament.state = TournamentState::Finished;
+        log!("Tournament {} is over, @{} is the champion", tournament_id, tournament.standings[0]);
+        self.tournament_players.remove(&tournament.standings[0]);
+        for rank in 0..tournament.prizes.len() {
+            let prize = tournament.prize(rank);
+            let account_id = &tournament.standings[rank];
+            self.internal_transfer(account_id, prize);
+            self.internal_update_stats(account_id, UpdateStatsAction::AddTotalReward, None, Some(prize));
+        }
+    }
+}
@@ -10,6 +10,8 @@ pub(crate) const GAS_FOR_FT_TRANSFER: Gas = Gas(Gas::ONE_TERA.0 * 10);
 pub(crate) const MAX_FEES: u16 = 500; // 5%
 pub(crate) const BASIS_P: u16 = 10000; // 100%
 pub(crate) const TIMEOUT_WIN: Duration = 5 * 60; // 5 minutes timeout in seconds
+pub(crate) const REVEAL_TIMEOUT: Duration = 10 * 60; // 10 minutes to reveal the secret in seconds
+pub(crate) const CHALLENGE_PERIOD: Duration = 10 * 60; // 10 minutes to answer a channel state in seconds
 pub(crate) const MIN_DEPOSIT_CHEDDAR: Balance =  50;
 pub(crate) const MIN_AVAILABLE_FOR: Duration = 1 * 60; // 1 minute 
 pub(crate) const MAX_AVAILABLE_FOR: Duration = 60 * 60; // 1 hour 
@@ -18,9 +20,19 @@ pub(crate) const MIN_BET_CHEDDAR: Balance = 50;
 pub(crate) type GameId = u64;
 pub(crate) type AffiliateId = AccountId;
 
-/// This constant can be used to set the board size
-pub(crate) const BOARD_SIZE: u8 = 25;
-pub(crate) const MAX_NUM_TURNS: u64 = BOARD_SIZE as u64 * BOARD_SIZE as u64;
+/// Board dimensions and win length used when the challenger doesn't pick any
+pub(crate) const DEFAULT_RULES: MnkRules = MnkRules::FIVE_IN_A_ROW;
+/// Limits for the board size and win length picked in `make_available`
+pub(crate) const MIN_BOARD_SIZE: u8 = 5;
+pub(crate) const MAX_BOARD_SIZE: u8 = BitBoard::SIZE;
+pub(crate) const MIN_WIN_LENGTH: u8 = 4;
+pub(crate) const MAX_WIN_LENGTH: u8 = 6;
+/// Max number of takebacks each player can agree on in `make_available`
+pub(crate) const MAX_TAKEBACKS: u8 = 3;
+/// Limits for the chess-clock time control picked in `make_available`, in seconds
+pub(crate) const MIN_CLOCK_TIME: Duration = 60;
+pub(crate) const MAX_CLOCK_INCREMENT: Duration = 60;
+pub(crate) const MAX_NUM_TURNS: u64 = MAX_BOARD_SIZE as u64 * MAX_BOARD_SIZE as u64;
 
 /// pesimistic assumption of the storage_deposit needed for every user 
 pub(crate) const STORAGE_COST_PER_USER: Balance = 200_000_000_000_000_000_000_000; // 0.2 NEAR in YOCTONEAR
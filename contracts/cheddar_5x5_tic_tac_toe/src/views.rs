@@ -1,12 +1,7 @@
 use crate::*;
 use std::collections::HashMap;
 
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Debug)]
-#[serde(crate = "near_sdk::serde")]
-pub enum GameResult {
-    Win(AccountId),
-    Tie,
-}
+pub use tic_tac_toe_rules::{GameResult, ResultReason};
 
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
@@ -41,17 +36,47 @@ pub struct GameView {
     pub initiated_at: Timestamp,
     pub last_turn_timestamp: Timestamp,
     pub current_duration: Duration,
+    /// board size and win length of the game
+    pub rules: MnkRules,
+    pub variant: Variant,
+    pub opening: Opening,
+    /// step of the opening the current player has to make, `None` after the opening
+    pub opening_phase: Option<OpeningPhase>,
+    pub match_id: Option<MatchId>,
+    pub tournament_id: Option<TournamentId>,
+    /// player offering a draw to the opponent
+    pub draw_offer: Option<AccountId>,
+    /// takebacks left for `player1` and `player2`
+    pub takebacks_left: (u8, u8),
+    /// player asking to take back their last move
+    pub takeback_request: Option<AccountId>,
+    /// clock time left of `player1` and `player2` in seconds
+    pub time_left: Option<(Duration, Duration)>,
+    /// commitments and revealed secrets while the first player is drawn
+    pub coin_toss: Option<CoinToss>,
+    /// channel keys and the deadline to answer the submitted state
+    pub channel: Option<Channel>,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct GameLimitedView {
     pub game_result: GameResult,
+    /// rule that decided `game_result`
+    pub reason: ResultReason,
     pub player1: AccountId,
     pub player2: AccountId,
     pub reward_or_tie_refund: GameDeposit,
     pub tiles: Tiles,
     pub last_move: Option<(Coords, Piece)>,
+    pub rules: MnkRules,
+    pub match_id: Option<MatchId>,
+    pub tournament_id: Option<TournamentId>,
+    /// all moves of the game in order, see `get_game_moves`
+    pub moves: Vec<MoveRecord>,
+    pub variant: Variant,
+    /// number of opening stones at the start of `moves`
+    pub opening_stones: u8,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
@@ -61,6 +86,30 @@ pub struct RangedPlayersView {
     pub player2: AccountId,
 }
 
+impl GameLimitedView {
+    /// Record of a finished `game` kept in `Contract.stored_games`
+    pub fn finished(game: &Game, game_result: GameResult, reason: ResultReason, balance: U128) -> Self {
+        Self {
+            game_result,
+            reason,
+            player1: game.players.0.clone(),
+            player2: game.players.1.clone(),
+            reward_or_tie_refund: GameDeposit { balance },
+            tiles: game.to_tiles(),
+            last_move: game
+                .last_move
+                .clone()
+                .and_then(|coords| game.board.piece(&coords).map(|piece| (coords, piece))),
+            rules: game.rules,
+            match_id: game.match_id,
+            tournament_id: game.tournament_id,
+            moves: game.moves.clone(),
+            variant: game.variant,
+            opening_stones: game.opening_stones,
+        }
+    }
+}
+
 impl From<&Game> for RangedPlayersView {
     fn from(g: &Game) -> Self {
         let current_player = g.current_player_account_id();
@@ -86,13 +135,25 @@ impl From<&Game> for GameView {
         Self {
             player1,
             player2,
-            game_status: g.game_state.clone(),
+            game_status: g.game_state,
             current_player,
             total_bet: g.reward(),
             tiles: g.to_tiles(),
             initiated_at: g.initiated_at,
             last_turn_timestamp: g.last_turn_timestamp,
             current_duration: g.duration,
+            rules: g.rules,
+            variant: g.variant,
+            opening: g.opening,
+            opening_phase: g.opening_phase,
+            match_id: g.match_id,
+            tournament_id: g.tournament_id,
+            draw_offer: g.draw_offer.clone(),
+            takebacks_left: g.takebacks_left,
+            takeback_request: g.takeback_request.clone(),
+            time_left: g.time_left(nano_to_sec(env::block_timestamp())),
+            coin_toss: g.coin_toss.clone(),
+            channel: g.channel.clone(),
         }
     }
 }
@@ -105,7 +166,7 @@ impl Contract {
             .iter()
             .map(|(game_id, game)| (game_id, GameView::from(&game)))
             .collect();
-        let available_players = self.get_available_players();
+        let available_players = self.get_available_players(None);
 
         ContractParams {
             games,
@@ -120,6 +181,19 @@ impl Contract {
         self.stored_games.get(game_id).expect("Game not found")
     }
 
+    /// Up to `limit` moves of an active or a stored game starting at `from_index`
+    pub fn get_game_moves(&self, game_id: &GameId, from_index: u64, limit: u64) -> Vec<MoveRecord> {
+        let moves = match self.games.get(game_id) {
+            Some(game) => game.moves,
+            None => self.get_game(game_id).moves,
+        };
+        moves
+            .into_iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+
     pub fn get_ordered_players(&self, game_id: &GameId) -> RangedPlayersView {
         self.games
             .get(game_id)
@@ -148,10 +222,19 @@ impl Contract {
         self.min_deposit.into()
     }
 
-    pub fn get_available_players(&self) -> Vec<(AccountId, GameConfigView)> {
+    /// Offers waiting for an opponent. With `account_id` only the offers whose
+    /// rating bounds accept the rating of `account_id` are listed.
+    pub fn get_available_players(&self, account_id: Option<AccountId>) -> Vec<(AccountId, GameConfigView)> {
         self.available_players
             .to_vec()
             .iter()
+            .filter(|(_, game_config)| match &account_id {
+                Some(account_id) => {
+                    let rating = self.internal_get_rating(account_id, RatingPool::from(game_config)).rating;
+                    game_config.accepts_rating(&rating)
+                }
+                None => true,
+            })
             .map(|(acc, game_config)| (acc.clone(), GameConfigView::from(game_config)))
             .collect()
     }
@@ -164,20 +247,18 @@ impl Contract {
             .collect()
     }
 
-    pub fn get_penalty_users(&self) -> Vec<(AccountId, UserPenalties)> {
-        let accounts_played = self.get_accounts_played();
-        assert_eq!(accounts_played.len() as u32, self.get_total_stats_num());
-
-        let result: Vec<(_, _)> = accounts_played
-            .iter()
-            .map(|acc| {
-                let penalties_by_acc = self.get_user_penalties(acc);
-                (acc.clone(), penalties_by_acc)
-            })
-            .filter(|(_, penalties_by_acc)| penalties_by_acc.penalties_num > 0)
-            .collect();
-
-        result
+    /// accounts with penalty games, the most penalized first, after `from_account_id`,
+    /// the last account of the previous page
+    pub fn get_penalty_users(&self, from_account_id: Option<AccountId>, limit: u64) -> Vec<(AccountId, UserPenalties)> {
+        let from = from_account_id.map(|account_id| LeaderboardEntry {
+            rank: 0,
+            score: u128::from(self.get_user_penalties(&account_id).penalties_num).into(),
+            account_id,
+        });
+        self.get_leaderboard(Leaderboard::Penalties, from, limit)
+            .into_iter()
+            .map(|entry| (entry.account_id, UserPenalties { penalties_num: entry.score.0 as u64 }))
+            .collect()
     }
 
 }
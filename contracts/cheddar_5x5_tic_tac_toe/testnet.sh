@@ -139,10 +139,10 @@ near view $TICTACTOE get_stats '{"account_id": "'$USER3'"}'
 near view $TICTACTOE get_stats '{"account_id": "'$USER4'"}'
 
 near view $TICTACTOE get_total_stats_num '' 
-near view $TICTACTOE get_accounts_played ''
+near view $TICTACTOE get_accounts_played '{"from_index": 0, "limit": 100}'
 near view $TICTACTOE get_user_penalties '{"account_id":"'$USER1'"}'
 near view $TICTACTOE get_user_penalties '{"account_id":"'$USER2'"}'
-near view $TICTACTOE get_penalty_users ''
+near view $TICTACTOE get_penalty_users '{"from_index": 0, "limit": 100}'
 near view $TICTACTOE get_game '{"game_id":0}'
 
 echo TIC_TAC_TOE is ${TIC_TAC_TOE}
@@ -0,0 +1,235 @@
+use crate::*;
+
+/// Bits in one row of a `BitMask`. The last column is never set,
+/// so shifted lines can't wrap around to the next row.
+const STRIDE: usize = BitBoard::SIZE as usize + 1;
+const WORDS: usize = (STRIDE * BitBoard::SIZE as usize).div_ceil(64);
+
+/// One bit per tile of a board up to `BitBoard::SIZE` x `BitBoard::SIZE`,
+/// stored row by row with an empty guard column at the end of each row
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct BitMask([u64; WORDS]);
+
+impl BitMask {
+    fn index(coords: &Coords) -> Option<usize> {
+        if coords.x < BitBoard::SIZE && coords.y < BitBoard::SIZE {
+            Some(coords.y as usize * STRIDE + coords.x as usize)
+        } else {
+            None
+        }
+    }
+
+    fn get(&self, index: usize) -> bool {
+        self.0[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    fn set(&mut self, index: usize) {
+        self.0[index / 64] |= 1 << (index % 64);
+    }
+
+    fn clear(&mut self, index: usize) {
+        self.0[index / 64] &= !(1 << (index % 64));
+    }
+
+    fn count_ones(&self) -> u64 {
+        self.0.iter().map(|word| u64::from(word.count_ones())).sum()
+    }
+
+    /// Moves every bit `n` positions down: bit `i` of the result is bit `i + n` of `self`
+    fn shr(&self, n: usize) -> BitMask {
+        let (words, bits) = (n / 64, n % 64);
+        let mut res = [0; WORDS];
+        for (i, word) in res.iter_mut().enumerate() {
+            let lo = self.0.get(i + words).copied().unwrap_or(0);
+            let hi = self.0.get(i + words + 1).copied().unwrap_or(0);
+            *word = if bits == 0 { lo } else { (lo >> bits) | (hi << (64 - bits)) };
+        }
+        BitMask(res)
+    }
+
+    fn and(&self, other: &BitMask) -> BitMask {
+        let mut res = self.0;
+        for (word, other) in res.iter_mut().zip(other.0.iter()) {
+            *word &= other;
+        }
+        BitMask(res)
+    }
+}
+
+/// Board stored inline as two bit masks, one for each piece.
+/// Lines are found with shifts and ANDs instead of probing tile by tile.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct BitBoard {
+    o: BitMask,
+    x: BitMask,
+}
+
+impl BitBoard {
+    /// max number of rows and columns
+    pub const SIZE: u8 = 25;
+
+    fn mask(&self, piece: Piece) -> &BitMask {
+        match piece {
+            Piece::O => &self.o,
+            Piece::X => &self.x,
+        }
+    }
+
+    /// Places `piece` on `coords`. Moves must be validated with
+    /// `Ruleset::check_move` first: panics if `coords` is out of the board.
+    pub fn insert(&mut self, coords: &Coords, piece: Piece) {
+        let index = BitMask::index(coords)
+            .unwrap_or_else(|| panic!("({}, {}) is out of the board", coords.x, coords.y));
+        match piece {
+            Piece::O => self.o.set(index),
+            Piece::X => self.x.set(index),
+        }
+    }
+
+    /// Removes the piece on `coords` and returns it
+    pub fn remove(&mut self, coords: &Coords) -> Option<Piece> {
+        let piece = self.piece(coords)?;
+        let index = BitMask::index(coords)?;
+        match piece {
+            Piece::O => self.o.clear(index),
+            Piece::X => self.x.clear(index),
+        }
+        Some(piece)
+    }
+
+    /// All placed pieces, row by row
+    pub fn iter(&self) -> impl Iterator<Item = (Coords, Piece)> + '_ {
+        (0..BitBoard::SIZE)
+            .flat_map(|y| (0..BitBoard::SIZE).map(move |x| Coords { x, y }))
+            .filter_map(move |coords| self.piece(&coords).map(|piece| (coords, piece)))
+    }
+}
+
+impl Grid for BitBoard {
+    fn piece(&self, coords: &Coords) -> Option<Piece> {
+        let index = BitMask::index(coords)?;
+        if self.o.get(index) {
+            Some(Piece::O)
+        } else if self.x.get(index) {
+            Some(Piece::X)
+        } else {
+            None
+        }
+    }
+
+    fn pieces_num(&self) -> u64 {
+        self.o.count_ones() + self.x.count_ones()
+    }
+
+    fn has_line(&self, coords: &Coords, piece: Piece, direction: (i8, i8), length: u8) -> bool {
+        let index = match BitMask::index(coords) {
+            Some(index) => index,
+            None => return false,
+        };
+        let mut line = *self.mask(piece);
+        line.set(index);
+        // (1, -1) and (-1, 1) are the same line, shifting always goes towards higher indexes
+        let shift = (isize::from(direction.0) + isize::from(direction.1) * STRIDE as isize).unsigned_abs();
+        // bit `i` of `runs` is set when `length` pieces in a row start at `i`
+        let runs = (1..usize::from(length)).fold(line, |runs, k| runs.and(&line.shr(k * shift)));
+        (0..usize::from(length))
+            .filter_map(|k| index.checked_sub(k * shift))
+            .any(|start| runs.get(start))
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_and_iter() {
+        let mut board = BitBoard::default();
+        board.insert(&Coords { x: 24, y: 0 }, Piece::X);
+        board.insert(&Coords { x: 0, y: 1 }, Piece::O);
+        board.insert(&Coords { x: 24, y: 24 }, Piece::X);
+        assert_eq!(board.piece(&Coords { x: 24, y: 0 }), Some(Piece::X));
+        assert_eq!(board.piece(&Coords { x: 1, y: 0 }), None);
+        assert_eq!(board.piece(&Coords { x: 25, y: 0 }), None);
+        assert_eq!(board.pieces_num(), 3);
+        let pieces: Vec<(Coords, Piece)> = board.iter().collect();
+        assert_eq!(
+            pieces,
+            vec![
+                (Coords { x: 24, y: 0 }, Piece::X),
+                (Coords { x: 0, y: 1 }, Piece::O),
+                (Coords { x: 24, y: 24 }, Piece::X),
+            ]
+        );
+    }
+
+    #[test]
+    fn remove() {
+        let mut board = BitBoard::default();
+        board.insert(&Coords { x: 3, y: 2 }, Piece::O);
+        board.insert(&Coords { x: 4, y: 2 }, Piece::X);
+        assert_eq!(board.remove(&Coords { x: 3, y: 2 }), Some(Piece::O));
+        assert_eq!(board.remove(&Coords { x: 3, y: 2 }), None);
+        assert_eq!(board.remove(&Coords { x: 25, y: 2 }), None);
+        assert_eq!(board.piece(&Coords { x: 4, y: 2 }), Some(Piece::X));
+        assert_eq!(board.pieces_num(), 1);
+    }
+
+    #[test]
+    fn same_lines_as_tile_probing() {
+        let rules = MnkRules::FIVE_IN_A_ROW;
+        let mut board = BitBoard::default();
+        let mut tiles = [[None; 25]; 25];
+        // pseudo random fill, dense enough to have lines in every direction
+        let mut seed: u32 = 7;
+        for y in 0..25u8 {
+            for x in 0..25u8 {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                let piece = match (seed >> 16) % 3 {
+                    0 => Piece::X,
+                    1 => Piece::O,
+                    _ => continue,
+                };
+                board.insert(&Coords { x, y }, piece);
+                tiles[y as usize][x as usize] = Some(piece);
+            }
+        }
+        for y in 0..25u8 {
+            for x in 0..25u8 {
+                let coords = Coords { x, y };
+                for piece in [Piece::X, Piece::O].iter() {
+                    for direction in DIRECTIONS.iter() {
+                        for length in 2..=6 {
+                            assert_eq!(
+                                board.has_line(&coords, *piece, *direction, length),
+                                tiles.has_line(&coords, *piece, *direction, length),
+                                "{:?} {:?} {:?} {}",
+                                coords,
+                                piece,
+                                direction,
+                                length
+                            );
+                        }
+                    }
+                }
+                assert_eq!(
+                    rules.is_winning_move(&board, &coords, Piece::X),
+                    rules.is_winning_move(&tiles, &coords, Piece::X)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn lines_do_not_wrap_around_rows() {
+        let rules = MnkRules::FIVE_IN_A_ROW;
+        let mut board = BitBoard::default();
+        // X X _ on the end of row 3, X X on the start of row 4
+        for coords in [(22, 3), (23, 3), (0, 4), (1, 4)].iter() {
+            board.insert(&Coords { x: coords.0, y: coords.1 }, Piece::X);
+        }
+        assert!(!rules.is_winning_move(&board, &Coords { x: 24, y: 3 }, Piece::X));
+    }
+}
@@ -0,0 +1,89 @@
+use crate::*;
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct Coords {
+    pub x: u8,
+    pub y: u8,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MoveError {
+    /// The game was already over when a move was attempted
+    GameOver,
+    /// The position provided was invalid
+    InvalidPosition { row: u8, col: u8 },
+    /// The tile already contained another piece
+    TileFilled {
+        other_piece: Piece,
+        row: u8,
+        col: u8,
+    },
+    /// The move is not allowed by the game variant
+    Forbidden {
+        kind: ForbiddenMove,
+        row: u8,
+        col: u8,
+    },
+    /// Gravity mode: no empty tile is left in the column
+    ColumnFull { col: u8 },
+    /// Ultimate mode: the sub-board provided was invalid
+    InvalidSubBoard { sub_board: u8 },
+    /// Ultimate mode: the move must be played in the `required` sub-board
+    WrongSubBoard { sub_board: u8, required: u8 },
+    /// Ultimate mode: the sub-board already has a winner or is full
+    SubBoardFinished { sub_board: u8 },
+}
+
+/// Read access to the pieces placed on a board.
+/// Implemented by the contracts on top of their own board storage.
+pub trait Grid {
+    /// Piece placed on `coords`, `None` for an empty tile or a position outside of the grid
+    fn piece(&self, coords: &Coords) -> Option<Piece>;
+    /// Number of pieces placed on the board
+    fn pieces_num(&self) -> u64;
+
+    /// Number of consecutive `piece`s next to `coords` going in `direction`.
+    /// `coords` itself isn't counted and counting stops at `limit`.
+    fn count_direction(&self, coords: &Coords, piece: Piece, direction: (i8, i8), limit: u16) -> u16 {
+        let (mut x, mut y) = (i16::from(coords.x), i16::from(coords.y));
+        let mut counter = 0;
+        while counter < limit {
+            x += i16::from(direction.0);
+            y += i16::from(direction.1);
+            if x < 0 || y < 0 || x > i16::from(u8::MAX) || y > i16::from(u8::MAX) {
+                break;
+            }
+            if self.piece(&Coords { x: x as u8, y: y as u8 }) != Some(piece) {
+                break;
+            }
+            counter += 1;
+        }
+        counter
+    }
+
+    /// Returns true if there are at least `length` `piece`s in a row going through
+    /// `coords` along `direction`. `coords` is counted as if `piece` was placed there.
+    fn has_line(&self, coords: &Coords, piece: Piece, direction: (i8, i8), length: u8) -> bool {
+        let limit = u16::from(length);
+        1 + self.count_direction(coords, piece, direction, limit)
+            + self.count_direction(coords, piece, (-direction.0, -direction.1), limit)
+            >= limit
+    }
+}
+
+/// Square board stored as rows of tiles: `tiles[y][x]`
+impl<const N: usize> Grid for [[Option<Piece>; N]; N] {
+    fn piece(&self, coords: &Coords) -> Option<Piece> {
+        self.get(coords.y as usize)
+            .and_then(|row| row.get(coords.x as usize))
+            .copied()
+            .flatten()
+    }
+    fn pieces_num(&self) -> u64 {
+        self.iter()
+            .map(|row| row.iter().filter(|tile| tile.is_some()).count() as u64)
+            .sum()
+    }
+}
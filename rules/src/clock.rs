@@ -0,0 +1,97 @@
+use crate::*;
+
+/// Chess-clock time control: each player starts with `initial` time
+/// and gets `increment` back after every move (Fischer increment)
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TimeControl {
+    pub initial: u64,
+    pub increment: u64,
+}
+
+/// Time banks of both players. Only the clock of the player to move runs,
+/// it started at `turn_started_at`. The time unit is picked by the caller.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ChessClock {
+    pub time_control: TimeControl,
+    /// time left of the first and the second player when the current turn started
+    pub remaining: [u64; 2],
+    pub turn_started_at: u64,
+}
+
+impl ChessClock {
+    pub fn new(time_control: TimeControl, now: u64) -> Self {
+        Self {
+            time_control,
+            remaining: [time_control.initial; 2],
+            turn_started_at: now,
+        }
+    }
+
+    /// Time left of `player` at `now` while `to_move` is on turn
+    pub fn time_left(&self, player: usize, to_move: usize, now: u64) -> u64 {
+        if player == to_move {
+            self.remaining[player].saturating_sub(now.saturating_sub(self.turn_started_at))
+        } else {
+            self.remaining[player]
+        }
+    }
+
+    /// Returns true if the player to move ran out of time
+    pub fn is_flagged(&self, to_move: usize, now: u64) -> bool {
+        self.time_left(to_move, to_move, now) == 0
+    }
+
+    /// Stops the clock of `mover` after a move, adds the increment and starts the clock of the
+    /// other player. Returns false if `mover` had already run out of time.
+    pub fn punch(&mut self, mover: usize, now: u64) -> bool {
+        let time_left = self.time_left(mover, mover, now);
+        self.turn_started_at = now;
+        if time_left == 0 {
+            self.remaining[mover] = 0;
+            return false;
+        }
+        self.remaining[mover] = time_left + self.time_control.increment;
+        true
+    }
+
+    /// Starts the turn again at `now` without charging the time spent so far
+    pub fn restart(&mut self, now: u64) {
+        self.turn_started_at = now;
+    }
+
+    /// Swaps the clocks when the players change places
+    pub fn swap_players(&mut self) {
+        self.remaining.swap(0, 1);
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    const BLITZ: TimeControl = TimeControl { initial: 180, increment: 2 };
+
+    #[test]
+    fn punch_adds_increment() {
+        let mut clock = ChessClock::new(BLITZ, 1000);
+        assert_eq!(clock.time_left(0, 0, 1030), 150);
+        // the clock of the waiting player doesn't run
+        assert_eq!(clock.time_left(1, 0, 1030), 180);
+        assert!(clock.punch(0, 1030));
+        assert_eq!(clock.remaining, [152, 180]);
+        assert_eq!(clock.time_left(1, 1, 1100), 110);
+        assert!(clock.punch(1, 1100));
+        assert_eq!(clock.remaining, [152, 112]);
+    }
+
+    #[test]
+    fn flag_falls() {
+        let mut clock = ChessClock::new(BLITZ, 0);
+        assert!(!clock.is_flagged(0, 179));
+        assert!(clock.is_flagged(0, 180));
+        assert!(!clock.punch(0, 200));
+        assert_eq!(clock.remaining, [0, 180]);
+    }
+}
@@ -0,0 +1,94 @@
+use near_sdk::env;
+use near_sdk::json_types::{Base58CryptoHash, U128};
+
+use crate::*;
+
+/// Commit–reveal draw of the first player. Both players commit a hash of a secret
+/// before the game and reveal the secret once it's created, the XOR of the secrets
+/// is random as long as one of the players picked the secret at random.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CoinToss {
+    /// commitments of the first and the second player, see `CoinToss::commitment`
+    pub commitments: [Base58CryptoHash; 2],
+    pub secrets: [Option<U128>; 2],
+    /// secrets revealed after the deadline aren't accepted
+    pub reveal_deadline: u64,
+}
+
+impl CoinToss {
+    pub fn new(commitments: [Base58CryptoHash; 2], reveal_deadline: u64) -> Self {
+        Self {
+            commitments,
+            secrets: [None; 2],
+            reveal_deadline,
+        }
+    }
+
+    /// sha256 of the account id followed by the little-endian bytes of the secret.
+    /// The account id keeps the opponent from copying the commitment and the secret.
+    pub fn commitment(account_id: &AccountId, secret: u128) -> Base58CryptoHash {
+        let mut value = account_id.as_bytes().to_vec();
+        value.extend_from_slice(&secret.to_le_bytes());
+        env::sha256_array(&value).into()
+    }
+
+    /// Stores the secret of `player` after checking it against the commitment
+    pub fn reveal(&mut self, player: usize, account_id: &AccountId, secret: u128) {
+        assert!(self.secrets[player].is_none(), "Secret is already revealed");
+        assert_eq!(
+            Self::commitment(account_id, secret),
+            self.commitments[player],
+            "Secret doesn't match the commitment"
+        );
+        self.secrets[player] = Some(secret.into());
+    }
+
+    /// XOR of both secrets once they are revealed
+    pub fn result(&self) -> Option<u128> {
+        match self.secrets {
+            [Some(secret_1), Some(secret_2)] => Some(secret_1.0 ^ secret_2.0),
+            _ => None,
+        }
+    }
+
+    /// Player who didn't reveal while the opponent did
+    pub fn defaulter(&self) -> Option<usize> {
+        match self.secrets {
+            [Some(_), None] => Some(1),
+            [None, Some(_)] => Some(0),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    fn accounts() -> [AccountId; 2] {
+        ["alice.near".parse().unwrap(), "bob.near".parse().unwrap()]
+    }
+
+    #[test]
+    fn reveal_both() {
+        let [alice, bob] = accounts();
+        let mut coin_toss = CoinToss::new([CoinToss::commitment(&alice, 6), CoinToss::commitment(&bob, 3)], 100);
+        assert_ne!(coin_toss.commitments[0], CoinToss::commitment(&bob, 6));
+        coin_toss.reveal(1, &bob, 3);
+        assert_eq!(coin_toss.result(), None);
+        assert_eq!(coin_toss.defaulter(), Some(0));
+        coin_toss.reveal(0, &alice, 6);
+        assert_eq!(coin_toss.result(), Some(5));
+        assert_eq!(coin_toss.defaulter(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Secret doesn't match the commitment")]
+    fn reveal_wrong_secret() {
+        let [alice, bob] = accounts();
+        let mut coin_toss = CoinToss::new([CoinToss::commitment(&alice, 6), CoinToss::commitment(&bob, 3)], 100);
+        // copied secret of the opponent
+        coin_toss.reveal(0, &alice, 3);
+    }
+}
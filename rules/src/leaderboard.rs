@@ -0,0 +1,133 @@
+use near_sdk::collections::TreeMap;
+use near_sdk::json_types::U128;
+use near_sdk::IntoStorageKey;
+
+use crate::*;
+
+/// Games an account must play before it's ranked by win rate
+pub const MIN_GAMES_FOR_WIN_RATE: u64 = 10;
+
+/// Sorted index of the accounts, the contracts keep it up to date on every stats, rating
+/// or season stats update. `P` is the rating pool of the contract.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Leaderboard<P> {
+    /// won games
+    Victories,
+    /// total reward of the account, in the units of the contract
+    TotalReward,
+    /// won games per played game in basis points, after `MIN_GAMES_FOR_WIN_RATE` games
+    WinRate,
+    /// penalty games, only accounts with a penalty are listed
+    Penalties,
+    /// Glicko-2 rating in the pool, see `rating_score`
+    Rating(P),
+    /// won games in the season with the given id
+    Season(u64),
+}
+
+impl<P> Leaderboard<P> {
+    /// Leaderboards built from the player stats
+    pub const STATS: [Self; 4] = [
+        Leaderboard::Victories,
+        Leaderboard::TotalReward,
+        Leaderboard::WinRate,
+        Leaderboard::Penalties,
+    ];
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LeaderboardEntry {
+    /// 1 for the top account
+    pub rank: u64,
+    pub account_id: AccountId,
+    pub score: U128,
+}
+
+/// Leaderboard score of a Glicko-2 rating
+pub fn rating_score(rating: &Rating) -> u128 {
+    rating.rating.max(0.0).round() as u128
+}
+
+/// Accounts of a leaderboard ordered by `(score, account_id)`, the best one last
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct LeaderboardIndex(TreeMap<(u128, AccountId), ()>);
+
+impl LeaderboardIndex {
+    pub fn new<S: IntoStorageKey>(prefix: S) -> Self {
+        Self(TreeMap::new(prefix))
+    }
+
+    pub fn len(&self) -> u64 {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.len() == 0
+    }
+
+    /// Up to `limit` accounts from the best one, ties are ordered by account id descending.
+    /// `from` is the last entry of the previous page, `None` for the first page, so a page
+    /// only reads its own entries whatever its position.
+    pub fn page(&self, from: Option<LeaderboardEntry>, limit: u64) -> Vec<LeaderboardEntry> {
+        match from {
+            Some(last) => ranked(self.0.iter_rev_from((last.score.0, last.account_id)), last.rank, limit),
+            None => ranked(self.0.iter_rev(), 0, limit),
+        }
+    }
+
+    /// Moves `account_id` from `old_score` to `new_score`, `None` isn't listed
+    pub fn update(&mut self, account_id: &AccountId, old_score: Option<u128>, new_score: Option<u128>) {
+        if let Some(score) = old_score {
+            self.0.remove(&(score, account_id.clone()));
+        }
+        if let Some(score) = new_score {
+            self.0.insert(&(score, account_id.clone()), &());
+        }
+    }
+}
+
+/// Up to `limit` entries of a leaderboard index, ranked after `last_rank`
+fn ranked(entries: impl Iterator<Item = ((u128, AccountId), ())>, last_rank: u64, limit: u64) -> Vec<LeaderboardEntry> {
+    entries
+        .take(limit as usize)
+        .enumerate()
+        .map(|(i, ((score, account_id), _))| LeaderboardEntry {
+            rank: last_rank + i as u64 + 1,
+            account_id,
+            score: score.into(),
+        })
+        .collect()
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    fn account(name: &str) -> AccountId {
+        name.parse().unwrap()
+    }
+
+    #[test]
+    fn pages() {
+        let mut index = LeaderboardIndex::new(b"l".to_vec());
+        index.update(&account("alice.near"), None, Some(3));
+        index.update(&account("bob.near"), None, Some(5));
+        index.update(&account("carol.near"), None, Some(3));
+        index.update(&account("dave.near"), None, Some(1));
+        // bob moves below the tie, dave leaves
+        index.update(&account("bob.near"), Some(5), Some(2));
+        index.update(&account("dave.near"), Some(1), None);
+        assert_eq!(index.len(), 3);
+
+        let first = index.page(None, 2);
+        assert_eq!(first, vec![
+            LeaderboardEntry { rank: 1, account_id: account("carol.near"), score: U128(3) },
+            LeaderboardEntry { rank: 2, account_id: account("alice.near"), score: U128(3) },
+        ]);
+        let second = index.page(first.last().cloned(), 2);
+        assert_eq!(second, vec![LeaderboardEntry { rank: 3, account_id: account("bob.near"), score: U128(2) }]);
+        assert!(index.page(second.last().cloned(), 2).is_empty());
+    }
+}
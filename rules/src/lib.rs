@@ -0,0 +1,31 @@
+//! Game rules shared by the Cheddar tic-tac-toe contracts.
+//!
+//! The crate has no contract state of its own, the leaderboard indexes live in the
+//! storage of the contracts. It builds both for `wasm32` (linked into the contracts)
+//! and for native targets, so off-chain tools can validate moves and
+//! detect results with exactly the same logic the chain uses.
+use near_sdk::borsh::{self, BorshSerialize, BorshDeserialize};
+use near_sdk::serde::{Serialize, Deserialize};
+use near_sdk::AccountId;
+
+mod bitboard;
+mod board;
+mod clock;
+mod coin_toss;
+mod leaderboard;
+mod piece;
+mod rating;
+mod renju;
+mod ruleset;
+mod state;
+
+pub use crate::bitboard::*;
+pub use crate::board::*;
+pub use crate::clock::*;
+pub use crate::coin_toss::*;
+pub use crate::leaderboard::*;
+pub use crate::piece::*;
+pub use crate::rating::*;
+pub use crate::renju::*;
+pub use crate::ruleset::*;
+pub use crate::state::*;
@@ -0,0 +1,53 @@
+use std::fmt;
+
+use crate::*;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Hash, PartialEq, Eq, PartialOrd, Clone, Copy, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Piece {
+    X,
+    O,
+}
+
+impl Piece {
+    pub fn other(self) -> Piece {
+        match self {
+            Piece::X => Piece::O,
+            Piece::O => Piece::X,
+        }
+    }
+    pub fn random() -> Piece {
+        let seed = near_sdk::env::random_seed();
+        match seed[0] % 2 {
+            0 => Piece::X,
+            _ => Piece::O
+        }
+    }
+}
+
+impl fmt::Display for Piece {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Piece::X => write!(f, "X"),
+            Piece::O => write!(f, "O"),
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Hash, PartialEq, Eq, PartialOrd, Clone, Copy)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum Winner {
+    X,
+    O,
+    Tie
+}
+
+impl From<Piece> for Winner {
+    fn from(piece: Piece) -> Self {
+        match piece {
+            Piece::X => Winner::X,
+            Piece::O => Winner::O,
+        }
+    }
+}
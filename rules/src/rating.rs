@@ -0,0 +1,238 @@
+use crate::*;
+
+/// Conversion factor between the Glicko and the Glicko-2 scale
+const GLICKO2_SCALE: f64 = 173.7178;
+/// System constant constraining the change of the volatility
+const TAU: f64 = 0.5;
+/// Convergence tolerance of the volatility iteration
+const EPSILON: f64 = 0.000_001;
+/// Bound of the volatility iteration, it converges in a few steps in practice
+const MAX_ITERATIONS: u32 = 100;
+
+/// Glicko-2 rating of a player, kept on the Glicko scale:
+/// new players start at 1500 with a deviation of 350
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Rating {
+    pub rating: f64,
+    /// uncertainty of `rating`, ~95% confidence is `rating ± 2 * deviation`
+    pub deviation: f64,
+    /// expected fluctuation of `rating`
+    pub volatility: f64,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Self {
+            rating: 1500.0,
+            deviation: 350.0,
+            volatility: 0.06,
+        }
+    }
+}
+
+/// Score of a game: 1 for a win, 0.5 for a tie and 0 for a loss
+pub fn score(won: Option<bool>) -> f64 {
+    match won {
+        Some(true) => 1.0,
+        Some(false) => 0.0,
+        None => 0.5,
+    }
+}
+
+fn g(phi: f64) -> f64 {
+    1.0 / (1.0 + 3.0 * phi * phi / (std::f64::consts::PI * std::f64::consts::PI)).sqrt()
+}
+
+fn expected_score(mu: f64, mu_j: f64, phi_j: f64) -> f64 {
+    1.0 / (1.0 + (-g(phi_j) * (mu - mu_j)).exp())
+}
+
+impl Rating {
+    /// Rating after a rating period with `results`: opponent ratings and the scores
+    /// against them, see `score`. Follows the steps of Glickman's Glicko-2 paper.
+    pub fn update(&self, results: &[(Rating, f64)]) -> Rating {
+        let mu = (self.rating - 1500.0) / GLICKO2_SCALE;
+        let phi = self.deviation / GLICKO2_SCALE;
+        if results.is_empty() {
+            // only the deviation grows
+            let phi_star = (phi * phi + self.volatility * self.volatility).sqrt();
+            return Rating { deviation: phi_star * GLICKO2_SCALE, ..*self };
+        }
+
+        let mut v_inv = 0.0;
+        let mut delta_sum = 0.0;
+        for (opponent, score) in results {
+            let mu_j = (opponent.rating - 1500.0) / GLICKO2_SCALE;
+            let phi_j = opponent.deviation / GLICKO2_SCALE;
+            let e = expected_score(mu, mu_j, phi_j);
+            v_inv += g(phi_j) * g(phi_j) * e * (1.0 - e);
+            delta_sum += g(phi_j) * (score - e);
+        }
+        let v = 1.0 / v_inv;
+        let delta = v * delta_sum;
+
+        let volatility = self.new_volatility(phi, v, delta);
+        let phi_star = (phi * phi + volatility * volatility).sqrt();
+        let new_phi = 1.0 / (1.0 / (phi_star * phi_star) + 1.0 / v).sqrt();
+        let new_mu = mu + new_phi * new_phi * delta_sum;
+        Rating {
+            rating: new_mu * GLICKO2_SCALE + 1500.0,
+            deviation: new_phi * GLICKO2_SCALE,
+            volatility,
+        }
+    }
+
+    /// Step 5 of the paper: the Illinois algorithm finding the new volatility
+    fn new_volatility(&self, phi: f64, v: f64, delta: f64) -> f64 {
+        let a = (self.volatility * self.volatility).ln();
+        let f = |x: f64| {
+            let ex = x.exp();
+            let d = phi * phi + v + ex;
+            ex * (delta * delta - phi * phi - v - ex) / (2.0 * d * d) - (x - a) / (TAU * TAU)
+        };
+
+        let mut big_a = a;
+        let mut big_b = if delta * delta > phi * phi + v {
+            (delta * delta - phi * phi - v).ln()
+        } else {
+            let mut k = 1.0;
+            while f(a - k * TAU) < 0.0 && k < MAX_ITERATIONS as f64 {
+                k += 1.0;
+            }
+            a - k * TAU
+        };
+        let mut f_a = f(big_a);
+        let mut f_b = f(big_b);
+        let mut iterations = 0;
+        while (big_b - big_a).abs() > EPSILON && iterations < MAX_ITERATIONS {
+            let big_c = big_a + (big_a - big_b) * f_a / (f_b - f_a);
+            let f_c = f(big_c);
+            if f_c * f_b <= 0.0 {
+                big_a = big_b;
+                f_a = f_b;
+            } else {
+                f_a /= 2.0;
+            }
+            big_b = big_c;
+            f_b = f_c;
+            iterations += 1;
+        }
+        (big_a / 2.0).exp()
+    }
+}
+
+/// Number of snapshots kept in the rating history of a player, older ones are dropped
+pub const MAX_RATING_HISTORY: usize = 50;
+
+/// Rating of a player after a game, `T` is the timestamp type of the contract
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RatingSnapshot<T> {
+    pub game_id: u64,
+    pub rating: f64,
+    pub deviation: f64,
+    /// timestamp in seconds
+    pub timestamp: T,
+}
+
+/// Rating of a player in a rating pool of a contract, see `RatingSnapshot` for `T`
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Default, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PlayerRating<T> {
+    pub rating: Rating,
+    pub games_rated: u64,
+    /// up to `MAX_RATING_HISTORY` latest snapshots, oldest first
+    pub history: Vec<RatingSnapshot<T>>,
+}
+
+impl<T: Copy> PlayerRating<T> {
+    /// Leaderboard score of the rating, `None` until the first rated game
+    pub fn leaderboard_score(&self) -> Option<u128> {
+        (self.games_rated > 0).then(|| rating_score(&self.rating))
+    }
+
+    /// Updates both ratings after a game against `opponent`, `won` is `None` on a tie
+    pub fn rate_game(&mut self, opponent: &mut Self, won: Option<bool>, game_id: u64, timestamp: T) {
+        let rating = self.rating.update(&[(opponent.rating, score(won))]);
+        let opponent_rating = opponent.rating.update(&[(self.rating, score(won.map(|won| !won)))]);
+        self.record(rating, game_id, timestamp);
+        opponent.record(opponent_rating, game_id, timestamp);
+    }
+
+    fn record(&mut self, rating: Rating, game_id: u64, timestamp: T) {
+        self.rating = rating;
+        self.games_rated += 1;
+        self.history.push(RatingSnapshot {
+            game_id,
+            rating: rating.rating,
+            deviation: rating.deviation,
+            timestamp,
+        });
+        if self.history.len() > MAX_RATING_HISTORY {
+            self.history.remove(0);
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    fn rating(rating: f64, deviation: f64) -> Rating {
+        Rating { rating, deviation, volatility: 0.06 }
+    }
+
+    #[test]
+    fn glickman_example() {
+        // the example of the Glicko-2 paper
+        let player = rating(1500.0, 200.0);
+        let updated = player.update(&[
+            (rating(1400.0, 30.0), 1.0),
+            (rating(1550.0, 100.0), 0.0),
+            (rating(1700.0, 300.0), 0.0),
+        ]);
+        assert!((updated.rating - 1464.06).abs() < 0.01, "{:?}", updated);
+        assert!((updated.deviation - 151.52).abs() < 0.01, "{:?}", updated);
+        assert!((updated.volatility - 0.05999).abs() < 0.00001, "{:?}", updated);
+    }
+
+    #[test]
+    fn single_games() {
+        let player = Rating::default();
+        let won = player.update(&[(Rating::default(), score(Some(true)))]);
+        let lost = player.update(&[(Rating::default(), score(Some(false)))]);
+        let tied = player.update(&[(Rating::default(), score(None))]);
+        assert!(won.rating > 1500.0 && lost.rating < 1500.0);
+        assert!((won.rating - 1500.0 - (1500.0 - lost.rating)).abs() < 0.000_001);
+        assert!((tied.rating - 1500.0).abs() < 0.000_001);
+        assert!(tied.deviation < player.deviation);
+        // an upset moves the rating further than an expected win
+        let upset = rating(1400.0, 100.0).update(&[(rating(1700.0, 100.0), 1.0)]);
+        let expected = rating(1700.0, 100.0).update(&[(rating(1400.0, 100.0), 1.0)]);
+        assert!(upset.rating - 1400.0 > expected.rating - 1700.0);
+    }
+
+    #[test]
+    fn rated_games() {
+        let mut player = PlayerRating::<u32>::default();
+        let mut opponent = PlayerRating::default();
+        assert_eq!(player.leaderboard_score(), None);
+        player.rate_game(&mut opponent, Some(true), 0, 10);
+        assert_eq!(player.rating, Rating::default().update(&[(Rating::default(), 1.0)]));
+        assert_eq!(opponent.rating, Rating::default().update(&[(Rating::default(), 0.0)]));
+        assert_eq!(player.leaderboard_score(), Some(rating_score(&player.rating)));
+        assert_eq!(opponent.history[0], RatingSnapshot {
+            game_id: 0,
+            rating: opponent.rating.rating,
+            deviation: opponent.rating.deviation,
+            timestamp: 10,
+        });
+        for game_id in 1..=MAX_RATING_HISTORY as u64 {
+            player.rate_game(&mut opponent, None, game_id, 20);
+        }
+        // only the latest snapshots are kept
+        assert_eq!((player.games_rated, player.history.len()), (MAX_RATING_HISTORY as u64 + 1, MAX_RATING_HISTORY));
+        assert_eq!(player.history[0].game_id, 1);
+    }
+}
@@ -0,0 +1,224 @@
+use crate::*;
+
+/// Set of restrictions played on top of the board size and win length
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Variant {
+    /// any move on an empty tile is allowed
+    #[default]
+    Freestyle,
+    /// O (the first player) can't make double-three, double-four or overline moves
+    Renju,
+}
+
+/// Moves the first player can't make in Renju
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ForbiddenMove {
+    /// the move creates two open threes at once
+    DoubleThree,
+    /// the move creates two fours at once
+    DoubleFour,
+    /// the move creates a line longer than the win length
+    Overline,
+}
+
+/// Tile along a line as seen by the player making the move
+#[derive(Clone, Copy, PartialEq)]
+enum Cell {
+    Own,
+    Empty,
+    Blocked,
+}
+
+/// Tiles of a single line through the analysed move
+struct Line {
+    cells: Vec<Cell>,
+    center: usize,
+    win_length: usize,
+}
+
+impl Line {
+    fn new(rules: &(impl Ruleset + ?Sized), grid: &dyn Grid, coords: &Coords, piece: Piece, direction: (i8, i8)) -> Line {
+        let win_length = usize::from(rules.win_length());
+        let radius = win_length as i16 + 2;
+        let cells = (-radius..=radius)
+            .map(|step| {
+                if step == 0 {
+                    return Cell::Own;
+                }
+                let x = i16::from(coords.x) + step * i16::from(direction.0);
+                let y = i16::from(coords.y) + step * i16::from(direction.1);
+                if x < 0 || y < 0 {
+                    return Cell::Blocked;
+                }
+                let tile = Coords { x: x as u8, y: y as u8 };
+                if !rules.contains(&tile) {
+                    return Cell::Blocked;
+                }
+                match grid.piece(&tile) {
+                    None => Cell::Empty,
+                    Some(other) if other == piece => Cell::Own,
+                    Some(_) => Cell::Blocked,
+                }
+            })
+            .collect();
+        Line { cells, center: radius as usize, win_length }
+    }
+
+    /// Tile `i` with extra own stones `placed` on the line
+    fn cell(&self, i: usize, placed: &[usize]) -> Cell {
+        if placed.contains(&i) {
+            Cell::Own
+        } else {
+            self.cells.get(i).copied().unwrap_or(Cell::Blocked)
+        }
+    }
+
+    /// First and last index of the run of own stones through the center
+    fn run(&self, placed: &[usize]) -> (usize, usize) {
+        let mut start = self.center;
+        while start > 0 && self.cell(start - 1, placed) == Cell::Own {
+            start -= 1;
+        }
+        let mut end = self.center;
+        while self.cell(end + 1, placed) == Cell::Own {
+            end += 1;
+        }
+        (start, end)
+    }
+
+    fn length(&self, placed: &[usize]) -> usize {
+        let (start, end) = self.run(placed);
+        end - start + 1
+    }
+
+    /// Returns true if stones `placed` make exactly `win_length` in a row with the center
+    fn completes_five(&self, placed: &[usize]) -> bool {
+        let (start, end) = self.run(placed);
+        end - start + 1 == self.win_length && placed.iter().all(|i| start <= *i && *i <= end)
+    }
+
+    fn empty_tiles(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.cells.len()).filter(move |i| self.cells[*i] == Cell::Empty)
+    }
+
+    /// A four: one more stone on the line makes exactly `win_length` in a row
+    fn is_four(&self) -> bool {
+        self.empty_tiles().any(|i| self.completes_five(&[i]))
+    }
+
+    /// An open three: one more stone on the line makes a straight four,
+    /// which can be completed on both ends
+    fn is_open_three(&self) -> bool {
+        self.empty_tiles().any(|i| {
+            let (start, end) = self.run(&[i]);
+            end - start + 2 == self.win_length
+                && start <= i
+                && i <= end
+                && start > 0
+                && self.cell(start - 1, &[]) == Cell::Empty
+                && self.cell(end + 1, &[]) == Cell::Empty
+                && self.completes_five(&[i, start - 1])
+                && self.completes_five(&[i, end + 1])
+        })
+    }
+}
+
+/// Renju restrictions for `piece` placed on `coords`. A move making exactly
+/// `win_length` in a row is always allowed, it wins the game.
+pub fn forbidden_move(
+    rules: &(impl Ruleset + ?Sized),
+    grid: &dyn Grid,
+    coords: &Coords,
+    piece: Piece,
+) -> Option<ForbiddenMove> {
+    let lines: Vec<Line> = DIRECTIONS
+        .iter()
+        .map(|direction| Line::new(rules, grid, coords, piece, *direction))
+        .collect();
+    if lines.iter().any(|line| line.length(&[]) == line.win_length) {
+        return None;
+    }
+    if lines.iter().any(|line| line.length(&[]) > line.win_length) {
+        return Some(ForbiddenMove::Overline);
+    }
+    let fours = lines.iter().filter(|line| line.is_four()).count();
+    if fours >= 2 {
+        return Some(ForbiddenMove::DoubleFour);
+    }
+    let threes = lines
+        .iter()
+        .filter(|line| !line.is_four() && line.is_open_three())
+        .count();
+    if threes >= 2 {
+        return Some(ForbiddenMove::DoubleThree);
+    }
+    None
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    const RULES: MnkRules = MnkRules::new(15, 15, 5);
+
+    fn board(stones: &[(u8, u8)]) -> BitBoard {
+        let mut board = BitBoard::default();
+        for (x, y) in stones {
+            board.insert(&Coords { x: *x, y: *y }, Piece::O);
+        }
+        board
+    }
+
+    fn check(board: &BitBoard, x: u8, y: u8) -> Option<ForbiddenMove> {
+        forbidden_move(&RULES, board, &Coords { x, y }, Piece::O)
+    }
+
+    #[test]
+    fn double_three() {
+        // O _ _ _ _
+        // _ O _ _ _
+        // _ _ ? O O
+        // (7, 7) makes an open three horizontally and diagonally
+        let mut b = board(&[(8, 7), (9, 7), (6, 6), (5, 5)]);
+        assert_eq!(check(&b, 7, 7), Some(ForbiddenMove::DoubleThree));
+        // a blocked three doesn't count
+        b.insert(&Coords { x: 10, y: 7 }, Piece::X);
+        assert_eq!(check(&b, 7, 7), None);
+    }
+
+    #[test]
+    fn double_four() {
+        // horizontal O O O ? and vertical O O O ?
+        let b = board(&[(4, 7), (5, 7), (6, 7), (7, 4), (7, 5), (7, 6)]);
+        assert_eq!(check(&b, 7, 7), Some(ForbiddenMove::DoubleFour));
+    }
+
+    #[test]
+    fn four_and_three_is_allowed() {
+        let b = board(&[(4, 7), (5, 7), (6, 7), (7, 5), (7, 6)]);
+        assert_eq!(check(&b, 7, 7), None);
+    }
+
+    #[test]
+    fn overline() {
+        // O O O ? O O
+        let b = board(&[(1, 3), (2, 3), (3, 3), (5, 3), (6, 3)]);
+        assert_eq!(check(&b, 4, 3), Some(ForbiddenMove::Overline));
+    }
+
+    #[test]
+    fn five_wins_over_restrictions() {
+        // exactly five horizontally, double three elsewhere doesn't matter
+        let b = board(&[(3, 7), (4, 7), (5, 7), (6, 7), (7, 5), (7, 6), (6, 6), (5, 5)]);
+        assert_eq!(check(&b, 7, 7), None);
+    }
+
+    #[test]
+    fn three_on_the_edge_is_not_open() {
+        // |O O ? _ _ and an open three on the anti-diagonal
+        let b = board(&[(0, 7), (1, 7), (3, 6), (4, 5)]);
+        assert_eq!(check(&b, 2, 7), None);
+    }
+}
@@ -0,0 +1,356 @@
+use crate::*;
+
+/// Line directions checked for a winner: horizontal, vertical,
+/// diagonal (NW - SE) and diagonal (NE - SW)
+pub const DIRECTIONS: [(i8, i8); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+
+/// Rules of a game played by placing pieces on a rectangular board.
+/// Only the board dimensions and the win length are required,
+/// move validation and result detection are provided on top of them.
+pub trait Ruleset {
+    /// number of columns
+    fn width(&self) -> u8;
+    /// number of rows
+    fn height(&self) -> u8;
+    /// number of pieces in a row needed to win
+    fn win_length(&self) -> u8;
+
+    /// If false, only exactly `win_length` pieces in a row win
+    fn overline_wins(&self) -> bool {
+        true
+    }
+
+    /// If true, completing a line loses the game
+    fn misere(&self) -> bool {
+        false
+    }
+
+    /// If true, pieces fall to the lowest empty row of their column
+    fn gravity(&self) -> bool {
+        false
+    }
+
+    fn tiles_num(&self) -> u64 {
+        u64::from(self.width()) * u64::from(self.height())
+    }
+
+    fn contains(&self, coords: &Coords) -> bool {
+        coords.x < self.width() && coords.y < self.height()
+    }
+
+    /// Checks if a piece can be placed on `coords`
+    fn check_move(&self, grid: &dyn Grid, coords: &Coords) -> Result<(), MoveError> {
+        if !self.contains(coords) {
+            return Err(MoveError::InvalidPosition {
+                row: coords.y,
+                col: coords.x,
+            });
+        }
+        // In gravity mode only the lowest empty tile of a column can be taken
+        let landing = if self.gravity() {
+            Some(self.drop_coords(grid, coords.x)?)
+        } else {
+            None
+        };
+        // Move in already filled tile
+        if let Some(other_piece) = grid.piece(coords) {
+            return Err(MoveError::TileFilled {
+                other_piece,
+                row: coords.y,
+                col: coords.x,
+            });
+        }
+        if matches!(&landing, Some(landing) if landing.y != coords.y) {
+            return Err(MoveError::InvalidPosition {
+                row: coords.y,
+                col: coords.x,
+            });
+        }
+        Ok(())
+    }
+
+    /// Gravity mode: the tile a piece dropped into column `col` lands on,
+    /// rows are filled from the bottom (`height - 1`) up
+    fn drop_coords(&self, grid: &dyn Grid, col: u8) -> Result<Coords, MoveError> {
+        if col >= self.width() {
+            return Err(MoveError::InvalidPosition { row: 0, col });
+        }
+        (0..self.height())
+            .rev()
+            .map(|y| Coords { x: col, y })
+            .find(|coords| grid.piece(coords).is_none())
+            .ok_or(MoveError::ColumnFull { col })
+    }
+
+    /// Length of the line of `piece`s going through `coords` along `direction`,
+    /// including `coords` itself. Each side is counted up to `win_length` pieces.
+    fn line_length(&self, grid: &dyn Grid, coords: &Coords, piece: Piece, direction: (i8, i8)) -> u16 {
+        let limit = u16::from(self.win_length());
+        1 + grid.count_direction(coords, piece, direction, limit)
+            + grid.count_direction(coords, piece, (-direction.0, -direction.1), limit)
+    }
+
+    /// Returns true if `piece` placed on `coords` completes a winning line.
+    /// To find a potential winner, we only need to check the lines
+    /// that the last move was made in.
+    fn is_winning_move(&self, grid: &dyn Grid, coords: &Coords, piece: Piece) -> bool {
+        DIRECTIONS.iter().any(|direction| {
+            grid.has_line(coords, piece, *direction, self.win_length())
+                && (self.overline_wins() || !grid.has_line(coords, piece, *direction, self.win_length() + 1))
+        })
+    }
+
+    /// Result of the game after `piece` was placed on `coords`:
+    /// the winner, `Winner::Tie` when the board is full or `None` when the game goes on.
+    /// In misère the opponent of the player completing a line wins.
+    fn game_result(&self, grid: &dyn Grid, coords: &Coords, piece: Piece) -> Option<Winner> {
+        if self.is_winning_move(grid, coords, piece) {
+            if self.misere() {
+                Some(piece.other().into())
+            } else {
+                Some(piece.into())
+            }
+        } else if grid.pieces_num() >= self.tiles_num() {
+            Some(Winner::Tie)
+        } else {
+            None
+        }
+    }
+
+    /// Rule that decided `winner` returned by `game_result`
+    fn result_reason(&self, winner: Winner) -> ResultReason {
+        match winner {
+            Winner::Tie => ResultReason::NoMovesLeft,
+            _ if self.misere() => ResultReason::MisereLine,
+            _ => ResultReason::Line,
+        }
+    }
+}
+
+/// m,n,k-game: players take turns placing pieces on a `width` x `height` board,
+/// the first one to get `win_length` pieces in a row wins
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MnkRules {
+    pub width: u8,
+    pub height: u8,
+    pub win_length: u8,
+    /// false for the exact-five rule: longer lines don't win
+    pub overline_wins: bool,
+    /// true if completing a line loses
+    pub misere: bool,
+    /// true if pieces fall to the lowest empty row, as in Connect Four
+    pub gravity: bool,
+}
+
+impl MnkRules {
+    /// classic 3x3 tic-tac-toe
+    pub const TIC_TAC_TOE: MnkRules = MnkRules::new(3, 3, 3);
+    /// five-in-a-row on a 25x25 board
+    pub const FIVE_IN_A_ROW: MnkRules = MnkRules::new(25, 25, 5);
+    /// Connect Four: pieces are dropped into 7 columns of 6 rows
+    pub const CONNECT_FOUR: MnkRules = MnkRules::new(7, 6, 4).with_gravity(true);
+
+    pub const fn new(width: u8, height: u8, win_length: u8) -> Self {
+        Self { width, height, win_length, overline_wins: true, misere: false, gravity: false }
+    }
+
+    pub const fn with_overline_wins(self, overline_wins: bool) -> Self {
+        Self { overline_wins, ..self }
+    }
+
+    pub const fn with_misere(self, misere: bool) -> Self {
+        Self { misere, ..self }
+    }
+
+    pub const fn with_gravity(self, gravity: bool) -> Self {
+        Self { gravity, ..self }
+    }
+}
+
+impl Ruleset for MnkRules {
+    fn width(&self) -> u8 {
+        self.width
+    }
+    fn height(&self) -> u8 {
+        self.height
+    }
+    fn win_length(&self) -> u8 {
+        self.win_length
+    }
+    fn overline_wins(&self) -> bool {
+        self.overline_wins
+    }
+    fn misere(&self) -> bool {
+        self.misere
+    }
+    fn gravity(&self) -> bool {
+        self.gravity
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    type Board3 = [[Option<Piece>; 3]; 3];
+    type Board25 = [[Option<Piece>; 25]; 25];
+
+    fn place(board: &mut Board25, piece: Piece, coords: &[(u8, u8)]) {
+        for (x, y) in coords {
+            board[*y as usize][*x as usize] = Some(piece);
+        }
+    }
+
+    #[test]
+    fn check_move() {
+        let rules = MnkRules::TIC_TAC_TOE;
+        let mut board: Board3 = Default::default();
+        board[1][2] = Some(Piece::O);
+        assert_eq!(rules.check_move(&board, &Coords { x: 0, y: 0 }), Ok(()));
+        assert_eq!(
+            rules.check_move(&board, &Coords { x: 3, y: 0 }),
+            Err(MoveError::InvalidPosition { row: 0, col: 3 })
+        );
+        assert_eq!(
+            rules.check_move(&board, &Coords { x: 2, y: 1 }),
+            Err(MoveError::TileFilled { other_piece: Piece::O, row: 1, col: 2 })
+        );
+    }
+
+    #[test]
+    fn tic_tac_toe_results() {
+        let rules = MnkRules::TIC_TAC_TOE;
+        // X X X
+        // O O _
+        // _ _ _
+        let mut board: Board3 = Default::default();
+        board[0] = [Some(Piece::X), Some(Piece::X), Some(Piece::X)];
+        board[1][0] = Some(Piece::O);
+        board[1][1] = Some(Piece::O);
+        assert_eq!(rules.game_result(&board, &Coords { x: 1, y: 0 }, Piece::X), Some(Winner::X));
+        assert_eq!(rules.game_result(&board, &Coords { x: 1, y: 1 }, Piece::O), None);
+
+        // X O X
+        // X O O
+        // O X X
+        let board: Board3 = [
+            [Some(Piece::X), Some(Piece::O), Some(Piece::X)],
+            [Some(Piece::X), Some(Piece::O), Some(Piece::O)],
+            [Some(Piece::O), Some(Piece::X), Some(Piece::X)],
+        ];
+        assert_eq!(rules.game_result(&board, &Coords { x: 2, y: 2 }, Piece::X), Some(Winner::Tie));
+    }
+
+    #[test]
+    fn winner_in_every_direction() {
+        let rules = MnkRules::FIVE_IN_A_ROW;
+        let lines: [[(u8, u8); 4]; 4] = [
+            [(20, 20), (21, 20), (23, 20), (24, 20)],
+            [(0, 0), (0, 1), (0, 2), (0, 3)],
+            [(20, 20), (21, 21), (23, 23), (24, 24)],
+            [(24, 0), (23, 1), (21, 3), (20, 4)],
+        ];
+        let last_moves = [(22, 20), (0, 4), (22, 22), (22, 2)];
+        for (line, (x, y)) in lines.iter().zip(last_moves.iter()) {
+            let mut board: Board25 = [[None; 25]; 25];
+            place(&mut board, Piece::X, line);
+            let coords = Coords { x: *x, y: *y };
+            assert!(rules.is_winning_move(&board, &coords, Piece::X), "no winner at {:?}", coords);
+            assert!(!rules.is_winning_move(&board, &coords, Piece::O));
+        }
+    }
+
+    #[test]
+    fn broken_line_is_not_a_win() {
+        let rules = MnkRules::FIVE_IN_A_ROW;
+        // X X O _ X X
+        let mut board: Board25 = [[None; 25]; 25];
+        place(&mut board, Piece::X, &[(0, 5), (1, 5), (4, 5), (5, 5)]);
+        place(&mut board, Piece::O, &[(2, 5)]);
+        assert!(!rules.is_winning_move(&board, &Coords { x: 3, y: 5 }, Piece::X));
+        assert_eq!(rules.line_length(&board, &Coords { x: 3, y: 5 }, Piece::X, (1, 0)), 3);
+    }
+
+    #[test]
+    fn exact_five_in_every_direction() {
+        let rules = MnkRules::FIVE_IN_A_ROW.with_overline_wins(false);
+        // (start, direction) of lines of 6 pieces, the 3rd piece is placed last
+        let lines = [((5, 5), (1, 0)), ((5, 5), (0, 1)), ((5, 5), (1, 1)), ((15, 5), (-1, 1))];
+        for ((x, y), (dx, dy)) in lines.iter() {
+            let tile = |i: i16| (((*x as i16) + i * (*dx as i16)) as u8, ((*y as i16) + i * (*dy as i16)) as u8);
+            let (last_x, last_y) = tile(2);
+            let coords = Coords { x: last_x, y: last_y };
+
+            // 5 in a row wins
+            let mut board: Board25 = [[None; 25]; 25];
+            place(&mut board, Piece::X, &[tile(0), tile(1), tile(3), tile(4)]);
+            assert!(rules.is_winning_move(&board, &coords, Piece::X), "no winner at {:?}", coords);
+            // 6 in a row doesn't
+            place(&mut board, Piece::X, &[tile(5)]);
+            assert!(!rules.is_winning_move(&board, &coords, Piece::X), "overline wins at {:?}", coords);
+            assert!(MnkRules::FIVE_IN_A_ROW.is_winning_move(&board, &coords, Piece::X));
+        }
+    }
+
+    #[test]
+    fn exact_five_on_board_edge() {
+        let rules = MnkRules::FIVE_IN_A_ROW.with_overline_wins(false);
+        // the board edge ends each line, pieces on the other
+        // side of the board must not make it look longer
+        let lines: [[(u8, u8); 5]; 4] = [
+            [(20, 0), (21, 0), (22, 0), (23, 0), (0, 1)],
+            [(24, 20), (24, 21), (24, 22), (24, 23), (24, 0)],
+            [(20, 20), (21, 21), (22, 22), (23, 23), (0, 0)],
+            [(4, 20), (3, 21), (2, 22), (1, 23), (24, 23)],
+        ];
+        let last_moves = [(24, 0), (24, 24), (24, 24), (0, 24)];
+        for (line, (x, y)) in lines.iter().zip(last_moves.iter()) {
+            let mut board = BitBoard::default();
+            let mut tiles: Board25 = [[None; 25]; 25];
+            for (px, py) in line.iter() {
+                board.insert(&Coords { x: *px, y: *py }, Piece::X);
+            }
+            place(&mut tiles, Piece::X, line);
+            let coords = Coords { x: *x, y: *y };
+            assert!(rules.is_winning_move(&board, &coords, Piece::X), "no winner at {:?}", coords);
+            assert!(rules.is_winning_move(&tiles, &coords, Piece::X), "no winner at {:?}", coords);
+        }
+    }
+
+    #[test]
+    fn misere_line_loses() {
+        let rules = MnkRules::TIC_TAC_TOE.with_misere(true);
+        let mut board: Board3 = Default::default();
+        board[0] = [Some(Piece::X), Some(Piece::X), Some(Piece::X)];
+        let coords = Coords { x: 2, y: 0 };
+        let winner = rules.game_result(&board, &coords, Piece::X);
+        assert_eq!(winner, Some(Winner::O));
+        assert_eq!(rules.result_reason(Winner::O), ResultReason::MisereLine);
+        assert_eq!(MnkRules::TIC_TAC_TOE.result_reason(Winner::X), ResultReason::Line);
+        assert_eq!(rules.result_reason(Winner::Tie), ResultReason::NoMovesLeft);
+    }
+
+    #[test]
+    fn gravity_drop() {
+        let rules = MnkRules::CONNECT_FOUR;
+        let mut board = BitBoard::default();
+        assert_eq!(rules.drop_coords(&board, 3), Ok(Coords { x: 3, y: 5 }));
+        assert_eq!(rules.drop_coords(&board, 7), Err(MoveError::InvalidPosition { row: 0, col: 7 }));
+        // only the lowest empty tile can be taken
+        assert_eq!(
+            rules.check_move(&board, &Coords { x: 3, y: 4 }),
+            Err(MoveError::InvalidPosition { row: 4, col: 3 })
+        );
+        for y in (0..6).rev() {
+            let coords = rules.drop_coords(&board, 3).unwrap();
+            assert_eq!(coords, Coords { x: 3, y });
+            assert_eq!(rules.check_move(&board, &coords), Ok(()));
+            board.insert(&coords, Piece::X);
+        }
+        assert_eq!(rules.drop_coords(&board, 3), Err(MoveError::ColumnFull { col: 3 }));
+        assert_eq!(rules.check_move(&board, &Coords { x: 3, y: 0 }), Err(MoveError::ColumnFull { col: 3 }));
+        // four stacked pieces win
+        assert!(rules.is_winning_move(&board, &Coords { x: 3, y: 2 }, Piece::X));
+    }
+}
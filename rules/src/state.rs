@@ -0,0 +1,34 @@
+use crate::*;
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum GameState {
+    NotStarted,
+    Active,
+    Finished
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum GameResult {
+    Win(AccountId),
+    Tie
+}
+
+/// Rule that decided a `GameResult`
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ResultReason {
+    /// the winner completed a line
+    Line,
+    /// misère: the loser completed a line
+    MisereLine,
+    /// tie, no moves are left
+    NoMovesLeft,
+    /// the loser gave up
+    GiveUp,
+    /// the loser ran out of time
+    Timeout,
+    /// tie, both players agreed to a draw
+    DrawByAgreement,
+}